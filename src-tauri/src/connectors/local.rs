@@ -6,9 +6,8 @@ use std::fmt;
 use std::io::{Read, Write};
 use std::sync::Arc;
 use std::thread;
-use std::sync::mpsc;
 
-use crate::session::{OutputMessage, Session};
+use crate::session::{OutputMessage, OutputSender, Session};
 
 /// Local PTY session
 pub struct LocalSession {
@@ -93,7 +92,7 @@ fn which_exists(cmd: &str) -> bool {
 /// Creates a new local PTY session
 pub fn create_local_session(
     session_id: String,
-    output_tx: mpsc::Sender<OutputMessage>,
+    output_tx: OutputSender,
     on_exit: impl FnOnce() + Send + 'static,
 ) -> Result<LocalSession, String> {
     let pty_system = native_pty_system();
@@ -127,6 +126,9 @@ pub fn create_local_session(
     thread::spawn(move || {
         let mut buf = [0u8; 4096];
         loop {
+            // Stop reading while the output isn't drained: the shell then
+            // blocks on a full PTY
+            output_tx.wait_ready(&session_id_clone);
             match reader.read(&mut buf) {
                 Ok(0) => break,
                 Ok(n) => {
@@ -1,16 +1,23 @@
-//! Available connectors (SSH, Local, Telnet, Serial)
+//! Available connectors (SSH, Local, Telnet, Serial, tmux)
 
 pub mod local;
 pub mod ssh;
 pub mod ssh_exec;
 pub mod sftp;
+pub mod sftp_search;
+pub mod scp;
+pub mod sudo_file;
 pub mod known_hosts;
 pub mod telnet;
 pub mod serial;
+pub mod tmux;
+pub mod tmux_control;
 
 pub use local::create_local_session;
 pub use ssh::{connect_ssh, finalize_cached_ssh, drop_cached_session, check_host_key_only, load_ssh_key, SshAuth, SshConfig, HostKeyCheckResult, SshConnectionResult};
 pub use known_hosts::{accept_pending_key, accept_and_update_pending_key, remove_pending_key, lookup_fingerprints};
-pub use sftp::{sftp_list_dir, sftp_read_file, sftp_write_file, sftp_delete, sftp_rename, sftp_mkdir, sftp_upload_file, FileEntry, SftpPool, new_sftp_pool, disconnect_sftp};
+pub use sftp::{sftp_list_dir, sftp_read_file, sftp_write_file, sftp_delete, sftp_rename, sftp_mkdir, FileEntry, SftpPool, new_sftp_pool, disconnect_sftp};
+pub use sftp::{sftp_stat, sftp_chmod, sftp_chown, sftp_utime, sftp_symlink, sftp_readlink, FileStat};
 pub use telnet::connect_telnet;
 pub use serial::{connect_serial, list_serial_ports, SerialConfig, SerialPortInfo};
+pub use tmux::{attach_tmux, reattach_tmux, detach_tmux, TmuxEvent, TmuxLayout};
@@ -0,0 +1,530 @@
+//! SCP fallback for servers without the SFTP subsystem
+//!
+//! Embedded devices and hardened hosts often disable `sftp` while still
+//! allowing exec channels. Files are then transferred with the SCP protocol
+//! (`scp -t` / `scp -f` on the server) and directories are listed with `stat`.
+//! Each operation uses its own connection, like `ssh_exec`.
+//!
+//! Sessions fall back automatically: `sftp::uses_scp` marks a session here the
+//! first time its SFTP connection fails with `SFTP_UNAVAILABLE`.
+
+use parking_lot::Mutex as SyncMutex;
+use std::collections::HashSet;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::sftp::{format_mode, join_remote_path, FileEntry, FileStat};
+use super::ssh_exec::{exec_channel, shell_quote, ssh_exec};
+use super::SshConfig;
+use crate::transfers::control::TransferControl;
+use crate::transfers::progress::ProgressTracker;
+use crate::transfers::stream::copy_with_progress;
+
+/// Printed after a shell command succeeded (ssh_exec doesn't return exit codes)
+const SUCCESS_MARKER: &str = "__SIMPLYTERM_OK__";
+
+/// File type bits of `st_mode`
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+lazy_static::lazy_static! {
+    /// Sessions whose server has no SFTP subsystem
+    static ref SCP_SESSIONS: SyncMutex<HashSet<String>> = SyncMutex::new(HashSet::new());
+}
+
+/// Whether a session was found to need SCP
+pub fn is_scp_session(session_id: &str) -> bool {
+    SCP_SESSIONS.lock().contains(session_id)
+}
+
+pub(crate) fn mark_scp_session(session_id: &str) {
+    SCP_SESSIONS.lock().insert(session_id.to_string());
+}
+
+/// Forget the fallback (the next connection probes SFTP again)
+pub(crate) fn clear_scp_session(session_id: &str) {
+    SCP_SESSIONS.lock().remove(session_id);
+}
+
+// ============================================================================
+// Protocol helpers
+// ============================================================================
+
+async fn read_line<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String, String> {
+    let mut line = Vec::new();
+    loop {
+        let byte = stream
+            .read_u8()
+            .await
+            .map_err(|_| "SCP connection closed unexpectedly".to_string())?;
+        if byte == b'\n' {
+            break;
+        }
+        line.push(byte);
+    }
+    Ok(String::from_utf8_lossy(&line).to_string())
+}
+
+/// Read a response byte: 0 = ok, 1/2 = warning/error followed by a message
+async fn read_ack<S: AsyncRead + Unpin>(stream: &mut S) -> Result<(), String> {
+    let code = stream
+        .read_u8()
+        .await
+        .map_err(|_| "SCP connection closed unexpectedly (is scp installed on the server?)".to_string())?;
+    match code {
+        0 => Ok(()),
+        1 | 2 => Err(format!("SCP error: {}", read_line(stream).await?.trim())),
+        other => Err(format!("Unexpected SCP response byte: {}", other)),
+    }
+}
+
+async fn send<S: AsyncWrite + Unpin>(stream: &mut S, data: &[u8]) -> Result<(), String> {
+    stream.write_all(data).await.map_err(|e| format!("Failed to send SCP data: {}", e))?;
+    stream.flush().await.map_err(|e| format!("Failed to send SCP data: {}", e))
+}
+
+/// Parse a `C<mode> <size> <name>` file header
+fn parse_file_header(line: &str) -> Result<(u32, u64), String> {
+    if line.starts_with('D') {
+        return Err("Not a regular file".to_string());
+    }
+    let rest = line
+        .strip_prefix('C')
+        .ok_or_else(|| format!("Unexpected SCP header: {}", line))?;
+    let mut parts = rest.splitn(3, ' ');
+    let mode = parts
+        .next()
+        .and_then(|m| u32::from_str_radix(m, 8).ok())
+        .ok_or_else(|| format!("Invalid SCP header: {}", line))?;
+    let size = parts
+        .next()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| format!("Invalid SCP header: {}", line))?;
+    Ok((mode, size))
+}
+
+fn file_name(remote_path: &str) -> &str {
+    remote_path.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or("file")
+}
+
+// ============================================================================
+// Transfers
+// ============================================================================
+
+/// Send `size` bytes from `reader` to `remote_path` (created or truncated).
+/// SCP can't resume: the whole file is always sent.
+pub async fn scp_upload<R, F>(
+    config: &SshConfig,
+    remote_path: &str,
+    reader: &mut R,
+    size: u64,
+    mode: u32,
+    control: &TransferControl,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(&ProgressTracker),
+{
+    let command = format!("scp -t {}", shell_quote(remote_path));
+    let (_session, channel) = exec_channel(config, &command).await?;
+    let mut stream = channel.into_stream();
+
+    read_ack(&mut stream).await?;
+    let header = format!("C{:04o} {} {}\n", mode & 0o7777, size, file_name(remote_path));
+    send(&mut stream, header.as_bytes()).await?;
+    read_ack(&mut stream).await?;
+
+    let mut tracker = ProgressTracker::new(size);
+    let mut limited = reader.take(size);
+    copy_with_progress(&mut limited, &mut stream, &mut tracker, control, &mut on_progress).await?;
+    if tracker.transferred() != size {
+        return Err("Local file changed size during the upload".to_string());
+    }
+
+    send(&mut stream, &[0]).await?;
+    read_ack(&mut stream).await?;
+    let _ = stream.shutdown().await;
+    Ok(size)
+}
+
+/// Receive `remote_path` into `writer`. Returns the file size.
+pub async fn scp_download<W, F>(
+    config: &SshConfig,
+    remote_path: &str,
+    writer: &mut W,
+    control: &TransferControl,
+    on_progress: F,
+) -> Result<u64, String>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(&ProgressTracker),
+{
+    let command = format!("scp -f {}", shell_quote(remote_path));
+    let (_session, channel) = exec_channel(config, &command).await?;
+    let mut stream = channel.into_stream();
+
+    let size = receive_file(&mut stream, writer, control, on_progress).await?;
+    let _ = stream.shutdown().await;
+    Ok(size)
+}
+
+/// Sink side of the protocol (`scp -f` on the other end) for a single file
+async fn receive_file<S, W, F>(
+    stream: &mut S,
+    writer: &mut W,
+    control: &TransferControl,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&ProgressTracker),
+{
+    send(stream, &[0]).await?;
+    let header = match stream
+        .read_u8()
+        .await
+        .map_err(|_| "SCP connection closed unexpectedly (is scp installed on the server?)".to_string())?
+    {
+        1 | 2 => return Err(format!("SCP error: {}", read_line(stream).await?.trim())),
+        first => format!("{}{}", first as char, read_line(stream).await?),
+    };
+    let (_, size) = parse_file_header(&header)?;
+    send(stream, &[0]).await?;
+
+    let mut tracker = ProgressTracker::new(size);
+    let mut limited = (&mut *stream).take(size);
+    copy_with_progress(&mut limited, writer, &mut tracker, control, &mut on_progress).await?;
+    if tracker.transferred() != size {
+        return Err("SCP connection closed before the end of the file".to_string());
+    }
+
+    read_ack(stream).await?;
+    send(stream, &[0]).await?;
+    Ok(size)
+}
+
+/// Read a whole remote file in memory
+pub async fn scp_read_file(config: &SshConfig, remote_path: &str) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    scp_download(config, remote_path, &mut data, &TransferControl::new(), |_| {}).await?;
+    Ok(data)
+}
+
+/// Write a whole remote file from memory
+pub async fn scp_write_file(config: &SshConfig, remote_path: &str, data: &[u8]) -> Result<(), String> {
+    let mut reader = data;
+    scp_upload(config, remote_path, &mut reader, data.len() as u64, 0o644, &TransferControl::new(), |_| {})
+        .await
+        .map(|_| ())
+}
+
+// ============================================================================
+// Browsing (shell commands)
+// ============================================================================
+
+/// Run a command and fail with its output unless it printed the success marker
+async fn exec_checked(config: &SshConfig, command: &str, what: &str) -> Result<String, String> {
+    let output = ssh_exec(config, &format!("{} && echo {}", command, SUCCESS_MARKER)).await?;
+    match output.rfind(SUCCESS_MARKER) {
+        Some(end) => Ok(output[..end].to_string()),
+        None => Err(format!("{}: {}", what, output.trim())),
+    }
+}
+
+/// List a directory with `stat` (GNU coreutils or busybox)
+pub async fn scp_list_dir(config: &SshConfig, path: &str) -> Result<Vec<FileEntry>, String> {
+    // One line per entry: mode, size, mtime, target mode (hex/dec), name, link target
+    let command = format!(
+        "cd {} && for f in .* *; do \
+         case \"$f\" in .|..) continue;; esac; \
+         [ -e \"$f\" ] || [ -L \"$f\" ] || continue; \
+         m=$(stat -c '%f\t%s\t%Y' -- \"$f\") || continue; \
+         t=$(stat -L -c '%f' -- \"$f\" 2>/dev/null || echo 0); \
+         l=$([ -L \"$f\" ] && readlink -- \"$f\"); \
+         printf '%s\\t%s\\t%s\\t%s\\n' \"$m\" \"$t\" \"$f\" \"$l\"; \
+         done",
+        shell_quote(path)
+    );
+    let output = exec_checked(config, &command, "Failed to read directory").await?;
+
+    let mut entries: Vec<FileEntry> = output.lines().filter_map(|line| parse_list_line(path, line)).collect();
+    entries.sort_by(|a, b| {
+        let a_dir = a.is_dir || a.target_is_dir;
+        let b_dir = b.is_dir || b.target_is_dir;
+        match (a_dir, b_dir) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        }
+    });
+    Ok(entries)
+}
+
+/// Parse one `mode\tsize\tmtime\ttarget_mode\tname\tlink` line
+fn parse_list_line(dir: &str, line: &str) -> Option<FileEntry> {
+    let mut parts = line.splitn(6, '\t');
+    let raw_mode = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let size = parts.next()?.parse().ok()?;
+    let modified = parts.next()?.parse().ok();
+    let target_mode = u32::from_str_radix(parts.next()?, 16).unwrap_or(0);
+    let name = parts.next()?.to_string();
+    let link = parts.next().filter(|l| !l.is_empty()).map(String::from);
+
+    let is_symlink = raw_mode & S_IFMT == S_IFLNK;
+    let mode = raw_mode & 0o7777;
+    Some(FileEntry {
+        path: join_remote_path(dir, &name),
+        name,
+        is_dir: raw_mode & S_IFMT == S_IFDIR,
+        size,
+        modified,
+        permissions: Some(format_mode(mode)),
+        mode: Some(mode),
+        is_symlink,
+        symlink_target: if is_symlink { link } else { None },
+        target_is_dir: is_symlink && target_mode & S_IFMT == S_IFDIR,
+    })
+}
+
+/// Type, size and mtime of a path (symlinks followed)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScpStat {
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<u64>,
+}
+
+/// Stat a path with `stat`, or None if it doesn't exist
+pub async fn scp_stat(config: &SshConfig, path: &str) -> Result<Option<ScpStat>, String> {
+    let path = shell_quote(path);
+    let command = format!("if [ -e {0} ]; then stat -L -c '%f\t%s\t%Y' -- {0}; fi", path);
+    let output = exec_checked(config, &command, "Failed to stat").await?;
+    match output.lines().find(|l| !l.trim().is_empty()) {
+        Some(line) => parse_stat_line(line).map(Some).ok_or_else(|| format!("Unexpected stat output: {}", line)),
+        None => Ok(None),
+    }
+}
+
+/// Parse a `mode\tsize\tmtime` line
+fn parse_stat_line(line: &str) -> Option<ScpStat> {
+    let mut parts = line.trim().splitn(3, '\t');
+    let raw_mode = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let size = parts.next()?.parse().ok()?;
+    let modified = parts.next()?.parse().ok();
+    Some(ScpStat { is_dir: raw_mode & S_IFMT == S_IFDIR, size, modified })
+}
+
+pub async fn scp_mkdir(config: &SshConfig, path: &str) -> Result<(), String> {
+    exec_checked(config, &format!("mkdir -- {}", shell_quote(path)), "Failed to create directory")
+        .await
+        .map(|_| ())
+}
+
+pub async fn scp_rename(config: &SshConfig, old_path: &str, new_path: &str) -> Result<(), String> {
+    let command = format!("mv -- {} {}", shell_quote(old_path), shell_quote(new_path));
+    exec_checked(config, &command, "Failed to rename").await.map(|_| ())
+}
+
+pub async fn scp_delete(config: &SshConfig, path: &str, is_dir: bool) -> Result<(), String> {
+    let command = if is_dir {
+        format!("rm -rf -- {}", shell_quote(path))
+    } else {
+        format!("rm -f -- {}", shell_quote(path))
+    };
+    exec_checked(config, &command, "Failed to delete").await.map(|_| ())
+}
+
+// ============================================================================
+// Attributes (shell commands)
+// ============================================================================
+
+/// Stat a path without following symlinks, with owner/group names
+pub async fn scp_file_stat(config: &SshConfig, path: &str) -> Result<FileStat, String> {
+    let quoted = shell_quote(path);
+    let command = format!(
+        "stat -c '%f\t%s\t%u\t%g\t%U\t%G\t%X\t%Y' -- {0} && if [ -L {0} ]; then readlink -- {0}; fi",
+        quoted
+    );
+    let output = exec_checked(config, &command, "Failed to stat").await?;
+    parse_file_stat(path, &output).ok_or_else(|| format!("Unexpected stat output: {}", output.trim()))
+}
+
+/// Parse a `mode\tsize\tuid\tgid\tuser\tgroup\tatime\tmtime` line, followed by
+/// the link target for symlinks
+fn parse_file_stat(path: &str, output: &str) -> Option<FileStat> {
+    let mut lines = output.lines();
+    let fields: Vec<&str> = lines.next()?.split('\t').collect();
+    if fields.len() != 8 {
+        return None;
+    }
+    let raw_mode = u32::from_str_radix(fields[0], 16).ok()?;
+    let name = |value: &str| Some(value.to_string()).filter(|v| !v.is_empty() && v != "UNKNOWN");
+    let file_type = match raw_mode & S_IFMT {
+        S_IFLNK => "symlink",
+        S_IFDIR => "dir",
+        S_IFREG => "file",
+        _ => "other",
+    };
+    let mode = raw_mode & 0o7777;
+    Some(FileStat {
+        path: path.to_string(),
+        file_type: file_type.to_string(),
+        size: fields[1].parse().ok()?,
+        mode: Some(mode),
+        permissions: Some(format_mode(mode)),
+        uid: fields[2].parse().ok(),
+        gid: fields[3].parse().ok(),
+        owner: name(fields[4]),
+        group: name(fields[5]),
+        accessed: fields[6].parse().ok(),
+        modified: fields[7].parse().ok(),
+        symlink_target: if file_type == "symlink" { lines.next().map(String::from) } else { None },
+    })
+}
+
+/// Set permission bits and/or times, like SFTP `setstat`
+pub async fn scp_set_attrs(
+    config: &SshConfig,
+    path: &str,
+    mode: Option<u32>,
+    accessed: Option<u32>,
+    modified: Option<u32>,
+) -> Result<(), String> {
+    match set_attrs_command(path, mode, accessed, modified) {
+        Some(command) => exec_checked(config, &command, "Failed to set attributes").await.map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// `chmod`/`touch` commands applying the given attributes
+fn set_attrs_command(path: &str, mode: Option<u32>, accessed: Option<u32>, modified: Option<u32>) -> Option<String> {
+    let path = shell_quote(path);
+    let mut commands = Vec::new();
+    if let Some(mode) = mode {
+        commands.push(format!("chmod {:04o} -- {}", mode & 0o7777, path));
+    }
+    if let Some(t) = modified {
+        commands.push(format!("touch -c -m -d @{} -- {}", t, path));
+    }
+    if let Some(t) = accessed {
+        commands.push(format!("touch -c -a -d @{} -- {}", t, path));
+    }
+    (!commands.is_empty()).then(|| commands.join(" && "))
+}
+
+/// Change owner and/or group (names or numeric ids)
+pub async fn scp_chown(config: &SshConfig, path: &str, owner: Option<&str>, group: Option<&str>) -> Result<(), String> {
+    let spec = match (owner, group) {
+        (Some(owner), Some(group)) => format!("{}:{}", owner, group),
+        (Some(owner), None) => owner.to_string(),
+        (None, Some(group)) => format!(":{}", group),
+        (None, None) => return Ok(()),
+    };
+    let command = format!("chown {} -- {}", shell_quote(&spec), shell_quote(path));
+    exec_checked(config, &command, "Failed to change owner").await.map(|_| ())
+}
+
+pub async fn scp_symlink(config: &SshConfig, link_path: &str, target: &str) -> Result<(), String> {
+    let command = format!("ln -s -- {} {}", shell_quote(target), shell_quote(link_path));
+    exec_checked(config, &command, "Failed to create symlink").await.map(|_| ())
+}
+
+pub async fn scp_readlink(config: &SshConfig, path: &str) -> Result<String, String> {
+    let output = exec_checked(config, &format!("readlink -- {}", shell_quote(path)), "Failed to read symlink").await?;
+    Ok(output.trim_end_matches('\n').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_file_headers() {
+        assert_eq!(parse_file_header("C0644 1234 my file.txt").unwrap(), (0o644, 1234));
+        assert!(parse_file_header("D0755 0 dir").is_err());
+        assert!(parse_file_header("garbage").is_err());
+    }
+
+    #[test]
+    fn test_parses_listing_lines() {
+        let file = parse_list_line("/etc", "81a4\t12\t1700000000\t81a4\thosts\t").unwrap();
+        assert_eq!(file.path, "/etc/hosts");
+        assert!(!file.is_dir && !file.is_symlink);
+        assert_eq!(file.permissions.as_deref(), Some("rw-r--r--"));
+        assert_eq!(file.modified, Some(1700000000));
+
+        let link = parse_list_line("/", "a1ff\t7\t1\t41ed\tbin\tusr/bin").unwrap();
+        assert!(link.is_symlink && link.target_is_dir && !link.is_dir);
+        assert_eq!(link.symlink_target.as_deref(), Some("usr/bin"));
+
+        assert!(parse_list_line("/", "nonsense").is_none());
+    }
+
+    #[test]
+    fn test_parses_stat_lines() {
+        assert_eq!(
+            parse_stat_line("81a4\t12\t1700000000\n"),
+            Some(ScpStat { is_dir: false, size: 12, modified: Some(1700000000) })
+        );
+        assert!(parse_stat_line("41ed\t4096\t1").unwrap().is_dir);
+        assert!(parse_stat_line("stat: cannot stat").is_none());
+    }
+
+    #[test]
+    fn test_parse_file_stat() {
+        let stat = parse_file_stat("/etc/hosts", "81a4\t12\t0\t0\troot\troot\t1700000001\t1700000000\n").unwrap();
+        assert_eq!(stat.file_type, "file");
+        assert_eq!(stat.permissions.as_deref(), Some("rw-r--r--"));
+        assert_eq!((stat.uid, stat.owner.as_deref()), (Some(0), Some("root")));
+        assert_eq!((stat.accessed, stat.modified), (Some(1700000001), Some(1700000000)));
+        assert_eq!(stat.symlink_target, None);
+
+        let link = parse_file_stat("/bin", "a1ff\t7\t0\t0\tUNKNOWN\troot\t1\t1\nusr/bin\n").unwrap();
+        assert_eq!(link.file_type, "symlink");
+        assert_eq!(link.owner, None);
+        assert_eq!(link.symlink_target.as_deref(), Some("usr/bin"));
+
+        assert!(parse_file_stat("/x", "stat: cannot stat").is_none());
+    }
+
+    #[test]
+    fn test_set_attrs_command() {
+        assert_eq!(
+            set_attrs_command("/srv/a b", Some(0o100755), None, Some(1700000000)).unwrap(),
+            "chmod 0755 -- '/srv/a b' && touch -c -m -d @1700000000 -- '/srv/a b'"
+        );
+        assert!(set_attrs_command("/srv", None, None, None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receives_a_file() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let server = async move {
+            assert_eq!(server.read_u8().await.unwrap(), 0);
+            server.write_all(b"C0644 11 notes.txt\n").await.unwrap();
+            assert_eq!(server.read_u8().await.unwrap(), 0);
+            server.write_all(b"hello world\0").await.unwrap();
+            assert_eq!(server.read_u8().await.unwrap(), 0);
+        };
+        let mut data = Vec::new();
+        let control = TransferControl::new();
+        let (received, ()) = tokio::join!(
+            receive_file(&mut client, &mut data, &control, |_| {}),
+            server
+        );
+        assert_eq!(received.unwrap(), 11);
+        assert_eq!(data, b"hello world");
+    }
+
+    #[tokio::test]
+    async fn test_reports_errors_from_the_source() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        server.write_all(b"\x01scp: /etc/shadow: Permission denied\n").await.unwrap();
+        let mut data = Vec::new();
+        let err = receive_file(&mut client, &mut data, &TransferControl::new(), |_| {}).await.unwrap_err();
+        assert_eq!(err, "SCP error: scp: /etc/shadow: Permission denied");
+    }
+}
@@ -4,13 +4,12 @@ use parking_lot::Mutex as SyncMutex;
 use serde::Serialize;
 use serialport::{DataBits, FlowControl, Parity, StopBits};
 use std::io::{Read, Write};
-use std::sync::mpsc as std_mpsc;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use tokio::sync::mpsc as tokio_mpsc;
 
-use crate::session::{OutputMessage, Session};
+use crate::session::{OutputMessage, OutputSender, Session};
 
 /// Available serial port information
 #[derive(Debug, Clone, Serialize)]
@@ -140,7 +139,7 @@ fn parse_flow_control(flow: &str) -> FlowControl {
 pub fn connect_serial(
     config: SerialConfig,
     session_id: String,
-    output_tx: std_mpsc::Sender<OutputMessage>,
+    output_tx: OutputSender,
     on_exit: impl FnOnce() + Send + 'static,
 ) -> Result<SerialSession, String> {
     // Ouvrir le port série
@@ -169,6 +168,7 @@ pub fn connect_serial(
         let mut buf = [0u8; 1024];
         
         while running_read.load(std::sync::atomic::Ordering::Relaxed) {
+            output_tx.wait_ready(&session_id_read);
             let result = {
                 let mut port = port_read.lock();
                 port.read(&mut buf)
@@ -1,18 +1,24 @@
 //! SFTP operations for SSH sessions
 //!
 //! Provides file browser functionality over SFTP with persistent connection pooling.
+//! Servers without the SFTP subsystem are served through `scp.rs` instead.
 
 use russh::client::{self, Config, Handle, Handler};
 use russh::keys::key::PublicKey;
+use russh::ChannelMsg;
 use russh_sftp::client::SftpSession;
-use russh_sftp::protocol::OpenFlags;
+use russh_sftp::protocol::FileAttributes;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use async_trait::async_trait;
-use tokio::sync::Mutex as TokioMutex;
+use futures::future::join_all;
+use parking_lot::Mutex;
+use tokio::sync::{Mutex as TokioMutex, OnceCell};
 
 use super::{SshAuth, SshConfig, load_ssh_key};
+use super::scp;
+use super::ssh_exec::{shell_quote, ssh_exec};
 
 /// Simple handler for SFTP connections
 struct SftpHandler;
@@ -33,23 +39,107 @@ impl Handler for SftpHandler {
 // Pool types
 // ============================================================================
 
-/// Holds an active SFTP session and the underlying SSH handle (must stay alive)
+/// Holds the SFTP sessions of one SSH connection and the handle that keeps it alive.
+///
+/// `SftpSession` pipelines requests (each one carries its own id), so callers
+/// share an entry without locking. File streams go through a second channel
+/// (`bulk`) so a large transfer doesn't queue its data ahead of browsing requests.
 pub struct SftpPoolEntry {
+    /// Interactive requests: listings, stat, rename...
     pub sftp: SftpSession,
-    #[allow(dead_code)]
+    /// File streams, opened on first use (`None` when the server refused it)
+    bulk: OnceCell<Option<SftpSession>>,
+    /// Owner/group names looked up for (uid, gid) pairs, see `sftp_stat`
+    owner_names: Mutex<HashMap<(u32, u32), (Option<String>, Option<String>)>>,
     handle: Handle<SftpHandler>,
 }
 
+impl SftpPoolEntry {
+    /// Session used for file streams. Falls back to the interactive session
+    /// when the server refuses another channel (e.g. `MaxSessions` reached),
+    /// without asking again for the lifetime of the connection.
+    pub async fn bulk(&self) -> &SftpSession {
+        let bulk = self
+            .bulk
+            .get_or_init(|| async {
+                match open_sftp_channel(&self.handle).await {
+                    Ok(sftp) => Some(sftp),
+                    Err(e) => {
+                        eprintln!("[SFTP] Using a single channel for file streams: {}", e);
+                        None
+                    }
+                }
+            })
+            .await;
+        bulk.as_ref().unwrap_or(&self.sftp)
+    }
+
+    /// Run a command on a new channel of this connection and return its
+    /// stdout, without opening another SSH connection
+    async fn exec(&self, command: &str) -> Result<String, String> {
+        let mut channel = self
+            .handle
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("Failed to open channel: {}", e))?;
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| format!("Failed to exec: {}", e))?;
+
+        let mut output = Vec::new();
+        loop {
+            match channel.wait().await {
+                Some(ChannelMsg::Data { data }) => output.extend_from_slice(&data),
+                Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => break,
+                _ => {}
+            }
+        }
+        let _ = channel.close().await;
+
+        String::from_utf8(output).map_err(|e| format!("Invalid UTF-8: {}", e))
+    }
+
+    /// Whether the underlying SSH connection has gone away
+    fn is_closed(&self) -> bool {
+        self.handle.is_closed()
+    }
+}
+
+/// Prefix of the connection error returned when the server refuses the `sftp` subsystem
+pub(crate) const SFTP_UNAVAILABLE: &str = "SFTP subsystem unavailable";
+
 /// Thread-safe pool of SFTP connections keyed by session_id.
-/// Outer mutex: short lock to access the HashMap.
-/// Inner mutex: held for the duration of an SFTP operation.
-pub type SftpPool = Arc<TokioMutex<HashMap<String, Arc<TokioMutex<SftpPoolEntry>>>>>;
+/// The mutex is only held to access the HashMap; entries are shared.
+pub type SftpPool = Arc<TokioMutex<HashMap<String, Arc<SftpPoolEntry>>>>;
 
 /// Create a new empty pool
 pub fn new_sftp_pool() -> SftpPool {
     Arc::new(TokioMutex::new(HashMap::new()))
 }
 
+/// Open a channel on an authenticated connection and start the SFTP subsystem
+async fn open_sftp_channel(handle: &Handle<SftpHandler>) -> Result<SftpSession, String> {
+    let channel = handle
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Failed to open channel: {}", e))?;
+    start_sftp(channel).await
+}
+
+/// Start the SFTP subsystem on an open channel. Errors mean the server has
+/// no (working) SFTP subsystem, unlike failures to open the channel itself.
+async fn start_sftp(channel: russh::Channel<client::Msg>) -> Result<SftpSession, String> {
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| format!("Failed to request SFTP subsystem: {}", e))?;
+
+    SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| format!("Failed to create SFTP session: {}", e))
+}
+
 /// Establish a fresh SFTP session from an SshConfig
 async fn connect_sftp(config: &SshConfig) -> Result<SftpPoolEntry, String> {
     let mut ssh_config = Config::default();
@@ -82,22 +172,22 @@ async fn connect_sftp(config: &SshConfig) -> Result<SftpPoolEntry, String> {
         return Err("Authentication failed".to_string());
     }
 
-    // Open SFTP channel
+    // Some servers only allow exec channels (see scp.rs). A channel that can't
+    // be opened (MaxSessions, transient errors) doesn't say anything about SFTP.
     let channel = session
         .channel_open_session()
         .await
         .map_err(|e| format!("Failed to open channel: {}", e))?;
-
-    channel
-        .request_subsystem(true, "sftp")
-        .await
-        .map_err(|e| format!("Failed to request SFTP subsystem: {}", e))?;
-
-    let sftp = SftpSession::new(channel.into_stream())
+    let sftp = start_sftp(channel)
         .await
-        .map_err(|e| format!("Failed to create SFTP session: {}", e))?;
+        .map_err(|e| format!("{}: {}", SFTP_UNAVAILABLE, e))?;
 
-    Ok(SftpPoolEntry { sftp, handle: session })
+    Ok(SftpPoolEntry {
+        sftp,
+        bulk: OnceCell::new(),
+        owner_names: Mutex::new(HashMap::new()),
+        handle: session,
+    })
 }
 
 /// Get an existing pooled connection or create a new one
@@ -105,7 +195,7 @@ async fn get_or_connect(
     pool: &SftpPool,
     session_id: &str,
     config: &SshConfig,
-) -> Result<Arc<TokioMutex<SftpPoolEntry>>, String> {
+) -> Result<Arc<SftpPoolEntry>, String> {
     // Check if we already have a connection
     {
         let map = pool.lock().await;
@@ -114,40 +204,119 @@ async fn get_or_connect(
         }
     }
 
-    // Create new connection
-    let entry = connect_sftp(config).await?;
-    let entry_arc = Arc::new(TokioMutex::new(entry));
+    // Create new connection (without holding the map lock)
+    let entry = Arc::new(connect_sftp(config).await?);
 
+    // Another caller may have connected meanwhile: keep the first one
     let mut map = pool.lock().await;
-    map.insert(session_id.to_string(), entry_arc.clone());
+    Ok(map.entry(session_id.to_string()).or_insert(entry).clone())
+}
 
-    Ok(entry_arc)
+/// Pool entry of a session, for session introspection
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SftpPoolInfo {
+    /// The pooled connection is still open
+    pub connected: bool,
+    /// A second channel was opened for file streams
+    pub bulk_channel: bool,
+    /// Files go through SCP: the server has no SFTP subsystem
+    pub scp: bool,
 }
 
-/// Remove a session from the pool (dropping the entry closes the connection)
+/// Describe a session's pool entry, if it has one (or uses SCP)
+pub async fn sftp_pool_info(pool: &SftpPool, session_id: &str) -> Option<SftpPoolInfo> {
+    let entry = pool.lock().await.get(session_id).cloned();
+    let scp = scp::is_scp_session(session_id);
+    if entry.is_none() && !scp {
+        return None;
+    }
+    Some(SftpPoolInfo {
+        connected: entry.as_ref().map_or(false, |e| !e.is_closed()),
+        bulk_channel: entry.as_ref().map_or(false, |e| e.bulk.initialized()),
+        scp,
+    })
+}
+
+/// Remove a session from the pool (the connection closes once no operation uses it)
 pub async fn disconnect_sftp(pool: &SftpPool, session_id: &str) {
     let mut map = pool.lock().await;
     map.remove(session_id);
+    scp::clear_scp_session(session_id);
+}
+
+/// Whether a session must use SCP because the server has no SFTP subsystem.
+/// The first call probes the pooled connection; the answer is kept per session.
+pub(crate) async fn uses_scp(pool: &SftpPool, session_id: &str, config: &SshConfig) -> bool {
+    if scp::is_scp_session(session_id) {
+        return true;
+    }
+    match get_or_connect(pool, session_id, config).await {
+        Err(e) if e.starts_with(SFTP_UNAVAILABLE) => {
+            println!("[SFTP] {} has no SFTP subsystem, falling back to SCP", config.host);
+            scp::mark_scp_session(session_id);
+            true
+        }
+        _ => false,
+    }
 }
 
-/// Helper: execute an SFTP operation with automatic retry on stale connection
-async fn with_sftp<F, Fut, T>(
+/// Run a command on the session's pooled connection and return its stdout.
+/// Falls back to `ssh_exec` for SCP sessions and when the server refuses
+/// another channel.
+pub(crate) async fn session_exec(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    command: &str,
+) -> Result<String, String> {
+    if uses_scp(pool, session_id, config).await {
+        return ssh_exec(config, command).await;
+    }
+    let pooled = with_sftp(pool, session_id, config, |entry| {
+        let command = command.to_string();
+        async move { entry.exec(&command).await }
+    })
+    .await;
+    match pooled {
+        Ok(output) => Ok(output),
+        Err(e) => {
+            eprintln!("[SFTP] Running command on a separate connection: {}", e);
+            ssh_exec(config, command).await
+        }
+    }
+}
+
+/// Drop `entry` from the pool unless it was already replaced
+async fn evict_entry(pool: &SftpPool, session_id: &str, entry: &Arc<SftpPoolEntry>) {
+    let mut map = pool.lock().await;
+    if map.get(session_id).map_or(false, |current| Arc::ptr_eq(current, entry)) {
+        map.remove(session_id);
+    }
+}
+
+/// Helper: execute an SFTP operation with automatic retry on stale connection.
+///
+/// Operations run concurrently on the shared entry. An error on a live
+/// connection (missing file, permission denied...) is returned as-is; only a
+/// closed connection is replaced and the operation retried once.
+pub(crate) async fn with_sftp<F, Fut, T>(
     pool: &SftpPool,
     session_id: &str,
     config: &SshConfig,
     op: F,
 ) -> Result<T, String>
 where
-    F: Fn(Arc<TokioMutex<SftpPoolEntry>>) -> Fut + Send,
+    F: Fn(Arc<SftpPoolEntry>) -> Fut + Send,
     Fut: std::future::Future<Output = Result<T, String>> + Send,
 {
     let entry = get_or_connect(pool, session_id, config).await?;
 
-    match op(entry).await {
+    match op(entry.clone()).await {
         Ok(val) => Ok(val),
+        Err(e) if !entry.is_closed() => Err(e),
         Err(_) => {
-            // Connection might be stale — drop it and retry once
-            disconnect_sftp(pool, session_id).await;
+            // Connection is stale — drop it and retry once
+            evict_entry(pool, session_id, &entry).await;
             let entry2 = get_or_connect(pool, session_id, config).await?;
             op(entry2).await
         }
@@ -163,10 +332,54 @@ where
 pub struct FileEntry {
     pub name: String,
     pub path: String,
+    /// False for symlinks, even when they point to a directory (see `target_is_dir`)
     pub is_dir: bool,
     pub size: u64,
     pub modified: Option<u64>,
     pub permissions: Option<String>,
+    /// Permission bits including setuid/setgid/sticky
+    pub mode: Option<u32>,
+    pub is_symlink: bool,
+    /// Where the symlink points to (symlinks only)
+    pub symlink_target: Option<String>,
+    /// Whether the symlink resolves to a directory (symlinks only)
+    pub target_is_dir: bool,
+}
+
+/// Format permission bits like `ls -l` (without the file type), e.g. "rwsr-xr-t"
+pub fn format_mode(mode: u32) -> String {
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    // Execute position shows setuid/setgid/sticky as s/t (S/T when not executable)
+    let exec = |mask: u32, special: u32, set: char| match (mode & mask != 0, mode & special != 0) {
+        (true, true) => set,
+        (false, true) => set.to_ascii_uppercase(),
+        (true, false) => 'x',
+        (false, false) => '-',
+    };
+    [
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        exec(0o100, 0o4000, 's'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        exec(0o010, 0o2000, 's'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        exec(0o001, 0o1000, 't'),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Join a remote directory and an entry name
+pub fn join_remote_path(dir: &str, name: &str) -> String {
+    if dir == "/" || dir.is_empty() {
+        format!("/{}", name)
+    } else if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
 }
 
 /// List directory contents via SFTP
@@ -176,12 +389,14 @@ pub async fn sftp_list_dir(
     config: &SshConfig,
     path: &str,
 ) -> Result<Vec<FileEntry>, String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_list_dir(config, path).await;
+    }
     let path = path.to_string();
     with_sftp(pool, session_id, config, |entry| {
         let path = path.clone();
         async move {
-            let guard = entry.lock().await;
-            let entries = guard.sftp
+            let entries = entry.sftp
                 .read_dir(&path)
                 .await
                 .map_err(|e| format!("Failed to read directory: {}", e))?;
@@ -193,34 +408,16 @@ pub async fn sftp_list_dir(
                     continue;
                 }
 
-                let file_path = if path == "/" || path.is_empty() {
-                    format!("/{}", name)
-                } else if path.ends_with('/') {
-                    format!("{}{}", path, name)
-                } else {
-                    format!("{}/{}", path, name)
-                };
+                let file_path = join_remote_path(&path, &name);
 
+                // Entry attributes come from lstat: symlinks are reported as such
                 let attrs = dir_entry.metadata();
                 let is_dir = attrs.is_dir();
+                let is_symlink = attrs.is_symlink();
                 let size = attrs.size.unwrap_or(0);
                 let modified = attrs.mtime.map(|t| t as u64);
-
-                let permissions = attrs.permissions.map(|p| {
-                    let mode = p & 0o777;
-                    format!(
-                        "{}{}{}{}{}{}{}{}{}",
-                        if mode & 0o400 != 0 { 'r' } else { '-' },
-                        if mode & 0o200 != 0 { 'w' } else { '-' },
-                        if mode & 0o100 != 0 { 'x' } else { '-' },
-                        if mode & 0o040 != 0 { 'r' } else { '-' },
-                        if mode & 0o020 != 0 { 'w' } else { '-' },
-                        if mode & 0o010 != 0 { 'x' } else { '-' },
-                        if mode & 0o004 != 0 { 'r' } else { '-' },
-                        if mode & 0o002 != 0 { 'w' } else { '-' },
-                        if mode & 0o001 != 0 { 'x' } else { '-' },
-                    )
-                });
+                let mode = attrs.permissions.map(|p| p & 0o7777);
+                let permissions = mode.map(format_mode);
 
                 result.push(FileEntry {
                     name,
@@ -229,11 +426,30 @@ pub async fn sftp_list_dir(
                     size,
                     modified,
                     permissions,
+                    mode,
+                    is_symlink,
+                    symlink_target: None,
+                    target_is_dir: false,
                 });
             }
 
+            // Resolve symlinks concurrently: requests are pipelined on the shared session
+            let sftp = &entry.sftp;
+            let resolved = join_all(result.iter().filter(|f| f.is_symlink).map(|f| async move {
+                let (target, metadata) = tokio::join!(sftp.read_link(&f.path), sftp.metadata(&f.path));
+                // Broken links resolve to nothing
+                (target.ok(), metadata.map(|m| m.is_dir()).unwrap_or(false))
+            }))
+            .await;
+            for (file, (target, target_is_dir)) in result.iter_mut().filter(|f| f.is_symlink).zip(resolved) {
+                file.symlink_target = target;
+                file.target_is_dir = target_is_dir;
+            }
+
             result.sort_by(|a, b| {
-                match (a.is_dir, b.is_dir) {
+                let a_dir = a.is_dir || a.target_is_dir;
+                let b_dir = b.is_dir || b.target_is_dir;
+                match (a_dir, b_dir) {
                     (true, false) => std::cmp::Ordering::Less,
                     (false, true) => std::cmp::Ordering::Greater,
                     _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
@@ -247,18 +463,22 @@ pub async fn sftp_list_dir(
 }
 
 /// Read file contents via SFTP
+///
+/// Loads the whole file in memory — use `transfers::sftp_download_file` for large files.
 pub async fn sftp_read_file(
     pool: &SftpPool,
     session_id: &str,
     config: &SshConfig,
     path: &str,
 ) -> Result<Vec<u8>, String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_read_file(config, path).await;
+    }
     let path = path.to_string();
     with_sftp(pool, session_id, config, |entry| {
         let path = path.clone();
         async move {
-            let guard = entry.lock().await;
-            guard.sftp
+            entry.sftp
                 .read(&path)
                 .await
                 .map_err(|e| format!("Failed to read file: {}", e))
@@ -275,13 +495,15 @@ pub async fn sftp_write_file(
     path: &str,
     data: Vec<u8>,
 ) -> Result<(), String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_write_file(config, path, &data).await;
+    }
     let path = path.to_string();
     with_sftp(pool, session_id, config, |entry| {
         let path = path.clone();
         let data = data.clone();
         async move {
-            let guard = entry.lock().await;
-            guard.sftp
+            entry.sftp
                 .write(&path, &data)
                 .await
                 .map_err(|e| format!("Failed to write file: {}", e))
@@ -298,6 +520,9 @@ pub async fn sftp_delete(
     path: &str,
     is_dir: bool,
 ) -> Result<(), String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_delete(config, path, is_dir).await;
+    }
     if is_dir {
         sftp_delete_recursive(pool, session_id, config, path).await
     } else {
@@ -305,8 +530,7 @@ pub async fn sftp_delete(
         with_sftp(pool, session_id, config, |entry| {
             let path = path.clone();
             async move {
-                let guard = entry.lock().await;
-                guard.sftp
+                entry.sftp
                     .remove_file(&path)
                     .await
                     .map_err(|e| format!("Failed to remove file: {}", e))
@@ -336,8 +560,7 @@ async fn sftp_delete_recursive(
             with_sftp(pool, session_id, config, |e| {
                 let path = path.clone();
                 async move {
-                    let guard = e.lock().await;
-                    guard.sftp
+                    e.sftp
                         .remove_file(&path)
                         .await
                         .map_err(|e| format!("Failed to remove file: {}", e))
@@ -352,8 +575,7 @@ async fn sftp_delete_recursive(
     with_sftp(pool, session_id, config, |entry| {
         let dir_path = dir_path.clone();
         async move {
-            let guard = entry.lock().await;
-            guard.sftp
+            entry.sftp
                 .remove_dir(&dir_path)
                 .await
                 .map_err(|e| format!("Failed to remove directory: {}", e))
@@ -370,14 +592,16 @@ pub async fn sftp_rename(
     old_path: &str,
     new_path: &str,
 ) -> Result<(), String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_rename(config, old_path, new_path).await;
+    }
     let old_path = old_path.to_string();
     let new_path = new_path.to_string();
     with_sftp(pool, session_id, config, |entry| {
         let old_path = old_path.clone();
         let new_path = new_path.clone();
         async move {
-            let guard = entry.lock().await;
-            guard.sftp
+            entry.sftp
                 .rename(&old_path, &new_path)
                 .await
                 .map_err(|e| format!("Failed to rename: {}", e))
@@ -393,12 +617,14 @@ pub async fn sftp_mkdir(
     config: &SshConfig,
     path: &str,
 ) -> Result<(), String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_mkdir(config, path).await;
+    }
     let path = path.to_string();
     with_sftp(pool, session_id, config, |entry| {
         let path = path.clone();
         async move {
-            let guard = entry.lock().await;
-            guard.sftp
+            entry.sftp
                 .create_dir(&path)
                 .await
                 .map_err(|e| format!("Failed to create directory: {}", e))
@@ -408,105 +634,300 @@ pub async fn sftp_mkdir(
 }
 
 // ============================================================================
-// Upload with progress
+// Metadata operations
 // ============================================================================
 
-/// Progress event emitted during upload
+/// Detailed attributes of a single path (lstat: symlinks are not followed)
 #[derive(Debug, Clone, serde::Serialize)]
-pub struct SftpUploadProgress {
-    pub session_id: String,
-    pub file_name: String,
-    pub bytes_sent: u64,
-    pub total_bytes: u64,
-    pub file_index: u32,
-    pub total_files: u32,
-    pub done: bool,
-    pub error: Option<String>,
-}
-
-/// Upload a file via SFTP (uses pool), emitting progress events
-pub async fn sftp_upload_file(
+pub struct FileStat {
+    pub path: String,
+    /// "file", "dir", "symlink" or "other"
+    pub file_type: String,
+    pub size: u64,
+    /// Permission bits including setuid/setgid/sticky
+    pub mode: Option<u32>,
+    pub permissions: Option<String>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub accessed: Option<u64>,
+    pub modified: Option<u64>,
+    pub symlink_target: Option<String>,
+}
+
+/// Stat a path via SFTP, resolving owner/group names when possible
+pub async fn sftp_stat(
     pool: &SftpPool,
     session_id: &str,
     config: &SshConfig,
-    remote_path: &str,
-    data: Vec<u8>,
-    file_name: String,
-    file_index: u32,
-    total_files: u32,
-    app_handle: &tauri::AppHandle,
-) -> Result<(), String> {
-    use tauri::Emitter;
-
-    let total_bytes = data.len() as u64;
-    let session_id_str = session_id.to_string();
-    let remote_path = remote_path.to_string();
-
-    // Emit start progress
-    let _ = app_handle.emit("sftp-upload-progress", SftpUploadProgress {
-        session_id: session_id_str.clone(),
-        file_name: file_name.clone(),
-        bytes_sent: 0,
-        total_bytes,
-        file_index,
-        total_files,
-        done: false,
-        error: None,
-    });
-
-    let result = with_sftp(pool, &session_id_str, config, |entry| {
-        let remote_path = remote_path.clone();
-        let data = data.clone();
+    path: &str,
+) -> Result<FileStat, String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_file_stat(config, path).await;
+    }
+    let path = path.to_string();
+    let (mut stat, entry) = with_sftp(pool, session_id, config, |entry| {
+        let path = path.clone();
         async move {
-            use tokio::io::AsyncWriteExt;
-            let guard = entry.lock().await;
-            let mut file = guard.sftp
-                .open_with_flags(
-                    &remote_path,
-                    OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE,
-                )
-                .await
-                .map_err(|e| format!("Failed to create remote file: {}", e))?;
-            file.write_all(&data)
-                .await
-                .map_err(|e| format!("Failed to write data: {}", e))?;
-            file.flush()
+            let attrs = entry.sftp
+                .symlink_metadata(&path)
                 .await
-                .map_err(|e| format!("Failed to flush: {}", e))?;
-            file.shutdown()
+                .map_err(|e| format!("Failed to stat: {}", e))?;
+
+            let file_type = if attrs.is_symlink() {
+                "symlink"
+            } else if attrs.is_dir() {
+                "dir"
+            } else if attrs.is_regular() {
+                "file"
+            } else {
+                "other"
+            };
+            let symlink_target = if attrs.is_symlink() {
+                entry.sftp.read_link(&path).await.ok()
+            } else {
+                None
+            };
+            let mode = attrs.permissions.map(|p| p & 0o7777);
+
+            let stat = FileStat {
+                path,
+                file_type: file_type.to_string(),
+                size: attrs.size.unwrap_or(0),
+                mode,
+                permissions: mode.map(format_mode),
+                uid: attrs.uid,
+                gid: attrs.gid,
+                owner: attrs.user.clone(),
+                group: attrs.group.clone(),
+                accessed: attrs.atime.map(|t| t as u64),
+                modified: attrs.mtime.map(|t| t as u64),
+                symlink_target,
+            };
+            Ok((stat, entry))
+        }
+    })
+    .await?;
+
+    // SFTPv3 servers only send numeric ids; names are best effort and looked
+    // up once per connection
+    if stat.owner.is_none() || stat.group.is_none() {
+        if let (Some(uid), Some(gid)) = (stat.uid, stat.gid) {
+            let cached = entry.owner_names.lock().get(&(uid, gid)).cloned();
+            let (owner, group) = match cached {
+                Some(names) => names,
+                None => {
+                    let names = resolve_owner_names(config, uid, gid).await.unwrap_or((None, None));
+                    entry.owner_names.lock().insert((uid, gid), names.clone());
+                    names
+                }
+            };
+            stat.owner = stat.owner.or(owner);
+            stat.group = stat.group.or(group);
+        }
+    }
+
+    Ok(stat)
+}
+
+/// Look up user and group names for numeric ids on the server
+async fn resolve_owner_names(config: &SshConfig, uid: u32, gid: u32) -> Result<(Option<String>, Option<String>), String> {
+    let command = format!(
+        "echo \"u:$(getent passwd {} | cut -d: -f1)\"; echo \"g:$(getent group {} | cut -d: -f1)\"",
+        uid, gid
+    );
+    let output = ssh_exec(config, &command).await?;
+
+    let field = |prefix: &str| {
+        output
+            .lines()
+            .find_map(|l| l.strip_prefix(prefix))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    Ok((field("u:"), field("g:")))
+}
+
+/// Resolve a user or group given by name or numeric id to its id on the server
+async fn resolve_owner_id(config: &SshConfig, value: &str, database: &str) -> Result<u32, String> {
+    if let Ok(id) = value.parse::<u32>() {
+        return Ok(id);
+    }
+    let command = format!("getent {} {} | cut -d: -f3", database, shell_quote(value));
+    ssh_exec(config, &command)
+        .await?
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| format!("Unknown {}: {}", if database == "passwd" { "user" } else { "group" }, value))
+}
+
+/// Apply a partial set of attributes to a path
+async fn set_attributes(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+    attrs: FileAttributes,
+) -> Result<(), String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_set_attrs(config, path, attrs.permissions, attrs.atime, attrs.mtime).await;
+    }
+    let path = path.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let path = path.clone();
+        let attrs = attrs.clone();
+        async move {
+            entry.sftp
+                .set_metadata(&path, attrs)
                 .await
-                .map_err(|e| format!("Failed to close file: {}", e))?;
-            Ok(())
+                .map_err(|e| format!("Failed to set attributes: {}", e))
         }
     })
-    .await;
+    .await
+}
 
-    match &result {
-        Ok(_) => {
-            let _ = app_handle.emit("sftp-upload-progress", SftpUploadProgress {
-                session_id: session_id_str,
-                file_name,
-                bytes_sent: total_bytes,
-                total_bytes,
-                file_index,
-                total_files,
-                done: true,
-                error: None,
-            });
+/// Change permission bits (including setuid/setgid/sticky)
+pub async fn sftp_chmod(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+    mode: u32,
+) -> Result<(), String> {
+    let mut attrs = FileAttributes::empty();
+    attrs.permissions = Some(mode & 0o7777);
+    set_attributes(pool, session_id, config, path, attrs).await
+}
+
+/// Change owner and/or group. Each may be a name or a numeric id;
+/// the one left out is kept as is.
+pub async fn sftp_chown(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+    owner: Option<&str>,
+    group: Option<&str>,
+) -> Result<(), String> {
+    if owner.is_none() && group.is_none() {
+        return Ok(());
+    }
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_chown(config, path, owner, group).await;
+    }
+
+    // SFTPv3 sets uid and gid together, so start from the current values
+    let current = sftp_stat(pool, session_id, config, path).await?;
+    let uid = match owner {
+        Some(owner) => resolve_owner_id(config, owner, "passwd").await?,
+        None => current.uid.ok_or("Server did not report the current owner")?,
+    };
+    let gid = match group {
+        Some(group) => resolve_owner_id(config, group, "group").await?,
+        None => current.gid.ok_or("Server did not report the current group")?,
+    };
+
+    let mut attrs = FileAttributes::empty();
+    attrs.uid = Some(uid);
+    attrs.gid = Some(gid);
+    set_attributes(pool, session_id, config, path, attrs).await
+}
+
+/// Set access and modification times (seconds since epoch).
+/// The access time is kept when not given.
+pub async fn sftp_utime(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+    accessed: Option<u32>,
+    modified: u32,
+) -> Result<(), String> {
+    // SFTPv3 sets both times together
+    let accessed = match accessed {
+        Some(t) => t,
+        None => sftp_stat(pool, session_id, config, path)
+            .await?
+            .accessed
+            .map(|t| t as u32)
+            .unwrap_or(modified),
+    };
+
+    let mut attrs = FileAttributes::empty();
+    attrs.atime = Some(accessed);
+    attrs.mtime = Some(modified);
+    set_attributes(pool, session_id, config, path, attrs).await
+}
+
+/// Create a symbolic link at `link_path` pointing to `target`
+pub async fn sftp_symlink(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    link_path: &str,
+    target: &str,
+) -> Result<(), String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_symlink(config, link_path, target).await;
+    }
+    let link_path = link_path.to_string();
+    let target = target.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let link_path = link_path.clone();
+        let target = target.clone();
+        async move {
+            entry.sftp
+                .symlink(&link_path, &target)
+                .await
+                .map_err(|e| format!("Failed to create symlink: {}", e))
         }
-        Err(e) => {
-            let _ = app_handle.emit("sftp-upload-progress", SftpUploadProgress {
-                session_id: session_id_str,
-                file_name,
-                bytes_sent: 0,
-                total_bytes,
-                file_index,
-                total_files,
-                done: true,
-                error: Some(e.clone()),
-            });
+    })
+    .await
+}
+
+/// Read the target of a symbolic link
+pub async fn sftp_readlink(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+) -> Result<String, String> {
+    if uses_scp(pool, session_id, config).await {
+        return scp::scp_readlink(config, path).await;
+    }
+    let path = path.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let path = path.clone();
+        async move {
+            entry.sftp
+                .read_link(&path)
+                .await
+                .map_err(|e| format!("Failed to read symlink: {}", e))
         }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_mode() {
+        assert_eq!(format_mode(0o755), "rwxr-xr-x");
+        assert_eq!(format_mode(0o640), "rw-r-----");
+        assert_eq!(format_mode(0o4755), "rwsr-xr-x");
+        assert_eq!(format_mode(0o2745), "rwxr-Sr-x");
+        assert_eq!(format_mode(0o1777), "rwxrwxrwt");
+        assert_eq!(format_mode(0o1666), "rw-rw-rwT");
     }
 
-    result
+    #[test]
+    fn test_join_remote_path() {
+        assert_eq!(join_remote_path("/", "etc"), "/etc");
+        assert_eq!(join_remote_path("", "etc"), "/etc");
+        assert_eq!(join_remote_path("/home/", "user"), "/home/user");
+        assert_eq!(join_remote_path("/home", "user"), "/home/user");
+    }
 }
@@ -0,0 +1,448 @@
+//! Remote file search over SFTP
+//!
+//! Walks a directory tree breadth-first through the pooled SFTP connection,
+//! matching names (glob or regex), size and mtime. Content search is delegated
+//! to `grep` on the server via `ssh_exec`, over batches of the walked files.
+//! Results are streamed as `sftp-search-results` events and the search ends
+//! with `sftp-search-done`.
+
+use parking_lot::Mutex as SyncMutex;
+use regex::{Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+
+use super::sftp::{join_remote_path, with_sftp};
+use super::ssh_exec::{shell_quote, ssh_exec};
+use super::{SftpPool, SshConfig};
+use crate::transfers::filter::glob_match;
+
+/// Number of files passed to a single `grep` invocation
+const GREP_BATCH_SIZE: usize = 100;
+
+lazy_static::lazy_static! {
+    /// Cancellation flags of running searches, keyed by search id
+    static ref ACTIVE_SEARCHES: SyncMutex<HashMap<String, Arc<AtomicBool>>> =
+        SyncMutex::new(HashMap::new());
+}
+
+/// Search criteria (all set criteria must match)
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    /// Directory to search from
+    pub root: String,
+    /// Pattern matched against entry names
+    pub name_pattern: Option<String>,
+    /// Treat `name_pattern` as a regular expression instead of a glob
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Maximum depth below `root` (1 = direct children only)
+    pub max_depth: Option<u32>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Seconds since epoch
+    pub modified_after: Option<u64>,
+    pub modified_before: Option<u64>,
+    /// Include entries whose name starts with a dot (and descend into them)
+    #[serde(default)]
+    pub include_hidden: bool,
+    /// Match directories as well as files (ignored for content search)
+    #[serde(default)]
+    pub include_dirs: bool,
+    /// Only files containing this text (fixed string, via grep on the server)
+    pub content: Option<String>,
+    /// Stop after this many matches
+    pub max_results: Option<usize>,
+}
+
+/// A matching entry
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SearchMatch {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<u64>,
+}
+
+/// Batch of results emitted while the search runs
+#[derive(Debug, Clone, serde::Serialize)]
+struct SearchResults {
+    search_id: String,
+    matches: Vec<SearchMatch>,
+}
+
+/// Final event of a search
+#[derive(Debug, Clone, serde::Serialize)]
+struct SearchDone {
+    search_id: String,
+    total_matches: usize,
+    directories_scanned: usize,
+    cancelled: bool,
+    truncated: bool,
+    error: Option<String>,
+}
+
+enum NameMatcher {
+    Any,
+    Glob { pattern: String, case_sensitive: bool },
+    Regex(Regex),
+}
+
+impl NameMatcher {
+    fn new(query: &SearchQuery) -> Result<Self, String> {
+        match &query.name_pattern {
+            None => Ok(NameMatcher::Any),
+            Some(p) if p.is_empty() => Ok(NameMatcher::Any),
+            Some(p) if query.regex => RegexBuilder::new(p)
+                .case_insensitive(!query.case_sensitive)
+                .build()
+                .map(NameMatcher::Regex)
+                .map_err(|e| format!("Invalid regex: {}", e)),
+            Some(p) => Ok(NameMatcher::Glob {
+                pattern: if query.case_sensitive { p.clone() } else { p.to_lowercase() },
+                case_sensitive: query.case_sensitive,
+            }),
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Any => true,
+            NameMatcher::Glob { pattern, case_sensitive: true } => glob_match(pattern, name),
+            NameMatcher::Glob { pattern, case_sensitive: false } => glob_match(pattern, &name.to_lowercase()),
+            NameMatcher::Regex(re) => re.is_match(name),
+        }
+    }
+}
+
+/// Whether the walk goes into a directory found at `depth`
+fn descends(query: &SearchQuery, depth: u32) -> bool {
+    query.max_depth.map_or(true, |max| depth + 1 < max)
+}
+
+fn matches_filters(query: &SearchQuery, size: u64, modified: Option<u64>) -> bool {
+    if query.min_size.map_or(false, |min| size < min) || query.max_size.map_or(false, |max| size > max) {
+        return false;
+    }
+    match modified {
+        Some(t) => {
+            query.modified_after.map_or(true, |after| t >= after)
+                && query.modified_before.map_or(true, |before| t <= before)
+        }
+        None => query.modified_after.is_none() && query.modified_before.is_none(),
+    }
+}
+
+/// Start a search in the background and return its id
+pub fn start_search(
+    app: AppHandle,
+    pool: SftpPool,
+    session_id: String,
+    config: SshConfig,
+    query: SearchQuery,
+) -> Result<String, String> {
+    let matcher = NameMatcher::new(&query)?;
+    let search_id = uuid::Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    ACTIVE_SEARCHES.lock().insert(search_id.clone(), cancelled.clone());
+
+    let id = search_id.clone();
+    tokio::spawn(async move {
+        let mut stats = SearchStats::default();
+        let result = run_search(&app, &pool, &session_id, &config, &query, &matcher, &id, &cancelled, &mut stats).await;
+        ACTIVE_SEARCHES.lock().remove(&id);
+
+        let _ = app.emit("sftp-search-done", SearchDone {
+            search_id: id,
+            total_matches: stats.matches,
+            directories_scanned: stats.directories,
+            cancelled: cancelled.load(Ordering::SeqCst),
+            truncated: stats.truncated,
+            error: result.err(),
+        });
+    });
+
+    Ok(search_id)
+}
+
+/// Cancel a running search. Returns false if it already finished.
+pub fn cancel_search(search_id: &str) -> bool {
+    match ACTIVE_SEARCHES.lock().get(search_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+#[derive(Default)]
+struct SearchStats {
+    matches: usize,
+    directories: usize,
+    truncated: bool,
+}
+
+async fn run_search(
+    app: &AppHandle,
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    query: &SearchQuery,
+    matcher: &NameMatcher,
+    search_id: &str,
+    cancelled: &AtomicBool,
+    stats: &mut SearchStats,
+) -> Result<(), String> {
+    let max_results = query.max_results.unwrap_or(usize::MAX);
+    let content = query.content.as_deref().filter(|c| !c.is_empty());
+    if content.is_some() {
+        let available = ssh_exec(config, "command -v grep >/dev/null && echo ok").await?;
+        if available.trim() != "ok" {
+            return Err("Content search requires grep on the server".to_string());
+        }
+    }
+
+    let emit = |matches: Vec<SearchMatch>| {
+        if !matches.is_empty() {
+            let _ = app.emit("sftp-search-results", SearchResults {
+                search_id: search_id.to_string(),
+                matches,
+            });
+        }
+    };
+
+    let mut queue: VecDeque<(String, u32)> = VecDeque::from([(query.root.clone(), 0)]);
+    // Content search: candidates waiting to be grepped
+    let mut pending: Vec<SearchMatch> = Vec::new();
+
+    while let Some((dir, depth)) = queue.pop_front() {
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let entries = match list_raw(pool, session_id, config, &dir).await {
+            Ok(entries) => entries,
+            // Unreadable directories (permissions) don't stop the search
+            Err(_) if depth > 0 => continue,
+            Err(e) => return Err(e),
+        };
+        stats.directories += 1;
+
+        let mut found = Vec::new();
+        for (name, is_dir, is_symlink, size, modified) in entries {
+            if !query.include_hidden && name.starts_with('.') {
+                continue;
+            }
+            let path = join_remote_path(&dir, &name);
+
+            // Symlinked directories are not followed (avoids loops)
+            if is_dir && !is_symlink && descends(query, depth) {
+                queue.push_back((path.clone(), depth + 1));
+            }
+
+            let wanted = if content.is_some() { !is_dir } else { !is_dir || query.include_dirs };
+            if wanted && matcher.matches(&name) && matches_filters(query, size, modified) {
+                found.push(SearchMatch { name, path, is_dir, size, modified });
+            }
+        }
+
+        match content {
+            Some(text) => {
+                pending.extend(found);
+                while pending.len() >= GREP_BATCH_SIZE {
+                    if cancelled.load(Ordering::SeqCst) {
+                        return Ok(());
+                    }
+                    let batch: Vec<SearchMatch> = pending.drain(..GREP_BATCH_SIZE).collect();
+                    let hits = grep_files(config, text, query.case_sensitive, batch).await?;
+                    if push_matches(hits, stats, max_results, &emit) {
+                        return Ok(());
+                    }
+                }
+            }
+            None => {
+                if push_matches(found, stats, max_results, &emit) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    if let Some(text) = content {
+        if !pending.is_empty() && !cancelled.load(Ordering::SeqCst) {
+            let hits = grep_files(config, text, query.case_sensitive, pending).await?;
+            push_matches(hits, stats, max_results, &emit);
+        }
+    }
+
+    Ok(())
+}
+
+/// Emit matches up to the result limit. Returns true once the limit is reached.
+fn push_matches(
+    mut matches: Vec<SearchMatch>,
+    stats: &mut SearchStats,
+    max_results: usize,
+    emit: &impl Fn(Vec<SearchMatch>),
+) -> bool {
+    let remaining = max_results.saturating_sub(stats.matches);
+    if matches.len() >= remaining {
+        stats.truncated = matches.len() > remaining;
+        matches.truncate(remaining);
+        stats.matches += matches.len();
+        emit(matches);
+        return true;
+    }
+    stats.matches += matches.len();
+    emit(matches);
+    false
+}
+
+/// Name, is_dir, is_symlink, size and mtime of each entry in a directory
+async fn list_raw(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    dir: &str,
+) -> Result<Vec<(String, bool, bool, u64, Option<u64>)>, String> {
+    let dir = dir.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let dir = dir.clone();
+        async move {
+            let entries = entry.sftp
+                .read_dir(&dir)
+                .await
+                .map_err(|e| format!("Failed to read directory {}: {}", dir, e))?;
+            Ok(entries
+                .filter(|e| e.file_name() != "." && e.file_name() != "..")
+                .map(|e| {
+                    let attrs = e.metadata();
+                    (
+                        e.file_name(),
+                        attrs.is_dir(),
+                        attrs.is_symlink(),
+                        attrs.size.unwrap_or(0),
+                        attrs.mtime.map(|t| t as u64),
+                    )
+                })
+                .collect())
+        }
+    })
+    .await
+}
+
+/// Keep the files that contain `text`, using grep on the server
+async fn grep_files(
+    config: &SshConfig,
+    text: &str,
+    case_sensitive: bool,
+    candidates: Vec<SearchMatch>,
+) -> Result<Vec<SearchMatch>, String> {
+    if candidates.is_empty() {
+        return Ok(candidates);
+    }
+
+    let paths: Vec<String> = candidates.iter().map(|m| shell_quote(&m.path)).collect();
+    // -l: names only, -I: skip binary files, -F: fixed string
+    let command = format!(
+        "grep -lIF{} -e {} -- {} 2>/dev/null",
+        if case_sensitive { "" } else { "i" },
+        shell_quote(text),
+        paths.join(" ")
+    );
+    // grep exits with 1 when nothing matches; ssh_exec only returns the output
+    let output = ssh_exec(config, &command).await?;
+    let hits: HashSet<&str> = output.lines().collect();
+
+    Ok(candidates.into_iter().filter(|m| hits.contains(m.path.as_str())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn query() -> SearchQuery {
+        SearchQuery { root: "/".to_string(), ..Default::default() }
+    }
+
+    fn found(name: &str) -> SearchMatch {
+        SearchMatch { name: name.to_string(), path: format!("/{}", name), is_dir: false, size: 0, modified: None }
+    }
+
+    #[test]
+    fn test_matches_names_by_glob_or_regex() {
+        let mut q = query();
+        assert!(NameMatcher::new(&q).unwrap().matches("anything"));
+
+        q.name_pattern = Some("*.LOG".to_string());
+        let glob = NameMatcher::new(&q).unwrap();
+        assert!(glob.matches("app.log"));
+        assert!(!glob.matches("app.txt"));
+        q.case_sensitive = true;
+        assert!(!NameMatcher::new(&q).unwrap().matches("app.log"));
+
+        q.name_pattern = Some("^app-\\d+$".to_string());
+        q.regex = true;
+        let regex = NameMatcher::new(&q).unwrap();
+        assert!(regex.matches("app-12"));
+        assert!(!regex.matches("app-12.log"));
+
+        q.name_pattern = Some("(".to_string());
+        assert!(NameMatcher::new(&q).is_err());
+    }
+
+    #[test]
+    fn test_filters_by_size_and_mtime() {
+        let mut q = query();
+        q.min_size = Some(10);
+        q.max_size = Some(20);
+        assert!(matches_filters(&q, 10, None));
+        assert!(matches_filters(&q, 20, None));
+        assert!(!matches_filters(&q, 9, None));
+        assert!(!matches_filters(&q, 21, None));
+
+        q.modified_after = Some(100);
+        q.modified_before = Some(200);
+        assert!(matches_filters(&q, 15, Some(150)));
+        assert!(!matches_filters(&q, 15, Some(99)));
+        assert!(!matches_filters(&q, 15, Some(201)));
+        // Entries without an mtime can't satisfy a date filter
+        assert!(!matches_filters(&q, 15, None));
+    }
+
+    #[test]
+    fn test_limits_depth() {
+        let mut q = query();
+        assert!(descends(&q, 10));
+        q.max_depth = Some(1);
+        assert!(!descends(&q, 0));
+        q.max_depth = Some(2);
+        assert!(descends(&q, 0));
+        assert!(!descends(&q, 1));
+    }
+
+    #[test]
+    fn test_truncates_at_max_results() {
+        let emitted = RefCell::new(Vec::new());
+        let emit = |matches: Vec<SearchMatch>| emitted.borrow_mut().extend(matches.into_iter().map(|m| m.name));
+        let mut stats = SearchStats::default();
+
+        assert!(!push_matches(vec![found("a"), found("b")], &mut stats, 3, &emit));
+        assert!(push_matches(vec![found("c"), found("d")], &mut stats, 3, &emit));
+        assert_eq!(*emitted.borrow(), vec!["a", "b", "c"]);
+        assert_eq!(stats.matches, 3);
+        assert!(stats.truncated);
+
+        // Reaching the limit exactly isn't a truncation
+        let mut stats = SearchStats::default();
+        assert!(push_matches(vec![found("a")], &mut stats, 1, &emit));
+        assert!(!stats.truncated);
+    }
+}
@@ -7,11 +7,10 @@ use russh::keys::key::PublicKey;
 use russh::ChannelMsg;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::mpsc as std_mpsc;
 use std::time::Instant;
 use tokio::sync::mpsc as tokio_mpsc;
 
-use crate::session::{OutputMessage, Session};
+use crate::session::{OutputMessage, OutputSender, Session};
 use super::known_hosts::{verify_host_key, HostKeyVerification, store_pending_key};
 
 /// Jump host (bastion) configuration
@@ -167,6 +166,10 @@ lazy_static::lazy_static! {
 /// Maximum time a cached session can wait for user confirmation (2 minutes)
 const CACHE_TTL_SECS: u64 = 120;
 
+/// Channel window of terminal connections (russh defaults to 2 MB): less
+/// output is in flight while a session is paused by flow control
+const PTY_WINDOW_SIZE: u32 = 512 * 1024;
+
 fn cache_session(cache_id: String, conn: CachedSshConnection) {
     let mut cache = SSH_SESSION_CACHE.lock();
     // Evict expired entries while we're at it
@@ -282,7 +285,10 @@ async fn authenticate_session<H: Handler + Send>(
 async fn establish_connection(
     config: &SshConfig,
 ) -> Result<(Handle<SshHandler>, Option<Handle<SshHandler>>, HostKeyCheckResult), String> {
-    let ssh_config = Arc::new(Config::default());
+    let ssh_config = Arc::new(Config {
+        window_size: PTY_WINDOW_SIZE,
+        ..Default::default()
+    });
     let key_check = Arc::new(SyncMutex::new(None));
 
     let (session, jump_session) = if let Some(ref jump) = config.jump_host {
@@ -362,7 +368,7 @@ async fn setup_pty_session(
     jump_session: Option<Handle<SshHandler>>,
     config: &SshConfig,
     session_id: String,
-    output_tx: std_mpsc::Sender<OutputMessage>,
+    output_tx: OutputSender,
     on_exit: impl FnOnce() + Send + 'static,
 ) -> Result<SshSession, String> {
     authenticate_session(&mut session, &config.username, &config.auth).await?;
@@ -389,8 +395,11 @@ async fn setup_pty_session(
         let _jump = jump_session;
 
         loop {
+            // While the output isn't drained the channel isn't read, so
+            // russh stops consuming the window and the server holds back
+            let paused = output_tx.is_paused(&session_id);
             tokio::select! {
-                msg = channel.wait() => {
+                msg = channel.wait(), if !paused => {
                     match msg {
                         Some(ChannelMsg::Data { data }) => {
                             let _ = output_tx.send(OutputMessage {
@@ -410,6 +419,7 @@ async fn setup_pty_session(
                         _ => {}
                     }
                 }
+                _ = output_tx.ready(&session_id), if paused => {}
                 Some(cmd) = cmd_rx.recv() => {
                     match cmd {
                         SshCommand::Data(data) => {
@@ -443,7 +453,7 @@ async fn setup_pty_session(
 pub async fn connect_ssh(
     config: SshConfig,
     session_id: String,
-    output_tx: std_mpsc::Sender<OutputMessage>,
+    output_tx: OutputSender,
     on_exit: impl FnOnce() + Send + 'static,
 ) -> Result<(SshConnectionResult, Option<SshSession>), String> {
     let (session, jump_session, check_result) = establish_connection(&config).await?;
@@ -501,7 +511,7 @@ pub async fn check_host_key_only(host: &str, port: u16) -> HostKeyCheckResult {
 pub async fn finalize_cached_ssh(
     cache_id: &str,
     session_id: String,
-    output_tx: std_mpsc::Sender<OutputMessage>,
+    output_tx: OutputSender,
     on_exit: impl FnOnce() + Send + 'static,
 ) -> Result<SshSession, String> {
     let cached = take_cached_session(cache_id)
@@ -6,9 +6,9 @@
 //! SECURITY: Host key verification is enforced. Only hosts that have been
 //! previously trusted (via the main SSH connection flow) will be accepted.
 
-use russh::client::{self, Config, Handler};
+use russh::client::{self, Config, Handle, Handler};
 use russh::keys::key::PublicKey;
-use russh::ChannelMsg;
+use russh::{Channel, ChannelMsg};
 use std::sync::Arc;
 use async_trait::async_trait;
 
@@ -16,7 +16,7 @@ use super::{SshAuth, SshConfig, load_ssh_key};
 use super::known_hosts::{verify_host_key, HostKeyVerification};
 
 /// Handler for exec connections with host key verification
-struct ExecHandler {
+pub(crate) struct ExecHandler {
     host: String,
     port: u16,
 }
@@ -49,12 +49,60 @@ impl Handler for ExecHandler {
     }
 }
 
-/// Execute a command on an SSH server and return the output
+/// Connect, authenticate and start `command` on a new exec channel.
+/// The returned handle keeps the connection alive while the channel is used.
 ///
-/// SECURITY: This function only works with hosts that have been previously
-/// trusted via the main SSH connection flow. Unknown or mismatched host keys
-/// will cause the connection to fail.
-pub async fn ssh_exec(config: &SshConfig, command: &str) -> Result<String, String> {
+/// SECURITY: Same host key requirements as `ssh_exec`.
+pub(crate) async fn exec_channel(
+    config: &SshConfig,
+    command: &str,
+) -> Result<(Handle<ExecHandler>, Channel<client::Msg>), String> {
+    let session = connect_authenticated(config).await?;
+
+    // Open exec channel (not PTY)
+    let channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Failed to open channel: {}", e))?;
+
+    // Execute command
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to exec: {}", e))?;
+
+    Ok((session, channel))
+}
+
+/// Like `exec_channel`, with a PTY for commands that need a terminal
+/// (e.g. `tmux -CC`)
+pub(crate) async fn exec_pty_channel(
+    config: &SshConfig,
+    command: &str,
+    cols: u32,
+    rows: u32,
+) -> Result<(Handle<ExecHandler>, Channel<client::Msg>), String> {
+    let session = connect_authenticated(config).await?;
+
+    let channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Failed to open channel: {}", e))?;
+
+    channel
+        .request_pty(false, "xterm-256color", cols, rows, 0, 0, &[])
+        .await
+        .map_err(|e| format!("Failed to request PTY: {}", e))?;
+
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to exec: {}", e))?;
+
+    Ok((session, channel))
+}
+
+async fn connect_authenticated(config: &SshConfig) -> Result<Handle<ExecHandler>, String> {
     let ssh_config = Config::default();
     let handler = ExecHandler {
         host: config.host.clone(),
@@ -87,17 +135,16 @@ pub async fn ssh_exec(config: &SshConfig, command: &str) -> Result<String, Strin
         return Err("Authentication failed".to_string());
     }
 
-    // Open exec channel (not PTY)
-    let mut channel = session
-        .channel_open_session()
-        .await
-        .map_err(|e| format!("Failed to open channel: {}", e))?;
+    Ok(session)
+}
 
-    // Execute command
-    channel
-        .exec(true, command)
-        .await
-        .map_err(|e| format!("Failed to exec: {}", e))?;
+/// Execute a command on an SSH server and return the output
+///
+/// SECURITY: This function only works with hosts that have been previously
+/// trusted via the main SSH connection flow. Unknown or mismatched host keys
+/// will cause the connection to fail.
+pub async fn ssh_exec(config: &SshConfig, command: &str) -> Result<String, String> {
+    let (_session, mut channel) = exec_channel(config, command).await?;
 
     // Collect output
     let mut output = Vec::new();
@@ -120,6 +167,11 @@ pub async fn ssh_exec(config: &SshConfig, command: &str) -> Result<String, Strin
     String::from_utf8(output).map_err(|e| format!("Invalid UTF-8: {}", e))
 }
 
+/// Quote a string for safe use as a single POSIX shell argument
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
 /// Server statistics exposed to plugins
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 pub struct ServerStats {
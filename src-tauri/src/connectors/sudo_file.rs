@@ -0,0 +1,246 @@
+//! Privileged file access through sudo
+//!
+//! Root-owned files (mostly configuration) can't be written over SFTP as the
+//! login user. They are read with `sudo cat`, and written by staging the new
+//! content in a private temp file that root then installs next to the target
+//! and renames over it, keeping the original owner and mode.
+//!
+//! The sudo password is sent on stdin (`sudo -S`). Without one, sudo runs
+//! non-interactively and fails with `SUDO_PASSWORD_REQUIRED` if it needs one.
+
+use russh::ChannelMsg;
+
+use super::scp::scp_write_file;
+use super::sftp::{sftp_write_file, uses_scp};
+use super::ssh_exec::{exec_channel, shell_quote, ssh_exec};
+use super::{SftpPool, SshConfig};
+
+/// Error returned when sudo asks for a password and none was given
+pub const SUDO_PASSWORD_REQUIRED: &str = "sudo password required";
+
+/// Credentials for running commands through sudo
+#[derive(Clone, Default)]
+pub struct SudoAuth {
+    /// None when sudo is passwordless (NOPASSWD or cached credentials)
+    pub password: Option<String>,
+}
+
+impl std::fmt::Debug for SudoAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SudoAuth")
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// Output of a command run through sudo
+struct SudoOutput {
+    stdout: Vec<u8>,
+    stderr: String,
+    exit_status: Option<u32>,
+}
+
+/// Run `command` as root on a new exec channel
+async fn sudo_run(config: &SshConfig, sudo: &SudoAuth, command: &str) -> Result<SudoOutput, String> {
+    // -p '': no prompt mixed into the output, -n: fail instead of waiting for input
+    let full = match sudo.password {
+        Some(_) => format!("sudo -S -p '' -- {}", command),
+        None => format!("sudo -n -- {}", command),
+    };
+    let (_session, mut channel) = exec_channel(config, &full).await?;
+
+    if let Some(password) = &sudo.password {
+        let line = format!("{}\n", password);
+        channel
+            .data(line.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to send sudo password: {}", e))?;
+    }
+    let _ = channel.eof().await;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_status = None;
+    // The exit status may arrive after EOF: read until the channel closes
+    loop {
+        match channel.wait().await {
+            Some(ChannelMsg::Data { data }) => stdout.extend_from_slice(&data),
+            Some(ChannelMsg::ExtendedData { data, .. }) => stderr.extend_from_slice(&data),
+            Some(ChannelMsg::ExitStatus { exit_status: status }) => exit_status = Some(status),
+            Some(ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+
+    Ok(SudoOutput {
+        stdout,
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+        exit_status,
+    })
+}
+
+/// Run `command` as the login user and return its stdout only, so warnings
+/// printed by login scripts don't end up in the result
+async fn exec_stdout(config: &SshConfig, command: &str) -> Result<String, String> {
+    let (_session, mut channel) = exec_channel(config, command).await?;
+
+    let mut stdout = Vec::new();
+    loop {
+        match channel.wait().await {
+            Some(ChannelMsg::Data { data }) => stdout.extend_from_slice(&data),
+            Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => break,
+            _ => {}
+        }
+    }
+
+    String::from_utf8(stdout).map_err(|e| format!("Invalid UTF-8: {}", e))
+}
+
+/// Turn a failed sudo invocation into a readable error
+fn sudo_error(what: &str, stderr: &str, exit_status: Option<u32>) -> String {
+    let lower = stderr.to_lowercase();
+    if lower.contains("a password is required") {
+        SUDO_PASSWORD_REQUIRED.to_string()
+    } else if lower.contains("incorrect password")
+        || lower.contains("sorry, try again")
+        || lower.contains("no password was provided")
+    {
+        "Incorrect sudo password".to_string()
+    } else if lower.contains("not in the sudoers") || lower.contains("not allowed to") {
+        "This user is not allowed to use sudo on the server".to_string()
+    } else if exit_status == Some(127) && lower.contains("sudo") {
+        "sudo is not installed on the server".to_string()
+    } else if stderr.trim().is_empty() {
+        format!("{} (exit status {})", what, exit_status.map_or("unknown".to_string(), |s| s.to_string()))
+    } else {
+        format!("{}: {}", what, stderr.trim())
+    }
+}
+
+/// Read a remote file as root
+pub async fn sudo_read_file(config: &SshConfig, sudo: &SudoAuth, remote_path: &str) -> Result<Vec<u8>, String> {
+    let output = sudo_run(config, sudo, &format!("cat -- {}", shell_quote(remote_path))).await?;
+    match output.exit_status {
+        Some(0) => Ok(output.stdout),
+        status => Err(sudo_error("Failed to read file", &output.stderr, status)),
+    }
+}
+
+/// Size and mtime of a remote file as root, or None if it doesn't exist
+pub async fn sudo_stat(config: &SshConfig, sudo: &SudoAuth, remote_path: &str) -> Result<Option<(u64, Option<u64>)>, String> {
+    let output = sudo_run(config, sudo, &format!("stat -L -c '%s %Y' -- {}", shell_quote(remote_path))).await?;
+    match output.exit_status {
+        Some(0) => parse_size_mtime(&String::from_utf8_lossy(&output.stdout))
+            .map(Some)
+            .ok_or_else(|| "Unexpected stat output".to_string()),
+        _ if output.stderr.contains("No such file or directory") => Ok(None),
+        status => Err(sudo_error("Failed to stat file", &output.stderr, status)),
+    }
+}
+
+/// Parse `stat -c '%s %Y'` output
+fn parse_size_mtime(output: &str) -> Option<(u64, Option<u64>)> {
+    let mut parts = output.split_whitespace();
+    let size = parts.next()?.parse().ok()?;
+    Some((size, parts.next().and_then(|t| t.parse().ok())))
+}
+
+/// Shell script (run as root) that replaces `target` with the content of
+/// `staged`. The new file is written next to the target and renamed over it,
+/// so readers never see a partial file. Symlinks are resolved to write the
+/// file they point to; new files get mode 644.
+fn install_script(staged: &str, target: &str) -> String {
+    format!(
+        "set -e; \
+         t={target}; \
+         if [ -L \"$t\" ]; then t=$(readlink -f -- \"$t\"); fi; \
+         n=$(mktemp \"$t.XXXXXX\"); \
+         trap 'rm -f -- \"$n\"' EXIT; \
+         if [ -e \"$t\" ]; then \
+         chown \"$(stat -c '%u:%g' -- \"$t\")\" \"$n\"; \
+         chmod \"$(stat -c '%a' -- \"$t\")\" \"$n\"; \
+         else chmod 644 \"$n\"; fi; \
+         cat -- {staged} > \"$n\"; \
+         mv -f -- \"$n\" \"$t\"",
+        target = shell_quote(target),
+        staged = shell_quote(staged),
+    )
+}
+
+/// Atomically replace a remote file as root, preserving its owner and mode
+pub async fn sudo_write_file(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    sudo: &SudoAuth,
+    remote_path: &str,
+    data: Vec<u8>,
+) -> Result<(), String> {
+    // mktemp creates the staging file with mode 600 as the login user
+    let staged = exec_stdout(config, "mktemp").await?.trim().to_string();
+    if !staged.starts_with('/') {
+        return Err(format!("Failed to create a temporary file: {}", staged));
+    }
+
+    let result = async {
+        // Servers without SFTP: the staging file is uploaded over SCP
+        if uses_scp(pool, session_id, config).await {
+            scp_write_file(config, &staged, &data).await?;
+        } else {
+            sftp_write_file(pool, session_id, config, &staged, data).await?;
+        }
+        let script = install_script(&staged, remote_path);
+        let output = sudo_run(config, sudo, &format!("sh -c {}", shell_quote(&script))).await?;
+        match output.exit_status {
+            Some(0) => Ok(()),
+            status => Err(sudo_error("Failed to write file", &output.stderr, status)),
+        }
+    }
+    .await;
+
+    // The staging file belongs to the login user: no sudo needed
+    if let Err(e) = ssh_exec(config, &format!("rm -f -- {}", shell_quote(&staged))).await {
+        eprintln!("[Sudo] Failed to remove staging file {}: {}", staged, e);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_sudo_errors() {
+        assert_eq!(
+            sudo_error("Failed", "sudo: a password is required\n", Some(1)),
+            SUDO_PASSWORD_REQUIRED
+        );
+        assert_eq!(
+            sudo_error("Failed", "Sorry, try again.\nsudo: 1 incorrect password attempt\n", Some(1)),
+            "Incorrect sudo password"
+        );
+        assert_eq!(
+            sudo_error("Failed", "bob is not in the sudoers file.\n", Some(1)),
+            "This user is not allowed to use sudo on the server"
+        );
+        assert_eq!(
+            sudo_error("Failed to read file", "cat: /etc/x: No such file or directory\n", Some(1)),
+            "Failed to read file: cat: /etc/x: No such file or directory"
+        );
+    }
+
+    #[test]
+    fn test_parses_stat_output() {
+        assert_eq!(parse_size_mtime("1234 1700000000\n"), Some((1234, Some(1700000000))));
+        assert_eq!(parse_size_mtime("0 ?"), Some((0, None)));
+        assert_eq!(parse_size_mtime(""), None);
+    }
+
+    #[test]
+    fn test_install_script_quotes_paths() {
+        let script = install_script("/tmp/tmp.abc", "/etc/it's here.conf");
+        assert!(script.contains("t='/etc/it'\\''s here.conf'"));
+        assert!(script.contains("cat -- '/tmp/tmp.abc' > \"$n\""));
+        assert!(script.ends_with("mv -f -- \"$n\" \"$t\""));
+    }
+}
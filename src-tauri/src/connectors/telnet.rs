@@ -1,12 +1,11 @@
 //! Telnet connector (RFC 854) with NAWS support for window resizing
 
 use parking_lot::Mutex as SyncMutex;
-use std::sync::mpsc as std_mpsc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::sync::mpsc as tokio_mpsc;
 
-use crate::session::{OutputMessage, Session};
+use crate::session::{OutputMessage, OutputSender, Session};
 
 /// Codes IAC (Interpret As Command) Telnet - RFC 854
 mod iac {
@@ -175,7 +174,7 @@ pub async fn connect_telnet(
     host: String,
     port: u16,
     session_id: String,
-    output_tx: std_mpsc::Sender<OutputMessage>,
+    output_tx: OutputSender,
     on_exit: impl FnOnce() + Send + 'static,
 ) -> Result<TelnetSession, String> {
     let addr = format!("{}:{}", host, port);
@@ -195,9 +194,10 @@ pub async fn connect_telnet(
         let mut current_rows: u16 = 24;
 
         loop {
+            let paused = output_tx.is_paused(&session_id);
             tokio::select! {
-                // Lecture depuis le serveur
-                result = reader.read(&mut buf) => {
+                // Lecture depuis le serveur (suspendue tant que la sortie n'est pas écoulée)
+                result = reader.read(&mut buf), if !paused => {
                     match result {
                         Ok(0) => break,
                         Ok(n) => {
@@ -224,6 +224,7 @@ pub async fn connect_telnet(
                         Err(_) => break,
                     }
                 }
+                _ = output_tx.ready(&session_id), if paused => {}
                 Some(cmd) = cmd_rx.recv() => {
                     match cmd {
                         TelnetCommand::Data(data) => {
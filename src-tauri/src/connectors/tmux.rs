@@ -0,0 +1,571 @@
+//! tmux control mode connector
+//!
+//! Runs `tmux -CC` on a host over its own SSH connection and maps each tmux
+//! pane to a native session: pane output goes to the session's output
+//! stream, and input and resizes of the session become tmux commands. The
+//! layout of windows and panes is listed after attaching and whenever tmux
+//! reports a change. Pane session ids only depend on the control id and
+//! the tmux pane id, so attaching again (e.g. after a reconnect) brings
+//! back the same sessions, repainted from the panes' content.
+
+use parking_lot::Mutex as SyncMutex;
+use russh::ChannelMsg;
+use std::collections::{HashMap, HashSet, VecDeque};
+use tokio::sync::mpsc as tokio_mpsc;
+
+use super::ssh_exec::{exec_pty_channel, shell_quote};
+use super::tmux_control::{parse_layout, ControlParser, Notification};
+use super::SshConfig;
+use crate::session::{OutputMessage, OutputSender, Session};
+
+/// Lines of history repainted when a pane is attached
+const CAPTURE_HISTORY: u32 = 1000;
+/// Input bytes per `send-keys` command
+const SEND_KEYS_CHUNK: usize = 256;
+/// Format of the `list-panes` reply (the window name last: it may hold spaces)
+const PANE_FORMAT: &str =
+    "#{pane_id} #{window_id} #{window_index} #{window_active} #{pane_active} #{cursor_x} #{cursor_y} #{window_layout} #{window_name}";
+
+lazy_static::lazy_static! {
+    /// Control mode clients, keyed by control id. Kept after the connection
+    /// is lost, so it can be attached again.
+    static ref TMUX_CONTROLS: SyncMutex<HashMap<String, TmuxControl>> = SyncMutex::new(HashMap::new());
+}
+
+/// What to attach to, kept for reattaching
+#[derive(Debug, Clone)]
+struct TmuxTarget {
+    config: SshConfig,
+    tmux_session: Option<String>,
+}
+
+/// Commands for the connection, with what their reply is for
+type CommandSender = tokio_mpsc::UnboundedSender<(String, Pending)>;
+
+struct TmuxControl {
+    target: TmuxTarget,
+    /// Commands for the connection, while it is up
+    cmd_tx: Option<CommandSender>,
+    layout: Option<TmuxLayout>,
+    /// Detaching on request: forgotten once the connection ends
+    detached: bool,
+}
+
+/// A tmux pane in its window
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TmuxPaneInfo {
+    /// tmux pane id (`%N`)
+    pub pane_id: String,
+    /// Native session of the pane
+    pub session_id: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TmuxWindow {
+    /// tmux window id (`@N`)
+    pub window_id: String,
+    pub index: u32,
+    pub name: String,
+    pub active: bool,
+    /// tmux layout string (`#{window_layout}`)
+    pub layout: String,
+    pub panes: Vec<TmuxPaneInfo>,
+}
+
+/// Windows and panes of an attached tmux session
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TmuxLayout {
+    pub control_id: String,
+    pub session_name: Option<String>,
+    pub windows: Vec<TmuxWindow>,
+}
+
+/// What the connection reports to its owner
+pub enum TmuxEvent {
+    /// A pane appeared (or was found when attaching): register its session
+    PaneOpened { session_id: String, pane: TmuxPane },
+    /// A pane is gone, or the connection was lost
+    PaneClosed { session_id: String },
+    /// The layout changed (sent after the panes it holds are opened)
+    Layout(TmuxLayout),
+    /// The client detached, tmux exited or the connection was lost
+    Exit { reason: Option<String> },
+}
+
+/// Native session of a tmux pane
+pub struct TmuxPane {
+    pane_id: String,
+    cmd_tx: CommandSender,
+}
+
+impl std::fmt::Debug for TmuxPane {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TmuxPane")
+            .field("pane_id", &self.pane_id)
+            .finish()
+    }
+}
+
+impl TmuxPane {
+    fn command(&self, command: String) -> Result<(), String> {
+        self.cmd_tx
+            .send((command, Pending::Ignore))
+            .map_err(|_| "tmux connection closed".to_string())
+    }
+}
+
+impl Session for TmuxPane {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        for chunk in data.chunks(SEND_KEYS_CHUNK) {
+            self.command(send_keys_command(&self.pane_id, chunk))?;
+        }
+        Ok(())
+    }
+
+    fn resize(&self, cols: u32, rows: u32) -> Result<(), String> {
+        self.command(format!("resize-pane -t {} -x {} -y {}", self.pane_id, cols, rows))
+    }
+
+    fn session_type(&self) -> &'static str {
+        "tmux"
+    }
+
+    /// The pane keeps running in tmux (it comes back when attaching again)
+    fn close(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// `send-keys` of raw bytes, as hex so any byte goes through (tmux 3.0+)
+fn send_keys_command(pane_id: &str, data: &[u8]) -> String {
+    let mut command = format!("send-keys -t {} -H", pane_id);
+    for byte in data {
+        command.push_str(&format!(" {:02x}", byte));
+    }
+    command
+}
+
+/// Native session id of a pane
+pub fn pane_session_id(control_id: &str, pane_id: &str) -> String {
+    format!("{}:{}", control_id, pane_id)
+}
+
+/// A command sent, waiting for its reply
+enum Pending {
+    ListPanes,
+    Capture { pane_id: String, cursor: (u32, u32) },
+    Ignore,
+}
+
+/// A pane as listed by `list-panes`
+struct ListedPane {
+    pane_id: String,
+    window_id: String,
+    window_index: u32,
+    window_active: bool,
+    pane_active: bool,
+    cursor: (u32, u32),
+    layout: String,
+    window_name: String,
+}
+
+fn parse_pane_line(line: &str) -> Option<ListedPane> {
+    let mut fields = line.splitn(9, ' ');
+    Some(ListedPane {
+        pane_id: fields.next()?.to_string(),
+        window_id: fields.next()?.to_string(),
+        window_index: fields.next()?.parse().ok()?,
+        window_active: fields.next()? == "1",
+        pane_active: fields.next()? == "1",
+        cursor: (fields.next()?.parse().ok()?, fields.next()?.parse().ok()?),
+        layout: fields.next()?.to_string(),
+        window_name: fields.next().unwrap_or("").to_string(),
+    })
+}
+
+/// Build the layout from a `list-panes` reply
+fn build_layout(control_id: &str, session_name: Option<String>, panes: &[ListedPane]) -> TmuxLayout {
+    let mut windows: Vec<TmuxWindow> = Vec::new();
+    for listed in panes {
+        if windows.iter().any(|w| w.window_id == listed.window_id) {
+            continue;
+        }
+        let geometry = parse_layout(&listed.layout).unwrap_or_else(|e| {
+            eprintln!("[Tmux] {}", e);
+            Vec::new()
+        });
+        let panes = panes
+            .iter()
+            .filter(|p| p.window_id == listed.window_id)
+            .map(|p| {
+                let number = p.pane_id.trim_start_matches('%').parse::<u32>().ok();
+                let geometry = geometry.iter().find(|g| Some(g.pane) == number);
+                TmuxPaneInfo {
+                    pane_id: p.pane_id.clone(),
+                    session_id: pane_session_id(control_id, &p.pane_id),
+                    x: geometry.map_or(0, |g| g.x),
+                    y: geometry.map_or(0, |g| g.y),
+                    width: geometry.map_or(0, |g| g.width),
+                    height: geometry.map_or(0, |g| g.height),
+                    active: p.pane_active,
+                }
+            })
+            .collect();
+        windows.push(TmuxWindow {
+            window_id: listed.window_id.clone(),
+            index: listed.window_index,
+            name: listed.window_name.clone(),
+            active: listed.window_active,
+            layout: listed.layout.clone(),
+            panes,
+        });
+    }
+    windows.sort_by_key(|w| w.index);
+    TmuxLayout {
+        control_id: control_id.to_string(),
+        session_name,
+        windows,
+    }
+}
+
+/// State of the connection task
+struct Connection {
+    control_id: String,
+    cmd_tx: CommandSender,
+    output_tx: OutputSender,
+    on_event: Box<dyn Fn(TmuxEvent) + Send + Sync>,
+    /// Commands written, oldest first, waiting for their reply
+    pending: VecDeque<Pending>,
+    /// Panes with a session, by pane id
+    panes: HashSet<String>,
+    /// Output of panes being repainted, sent after their content
+    capturing: HashMap<String, Vec<u8>>,
+    session_name: Option<String>,
+    /// A `list-panes` is pending, and another is needed once it's answered
+    refreshing: bool,
+    refresh_again: bool,
+}
+
+impl Connection {
+    /// Queue a command (written, and its reply expected, in order with the
+    /// input and commands of pane sessions)
+    fn send(&self, command: String, pending: Pending) {
+        let _ = self.cmd_tx.send((command, pending));
+    }
+
+    /// List the panes again, unless it is already being done
+    fn refresh(&mut self) {
+        if self.refreshing {
+            self.refresh_again = true;
+            return;
+        }
+        self.refreshing = true;
+        self.send(format!("list-panes -s -F {}", shell_quote(PANE_FORMAT)), Pending::ListPanes);
+    }
+
+    fn output(&self, pane_id: &str, data: Vec<u8>) {
+        let _ = self.output_tx.send(OutputMessage {
+            session_id: pane_session_id(&self.control_id, pane_id),
+            data,
+        });
+    }
+
+    fn handle(&mut self, notification: Notification) {
+        match notification {
+            Notification::Output { pane, data } => {
+                if let Some(buffered) = self.capturing.get_mut(&pane) {
+                    buffered.extend_from_slice(&data);
+                } else if self.panes.contains(&pane) {
+                    self.output(&pane, data);
+                }
+            }
+            Notification::Reply { lines, error } => match self.pending.pop_front() {
+                Some(Pending::ListPanes) => {
+                    self.refreshing = false;
+                    if error {
+                        eprintln!("[Tmux] list-panes failed: {}", lines.join(" "));
+                    } else {
+                        self.panes_listed(&lines);
+                    }
+                    if std::mem::take(&mut self.refresh_again) {
+                        self.refresh();
+                    }
+                }
+                Some(Pending::Capture { pane_id, cursor }) => {
+                    let buffered = self.capturing.remove(&pane_id).unwrap_or_default();
+                    if !error {
+                        let mut repaint = lines.join("\r\n").into_bytes();
+                        repaint.extend_from_slice(format!("\x1b[{};{}H", cursor.1 + 1, cursor.0 + 1).as_bytes());
+                        self.output(&pane_id, repaint);
+                    }
+                    if !buffered.is_empty() {
+                        self.output(&pane_id, buffered);
+                    }
+                }
+                Some(Pending::Ignore) => {
+                    if error {
+                        eprintln!("[Tmux] Command failed: {}", lines.join(" "));
+                    }
+                }
+                // Reply to the command tmux was started with
+                None => {}
+            },
+            Notification::LayoutChanged => self.refresh(),
+            Notification::SessionChanged { name } => {
+                self.session_name = Some(name);
+                self.refresh();
+            }
+            // Handled by the connection loop
+            Notification::Exit { .. } => {}
+        }
+    }
+
+    fn panes_listed(&mut self, lines: &[String]) {
+        let listed: Vec<ListedPane> = lines.iter().filter_map(|line| parse_pane_line(line)).collect();
+        let current: HashSet<String> = listed.iter().map(|p| p.pane_id.clone()).collect();
+
+        let closed: Vec<String> = self.panes.difference(&current).cloned().collect();
+        for pane_id in closed {
+            self.panes.remove(&pane_id);
+            self.capturing.remove(&pane_id);
+            (self.on_event)(TmuxEvent::PaneClosed {
+                session_id: pane_session_id(&self.control_id, &pane_id),
+            });
+        }
+
+        for pane in &listed {
+            if !self.panes.insert(pane.pane_id.clone()) {
+                continue;
+            }
+            // Repaint the pane, holding back its output until then
+            self.capturing.insert(pane.pane_id.clone(), Vec::new());
+            self.send(
+                format!("capture-pane -p -e -t {} -S -{}", pane.pane_id, CAPTURE_HISTORY),
+                Pending::Capture { pane_id: pane.pane_id.clone(), cursor: pane.cursor },
+            );
+            (self.on_event)(TmuxEvent::PaneOpened {
+                session_id: pane_session_id(&self.control_id, &pane.pane_id),
+                pane: TmuxPane { pane_id: pane.pane_id.clone(), cmd_tx: self.cmd_tx.clone() },
+            });
+        }
+
+        let layout = build_layout(&self.control_id, self.session_name.clone(), &listed);
+        if let Some(control) = TMUX_CONTROLS.lock().get_mut(&self.control_id) {
+            control.layout = Some(layout.clone());
+        }
+        (self.on_event)(TmuxEvent::Layout(layout));
+    }
+
+    /// Close the sessions of all panes
+    fn close_panes(&mut self) {
+        for pane_id in self.panes.drain() {
+            (self.on_event)(TmuxEvent::PaneClosed {
+                session_id: pane_session_id(&self.control_id, &pane_id),
+            });
+        }
+        self.capturing.clear();
+    }
+}
+
+/// Attach to tmux on the host of `config`: the named session (created if
+/// needed), or the most recent one (a new one when there is none)
+pub async fn attach_tmux(
+    control_id: String,
+    config: SshConfig,
+    tmux_session: Option<String>,
+    output_tx: OutputSender,
+    on_event: impl Fn(TmuxEvent) + Send + Sync + 'static,
+) -> Result<(), String> {
+    if TMUX_CONTROLS.lock().get(&control_id).map_or(false, |c| c.cmd_tx.is_some()) {
+        return Err(format!("tmux client {} is already attached", control_id));
+    }
+
+    let command = match &tmux_session {
+        Some(name) => format!("tmux -CC new-session -A -s {}", shell_quote(name)),
+        None => "tmux -CC attach-session || tmux -CC new-session".to_string(),
+    };
+    let (session, mut channel) = exec_pty_channel(&config, &command, 80, 24).await?;
+
+    let (cmd_tx, mut cmd_rx) = tokio_mpsc::unbounded_channel::<(String, Pending)>();
+    TMUX_CONTROLS.lock().insert(control_id.clone(), TmuxControl {
+        target: TmuxTarget { config, tmux_session },
+        cmd_tx: Some(cmd_tx.clone()),
+        layout: None,
+        detached: false,
+    });
+
+    let mut connection = Connection {
+        control_id: control_id.clone(),
+        cmd_tx,
+        output_tx,
+        on_event: Box::new(on_event),
+        pending: VecDeque::new(),
+        panes: HashSet::new(),
+        capturing: HashMap::new(),
+        session_name: None,
+        refreshing: false,
+        refresh_again: false,
+    };
+
+    tokio::spawn(async move {
+        let _session = session;
+        let mut parser = ControlParser::new();
+        let mut reason = None;
+
+        loop {
+            // One channel carries all panes: it isn't read while any of them
+            // has too much output pending
+            let paused = connection
+                .panes
+                .iter()
+                .map(|pane_id| pane_session_id(&control_id, pane_id))
+                .find(|session_id| connection.output_tx.is_paused(session_id));
+            tokio::select! {
+                msg = channel.wait(), if paused.is_none() => {
+                    match msg {
+                        Some(ChannelMsg::Data { data }) => {
+                            let mut exited = false;
+                            for notification in parser.feed(&data) {
+                                if let Notification::Exit { reason: exit_reason } = notification {
+                                    reason = exit_reason;
+                                    exited = true;
+                                    break;
+                                }
+                                connection.handle(notification);
+                            }
+                            if exited {
+                                break;
+                            }
+                        }
+                        Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => {
+                            reason = parser.noise().map(str::to_string);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                _ = connection.output_tx.ready(paused.as_deref().unwrap_or_default()), if paused.is_some() => {}
+                Some((command, pending)) = cmd_rx.recv() => {
+                    let mut line = command.into_bytes();
+                    line.push(b'\n');
+                    if channel.data(&line[..]).await.is_err() {
+                        break;
+                    }
+                    connection.pending.push_back(pending);
+                }
+            }
+        }
+
+        let _ = channel.close().await;
+        connection.close_panes();
+        {
+            let mut controls = TMUX_CONTROLS.lock();
+            if controls.get(&control_id).map_or(false, |c| c.detached) {
+                controls.remove(&control_id);
+            } else if let Some(control) = controls.get_mut(&control_id) {
+                control.cmd_tx = None;
+            }
+        }
+        (connection.on_event)(TmuxEvent::Exit { reason });
+    });
+
+    Ok(())
+}
+
+/// Attach again with the target of an earlier attach, after the connection
+/// was lost
+pub async fn reattach_tmux(
+    control_id: String,
+    output_tx: OutputSender,
+    on_event: impl Fn(TmuxEvent) + Send + Sync + 'static,
+) -> Result<(), String> {
+    let target = TMUX_CONTROLS
+        .lock()
+        .get(&control_id)
+        .map(|control| control.target.clone())
+        .ok_or_else(|| "tmux client not found".to_string())?;
+    attach_tmux(control_id, target.config, target.tmux_session, output_tx, on_event).await
+}
+
+/// Detach the client, leaving the tmux session running, and forget it.
+/// Returns whether it was attached.
+pub fn detach_tmux(control_id: &str) -> bool {
+    let mut controls = TMUX_CONTROLS.lock();
+    let cmd_tx = match controls.get_mut(control_id) {
+        Some(control) => {
+            control.detached = true;
+            control.cmd_tx.clone()
+        }
+        None => return false,
+    };
+    match cmd_tx {
+        // The connection task forgets it once tmux confirms
+        Some(cmd_tx) => cmd_tx.send(("detach-client".to_string(), Pending::Ignore)).is_ok(),
+        None => {
+            controls.remove(control_id);
+            false
+        }
+    }
+}
+
+/// Run a tmux command (e.g. `new-window`, `split-window -h`) on the attached
+/// session. Layout changes are reported as usual.
+pub fn tmux_command(control_id: &str, command: &str) -> Result<(), String> {
+    send_control(control_id, command.to_string())
+}
+
+/// Set the size of the client, which tmux splits between the panes
+pub fn resize_tmux_client(control_id: &str, cols: u32, rows: u32) -> Result<(), String> {
+    send_control(control_id, format!("refresh-client -C {},{}", cols, rows))
+}
+
+/// Last layout of a client (also after the connection was lost)
+pub fn tmux_layout(control_id: &str) -> Option<TmuxLayout> {
+    TMUX_CONTROLS.lock().get(control_id).and_then(|control| control.layout.clone())
+}
+
+fn send_control(control_id: &str, command: String) -> Result<(), String> {
+    let controls = TMUX_CONTROLS.lock();
+    let cmd_tx = controls
+        .get(control_id)
+        .and_then(|control| control.cmd_tx.as_ref())
+        .ok_or_else(|| "tmux client not attached".to_string())?;
+    // The reply isn't waited for: the connection task logs it if it fails
+    cmd_tx
+        .send((command, Pending::Ignore))
+        .map_err(|_| "tmux connection closed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sends_input_as_hex() {
+        assert_eq!(send_keys_command("%3", b"ls\r"), "send-keys -t %3 -H 6c 73 0d");
+    }
+
+    #[test]
+    fn test_builds_layout_from_listed_panes() {
+        let lines = [
+            "%1 @1 0 1 0 0 5 d1f5,160x48,0,0{80x48,0,0,1,79x48,81,0,2} editor",
+            "%2 @1 0 1 1 3 7 d1f5,160x48,0,0{80x48,0,0,1,79x48,81,0,2} editor",
+            "%4 @2 1 0 1 0 0 a0b1,160x48,0,0,4 build logs",
+        ];
+        let listed: Vec<ListedPane> = lines.iter().filter_map(|l| parse_pane_line(l)).collect();
+        let layout = build_layout("t1", Some("work".to_string()), &listed);
+
+        assert_eq!(layout.windows.len(), 2);
+        let editor = &layout.windows[0];
+        assert_eq!(editor.panes.len(), 2);
+        assert_eq!(editor.panes[1].session_id, "t1:%2");
+        assert_eq!((editor.panes[1].x, editor.panes[1].width), (81, 79));
+        assert!(editor.panes[1].active && editor.active);
+        assert_eq!(layout.windows[1].name, "build logs");
+    }
+}
@@ -0,0 +1,321 @@
+//! tmux control mode protocol (`tmux -CC`)
+//!
+//! In control mode tmux reports what happens in its session as lines of
+//! text: `%output` carries what a pane printed (control characters and `\`
+//! octal-escaped), `%begin` ... `%end` (or `%error`) enclose the reply to
+//! each command sent, in order, and other notifications tell about windows,
+//! layouts and the session. With `-CC` the whole stream is wrapped in a DCS
+//! sequence, which is skipped.
+
+/// Start of the DCS sequence wrapping the stream with `-CC`
+const DCS_START: &[u8] = b"\x1bP1000p";
+/// String terminator ending it
+const DCS_END: &[u8] = b"\x1b\\";
+
+/// A notification from tmux
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    /// Output of a pane (`%N`)
+    Output { pane: String, data: Vec<u8> },
+    /// Reply to the oldest command not answered yet
+    Reply { lines: Vec<String>, error: bool },
+    /// Windows or panes were added, closed, renamed, resized or focused
+    LayoutChanged,
+    /// The client is now attached to this session
+    SessionChanged { name: String },
+    /// The client is detaching (or tmux exited)
+    Exit { reason: Option<String> },
+}
+
+/// Splits the control mode stream into notifications
+#[derive(Debug, Default)]
+pub struct ControlParser {
+    line: Vec<u8>,
+    /// Lines of the reply being received
+    reply: Option<Vec<String>>,
+    /// Arguments of its `%begin` (`<time> <number> <flags>`), repeated by the
+    /// `%end` or `%error` closing it
+    reply_guard: Vec<u8>,
+    /// Last line that wasn't part of the protocol (e.g. an error of the shell
+    /// before tmux started)
+    noise: Option<String>,
+}
+
+impl ControlParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Notification> {
+        let mut notifications = Vec::new();
+        for &byte in data {
+            if byte == b'\n' {
+                let mut line = std::mem::take(&mut self.line);
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                if let Some(notification) = self.parse_line(&line) {
+                    notifications.push(notification);
+                }
+            } else {
+                self.line.push(byte);
+            }
+        }
+        notifications
+    }
+
+    /// Last line received that wasn't part of the protocol
+    pub fn noise(&self) -> Option<&str> {
+        self.noise.as_deref()
+    }
+
+    fn parse_line(&mut self, line: &[u8]) -> Option<Notification> {
+        let line = strip_dcs(line);
+
+        if let Some(reply) = self.reply.as_mut() {
+            // Output of the command may itself start with `%end`: only the
+            // line repeating the `%begin` arguments closes the reply
+            let closing = [&b"%end "[..], b"%error "]
+                .into_iter()
+                .find(|prefix| line.strip_prefix(*prefix) == Some(self.reply_guard.as_slice()));
+            if let Some(prefix) = closing {
+                let lines = self.reply.take().unwrap_or_default();
+                return Some(Notification::Reply { lines, error: prefix.starts_with(b"%error") });
+            }
+            reply.push(String::from_utf8_lossy(line).into_owned());
+            return None;
+        }
+
+        if !line.starts_with(b"%") {
+            if !line.is_empty() {
+                self.noise = Some(String::from_utf8_lossy(line).trim().to_string());
+            }
+            return None;
+        }
+
+        let (name, args) = match line.iter().position(|&b| b == b' ') {
+            Some(space) => (&line[..space], &line[space + 1..]),
+            None => (line, &[][..]),
+        };
+        match name {
+            b"%begin" => {
+                self.reply = Some(Vec::new());
+                self.reply_guard = args.to_vec();
+                None
+            }
+            b"%output" => {
+                let space = args.iter().position(|&b| b == b' ')?;
+                Some(Notification::Output {
+                    pane: String::from_utf8_lossy(&args[..space]).into_owned(),
+                    data: unescape(&args[space + 1..]),
+                })
+            }
+            // %extended-output %N age ... : data (when pausing panes)
+            b"%extended-output" => {
+                let space = args.iter().position(|&b| b == b' ')?;
+                let colon = args.windows(3).position(|w| w == b" : ")?;
+                Some(Notification::Output {
+                    pane: String::from_utf8_lossy(&args[..space]).into_owned(),
+                    data: unescape(&args[colon + 3..]),
+                })
+            }
+            b"%layout-change" | b"%window-add" | b"%window-close" | b"%unlinked-window-close"
+            | b"%window-renamed" | b"%window-pane-changed" | b"%session-window-changed" => {
+                Some(Notification::LayoutChanged)
+            }
+            b"%session-changed" => {
+                // %session-changed $id name
+                let text = String::from_utf8_lossy(args);
+                let name = text.split_once(' ').map_or("", |(_, name)| name);
+                Some(Notification::SessionChanged { name: name.to_string() })
+            }
+            b"%exit" => {
+                let reason = String::from_utf8_lossy(args).trim().to_string();
+                Some(Notification::Exit { reason: Some(reason).filter(|r| !r.is_empty()) })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Remove the DCS introducer and terminator from a line
+fn strip_dcs(line: &[u8]) -> &[u8] {
+    let line = match line.windows(DCS_START.len()).position(|w| w == DCS_START) {
+        Some(start) => &line[start + DCS_START.len()..],
+        None => line,
+    };
+    line.strip_suffix(DCS_END).unwrap_or(line)
+}
+
+/// Decode the `\ooo` octal escapes of `%output` data
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let digits = data.get(i + 1..i + 4);
+        match digits {
+            Some(digits) if data[i] == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)) => {
+                let value = digits.iter().fold(0u32, |v, d| v * 8 + u32::from(d - b'0'));
+                out.push(value as u8);
+                i += 4;
+            }
+            _ => {
+                out.push(data[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Where a pane sits in its window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaneGeometry {
+    /// Numeric part of the pane id (`%N`)
+    pub pane: u32,
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Panes of a window layout (`#{window_layout}`), e.g.
+/// `b25f,80x24,0,0{40x24,0,0,1,39x24,41,0,2}`
+pub fn parse_layout(layout: &str) -> Result<Vec<PaneGeometry>, String> {
+    let invalid = || format!("Invalid tmux layout: {}", layout);
+    // Skip the checksum
+    let (_, cells) = layout.split_once(',').ok_or_else(invalid)?;
+    let mut parser = LayoutParser { text: cells.as_bytes(), pos: 0 };
+    let mut panes = Vec::new();
+    parser.cell(&mut panes).ok_or_else(invalid)?;
+    if parser.pos != parser.text.len() {
+        return Err(invalid());
+    }
+    Ok(panes)
+}
+
+struct LayoutParser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl LayoutParser<'_> {
+    /// `WxH,X,Y` followed by `,ID` (a pane) or `{...}`/`[...]` (a split)
+    fn cell(&mut self, panes: &mut Vec<PaneGeometry>) -> Option<()> {
+        let width = self.number()?;
+        self.expect(b'x')?;
+        let height = self.number()?;
+        self.expect(b',')?;
+        let x = self.number()?;
+        self.expect(b',')?;
+        let y = self.number()?;
+
+        match self.text.get(self.pos) {
+            Some(&open) if open == b'{' || open == b'[' => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                loop {
+                    self.cell(panes)?;
+                    match self.text.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(&c) if c == close => {
+                            self.pos += 1;
+                            return Some(());
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            Some(b',') => {
+                self.pos += 1;
+                let pane = self.number()?;
+                panes.push(PaneGeometry { pane, width, height, x, y });
+                Some(())
+            }
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.text.get(self.pos).map_or(false, u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.pos]).ok()?.parse().ok()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.text.get(self.pos) == Some(&byte)).then(|| self.pos += 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_output_and_replies() {
+        let mut parser = ControlParser::new();
+        let notifications = parser.feed(
+            b"\x1bP1000p%begin 1 1 0\n%end 1 1 0\n%session-changed $0 work\n%output %3 ls\\015\\012\\134o\n%beg",
+        );
+        assert_eq!(
+            notifications,
+            vec![
+                Notification::Reply { lines: Vec::new(), error: false },
+                Notification::SessionChanged { name: "work".to_string() },
+                Notification::Output { pane: "%3".to_string(), data: b"ls\r\n\\o".to_vec() },
+            ]
+        );
+
+        let notifications = parser.feed(b"in 2 5 1\n%1 @0\nbad\n%error 2 5 1\n%exit detached\n\x1b\\");
+        assert_eq!(
+            notifications,
+            vec![
+                Notification::Reply { lines: vec!["%1 @0".to_string(), "bad".to_string()], error: true },
+                Notification::Exit { reason: Some("detached".to_string()) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ends_replies_on_matching_guard_only() {
+        let mut parser = ControlParser::new();
+        let notifications = parser.feed(
+            b"%begin 1700000000 7 1
+%end of a captured line
+%end 1700000000 6 1
+%error 1700000000 7 1
+",
+        );
+        assert_eq!(
+            notifications,
+            vec![Notification::Reply {
+                lines: vec!["%end of a captured line".to_string(), "%end 1700000000 6 1".to_string()],
+                error: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_keeps_lines_outside_the_protocol() {
+        let mut parser = ControlParser::new();
+        assert!(parser.feed(b"bash: tmux: command not found\r\n").is_empty());
+        assert_eq!(parser.noise(), Some("bash: tmux: command not found"));
+    }
+
+    #[test]
+    fn test_parses_nested_layouts() {
+        let panes = parse_layout("d1f5,160x48,0,0{80x48,0,0,1,79x48,81,0[79x24,81,0,2,79x23,81,25,5]}").unwrap();
+        assert_eq!(
+            panes,
+            vec![
+                PaneGeometry { pane: 1, width: 80, height: 48, x: 0, y: 0 },
+                PaneGeometry { pane: 2, width: 79, height: 24, x: 81, y: 0 },
+                PaneGeometry { pane: 5, width: 79, height: 23, x: 81, y: 25 },
+            ]
+        );
+        assert_eq!(parse_layout("b25f,80x24,0,0,7").unwrap().len(), 1);
+        assert!(parse_layout("b25f,80x24,0,0{1").is_err());
+    }
+}
@@ -2,26 +2,126 @@
 //!
 //! This module handles watching locally edited files and automatically
 //! uploading them back to the remote server via SFTP when they change.
+//!
+//! Each tracked file remembers the remote state it was last synced with
+//! (`RemoteBaseline`). If the remote file changed since, the upload is held
+//! back and an `sftp-edit-conflict` event is emitted instead; the user then
+//! picks a `ConflictResolution`.
+//!
+//! Tracked files are persisted (storage/edits.rs) and picked up again when a
+//! session to the same host connects after a restart.
+//!
+//! Elevated edits (root-owned files) read and write the remote file through
+//! sudo (connectors/sudo_file.rs) instead of SFTP.
 
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::RwLock;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
 use tokio::sync::mpsc;
 
-use crate::connectors::sftp_write_file;
+use crate::connectors::scp::scp_stat;
+use crate::connectors::sftp::{uses_scp, with_sftp};
+use crate::connectors::sudo_file::{sudo_read_file, sudo_stat, sudo_write_file, SudoAuth};
+use crate::connectors::{sftp_read_file, sftp_write_file, SftpPool, SshConfig};
 use crate::session::SessionManager;
+use crate::storage::edits::{load_persisted_edits, record_edit, remove_edit, remove_edits_for_target};
+use crate::storage::PersistedEdit;
+use crate::transfers::control::TransferControl;
+use crate::transfers::integrity::{local_sha256, remote_sha256};
+use crate::transfers::stream::download_to_local;
+
+/// Files larger than this are not diffed in conflict events
+const MAX_DIFF_SIZE: u64 = 1024 * 1024;
+
+/// Remote file state the local copy was last synced with
+#[derive(Debug, Clone, Default)]
+pub struct RemoteBaseline {
+    pub size: u64,
+    pub modified: Option<u64>,
+    /// SHA-256 of the content (hex)
+    pub hash: String,
+}
 
 /// Tracks files being edited externally
 #[derive(Debug, Clone)]
 pub struct EditedFile {
     pub session_id: String,
+    /// Target of the session, used to restore the edit after a restart
+    pub host: String,
+    pub port: u16,
+    pub username: String,
     pub remote_path: String,
     pub local_path: PathBuf,
     pub last_upload: Option<Instant>,
+    pub baseline: RemoteBaseline,
+    /// A conflict resolution is rewriting the file: ignore change events
+    pub syncing: bool,
+    pub started_at: i64,
+    /// Sudo credentials for elevated edits (the password is never persisted)
+    pub sudo: Option<SudoAuth>,
+}
+
+/// Whether a local copy differs from the content last synced (`hash`)
+fn differs_from(local_path: &Path, hash: &str) -> bool {
+    std::fs::read(local_path).map_or(false, |data| sha256_hex(&data) != hash)
+}
+
+/// Split the tracked copies of a host/user into those to keep (changes not
+/// uploaded, or edited through a session that is still open) and those to discard
+fn partition_tracked<'a>(
+    files: impl IntoIterator<Item = &'a EditedFile>,
+    host: &str,
+    port: u16,
+    username: &str,
+    is_live: impl Fn(&str) -> bool,
+) -> (Vec<String>, Vec<PathBuf>) {
+    let mut kept = Vec::new();
+    let mut discarded = Vec::new();
+    for file in files {
+        if file.host != host || file.port != port || file.username != username {
+            continue;
+        }
+        if is_live(&file.session_id) || file.has_local_changes() {
+            kept.push(file.local_path.to_string_lossy().to_string());
+        } else {
+            discarded.push(file.local_path.clone());
+        }
+    }
+    (kept, discarded)
+}
+
+impl EditedFile {
+    /// Whether the local copy differs from what was last synced
+    pub fn has_local_changes(&self) -> bool {
+        differs_from(&self.local_path, &self.baseline.hash)
+    }
+
+    fn to_persisted(&self) -> PersistedEdit {
+        PersistedEdit {
+            host: self.host.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            remote_path: self.remote_path.clone(),
+            local_path: self.local_path.to_string_lossy().to_string(),
+            remote_size: self.baseline.size,
+            remote_modified: self.baseline.modified,
+            remote_hash: self.baseline.hash.clone(),
+            started_at: self.started_at,
+            elevated: self.sudo.is_some(),
+        }
+    }
+
+    fn persist(&self) {
+        if let Err(e) = record_edit(self.to_persisted()) {
+            eprintln!("[EditWatcher] Failed to persist edit: {}", e);
+        }
+    }
 }
 
 /// Event emitted when a file is uploaded
@@ -34,6 +134,31 @@ pub struct FileUploadedEvent {
     pub error: Option<String>,
 }
 
+/// Event emitted instead of uploading when the remote file changed since download
+#[derive(serde::Serialize, Clone)]
+pub struct EditConflictEvent {
+    pub session_id: String,
+    pub remote_path: String,
+    pub local_path: String,
+    pub local_size: u64,
+    pub remote_size: u64,
+    pub remote_modified: Option<u64>,
+    /// Unified diff from the remote to the local version (small text files only)
+    pub diff: Option<String>,
+}
+
+/// How to settle a conflict reported by `sftp-edit-conflict`
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    /// Upload the local version over the remote changes
+    Overwrite,
+    /// Discard local edits and replace the local copy with the remote file
+    DownloadTheirs,
+    /// Upload the local version next to the remote file, then reload theirs locally
+    SaveSideBySide,
+}
+
 /// Manages file watching for external editing
 pub struct EditWatcher {
     /// Maps local file paths to their remote counterparts
@@ -80,6 +205,9 @@ impl EditWatcher {
                 let file_info = {
                     let files = tracked_files.read();
                     if let Some(edited_file) = files.get(&path) {
+                        if edited_file.syncing {
+                            continue;
+                        }
                         // Check debounce
                         if let Some(last_upload) = edited_file.last_upload {
                             if last_upload.elapsed() < debounce {
@@ -90,13 +218,15 @@ impl EditWatcher {
                             edited_file.session_id.clone(),
                             edited_file.remote_path.clone(),
                             edited_file.local_path.clone(),
+                            edited_file.baseline.clone(),
+                            edited_file.sudo.clone(),
                         ))
                     } else {
                         None
                     }
                 }; // Lock released here
 
-                let (session_id, remote_path, local_path) = match file_info {
+                let (session_id, remote_path, local_path, baseline, sudo) = match file_info {
                     Some(info) => info,
                     None => continue,
                 };
@@ -126,17 +256,53 @@ impl EditWatcher {
                     }
                 };
 
-                // Upload to remote via pool
+                // Nothing to upload if the content is what we last synced
+                if sha256_hex(&data) == baseline.hash {
+                    continue;
+                }
+
+                // Don't clobber changes made on the server since download
                 let pool = session_manager.sftp_pool();
-                match sftp_write_file(pool, &session_id, &config, &remote_path, data).await {
-                    Ok(_) => {
+                match check_conflict(pool, &session_id, &config, &remote_path, &baseline, &data, sudo.as_ref()).await {
+                    Ok(None) => {}
+                    Ok(Some(diff)) => {
+                        println!("[EditWatcher] Conflict on {}, upload held back", remote_path);
+                        let _ = app_handle.emit("sftp-edit-conflict", EditConflictEvent {
+                            session_id: session_id.clone(),
+                            remote_path: remote_path.clone(),
+                            local_path: local_path.to_string_lossy().to_string(),
+                            local_size: data.len() as u64,
+                            remote_size: diff.remote_size,
+                            remote_modified: diff.remote_modified,
+                            diff: diff.diff,
+                        });
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("[EditWatcher] Conflict check failed: {}", e);
+                        let _ = app_handle.emit("sftp-file-uploaded", FileUploadedEvent {
+                            session_id: session_id.clone(),
+                            remote_path: remote_path.clone(),
+                            local_path: local_path.to_string_lossy().to_string(),
+                            success: false,
+                            error: Some(e),
+                        });
+                        continue;
+                    }
+                }
+
+                // Upload to remote via pool
+                match upload_and_rebase(pool, &session_id, &config, &remote_path, data, sudo.as_ref()).await {
+                    Ok(new_baseline) => {
                         println!("[EditWatcher] Uploaded {} -> {}", local_path.display(), remote_path);
 
-                        // Update last upload time
+                        // Update last upload time and the synced state
                         {
                             let mut files = tracked_files.write();
                             if let Some(f) = files.get_mut(&path) {
                                 f.last_upload = Some(Instant::now());
+                                f.baseline = new_baseline;
+                                f.persist();
                             }
                         }
 
@@ -183,26 +349,43 @@ impl EditWatcher {
         Ok(())
     }
 
-    /// Start tracking a file for external editing
+    /// Start tracking a file for external editing.
+    /// `baseline` is the remote state the local copy was downloaded from;
+    /// `sudo` is set for files written back through sudo.
     pub fn track_file(
         &mut self,
         session_id: String,
+        config: &SshConfig,
         remote_path: String,
         local_path: PathBuf,
+        baseline: RemoteBaseline,
+        sudo: Option<SudoAuth>,
     ) -> Result<(), String> {
-        // Initialize watcher if not already done
-        if self.watcher.is_none() {
-            self.init()?;
-        }
-
-        // Add to tracked files
         let edited_file = EditedFile {
             session_id,
+            host: config.host.clone(),
+            port: config.port,
+            username: config.username.clone(),
             remote_path,
-            local_path: local_path.clone(),
+            local_path,
             last_upload: None,
+            baseline,
+            syncing: false,
+            started_at: chrono::Utc::now().timestamp(),
+            sudo,
         };
+        edited_file.persist();
+        self.watch(edited_file)
+    }
+
+    /// Add a file to the tracked set and watch it for changes
+    fn watch(&mut self, edited_file: EditedFile) -> Result<(), String> {
+        // Initialize watcher if not already done
+        if self.watcher.is_none() {
+            self.init()?;
+        }
 
+        let local_path = edited_file.local_path.clone();
         self.tracked_files.write().insert(local_path.clone(), edited_file);
 
         // Start watching the file
@@ -215,7 +398,7 @@ impl EditWatcher {
         Ok(())
     }
 
-    /// Stop tracking a file
+    /// Stop tracking a file (and forget it across restarts)
     pub fn untrack_file(&mut self, local_path: &PathBuf) -> Result<(), String> {
         self.tracked_files.write().remove(local_path);
 
@@ -223,9 +406,112 @@ impl EditWatcher {
             let _ = watcher.unwatch(local_path);
         }
 
+        remove_edit(&local_path.to_string_lossy())?;
         Ok(())
     }
 
+    /// Resume watching the persisted edits of a host once a session to it is
+    /// connected. Records whose local copy disappeared are dropped.
+    /// Elevated edits get `sudo` as their credentials.
+    pub fn restore_for_session(
+        &mut self,
+        session_id: &str,
+        config: &SshConfig,
+        sudo: &SudoAuth,
+    ) -> Result<Vec<EditedFile>, String> {
+        let mut restored = Vec::new();
+        for edit in load_persisted_edits()? {
+            if !edit.same_target(&config.host, config.port, &config.username) {
+                continue;
+            }
+            let local_path = PathBuf::from(&edit.local_path);
+            if !local_path.is_file() {
+                remove_edit(&edit.local_path)?;
+                continue;
+            }
+            // Already followed by another session to the same host
+            if self.tracked_files.read().contains_key(&local_path) {
+                continue;
+            }
+
+            let edited_file = EditedFile {
+                session_id: session_id.to_string(),
+                host: edit.host,
+                port: edit.port,
+                username: edit.username,
+                remote_path: edit.remote_path,
+                local_path,
+                last_upload: None,
+                baseline: RemoteBaseline {
+                    size: edit.remote_size,
+                    modified: edit.remote_modified,
+                    hash: edit.remote_hash,
+                },
+                syncing: false,
+                started_at: edit.started_at,
+                sudo: edit.elevated.then(|| sudo.clone()),
+            };
+            self.watch(edited_file.clone())?;
+            restored.push(edited_file);
+        }
+        Ok(restored)
+    }
+
+    /// Upload a file's pending local changes now (same checks as a save)
+    pub fn sync_now(&self, local_path: &PathBuf) -> Result<(), String> {
+        if !self.tracked_files.read().contains_key(local_path) {
+            return Err(format!("File is not being edited: {}", local_path.display()));
+        }
+        let tx = self
+            .event_tx
+            .as_ref()
+            .ok_or_else(|| "File watcher not initialized".to_string())?;
+        tx.send(local_path.clone())
+            .map_err(|_| "File watcher stopped".to_string())
+    }
+
+    /// Stop every edit on a host (e.g. its saved session was deleted) and
+    /// delete the local copies.
+    ///
+    /// Copies with changes that weren't uploaded, or edited through a session
+    /// that is still open (`is_live`), are left alone with their records.
+    /// Returns the local paths of those copies.
+    pub fn discard_for_target(
+        &mut self,
+        host: &str,
+        port: u16,
+        username: &str,
+        is_live: impl Fn(&str) -> bool,
+    ) -> Result<Vec<String>, String> {
+        let (mut kept, discarded) = partition_tracked(self.tracked_files.read().values(), host, port, username, is_live);
+        for local_path in &discarded {
+            self.tracked_files.write().remove(local_path);
+            if let Some(watcher) = &mut self.watcher {
+                let _ = watcher.unwatch(local_path);
+            }
+        }
+
+        // Untracked records (not restored yet) are kept while their copy differs
+        // from what was last synced
+        let keep = |edit: &PersistedEdit| {
+            kept.contains(&edit.local_path) || differs_from(Path::new(&edit.local_path), &edit.remote_hash)
+        };
+        let removed = remove_edits_for_target(host, port, username, keep)?;
+        for local_path in discarded {
+            remove_local_copy(&local_path);
+        }
+        for edit in removed {
+            remove_local_copy(std::path::Path::new(&edit.local_path));
+        }
+
+        for edit in load_persisted_edits()? {
+            if edit.same_target(host, port, username) && !kept.contains(&edit.local_path) {
+                kept.push(edit.local_path);
+            }
+        }
+        Ok(kept)
+    }
+
     /// Get all tracked files for a session
     pub fn get_tracked_files(&self, session_id: &str) -> Vec<EditedFile> {
         self.tracked_files
@@ -236,6 +522,35 @@ impl EditWatcher {
             .collect()
     }
 
+    /// Mark a file as being rewritten by a conflict resolution and return it
+    pub fn begin_sync(&self, local_path: &PathBuf) -> Result<EditedFile, String> {
+        let mut files = self.tracked_files.write();
+        let file = files
+            .get_mut(local_path)
+            .ok_or_else(|| format!("File is not being edited: {}", local_path.display()))?;
+        if file.syncing {
+            return Err("A conflict resolution is already running for this file".to_string());
+        }
+        file.syncing = true;
+        Ok(file.clone())
+    }
+
+    /// Resume watching a file after `begin_sync`, with its new baseline on success
+    pub fn end_sync(&self, local_path: &PathBuf, baseline: Option<RemoteBaseline>) {
+        if let Some(file) = self.tracked_files.write().get_mut(local_path) {
+            file.syncing = false;
+            if let Some(baseline) = baseline {
+                file.baseline = baseline;
+                file.persist();
+            }
+        }
+    }
+
+    /// Session currently watching a local copy
+    pub fn session_of(&self, local_path: &PathBuf) -> Option<String> {
+        self.tracked_files.read().get(local_path).map(|f| f.session_id.clone())
+    }
+
     /// Check if a file is being tracked
     #[allow(dead_code)]
     pub fn is_tracking(&self, local_path: &PathBuf) -> bool {
@@ -243,6 +558,238 @@ impl EditWatcher {
     }
 }
 
+// ============================================================================
+// Conflict detection
+// ============================================================================
+
+/// Remote state found when a conflict is detected
+struct ConflictInfo {
+    remote_size: u64,
+    remote_modified: Option<u64>,
+    diff: Option<String>,
+}
+
+/// SHA-256 of a buffer (hex)
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Size and mtime of a remote file, or None if it doesn't exist.
+/// Elevated edits stat as root, since the login user may not see the file.
+async fn remote_attrs(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    sudo: Option<&SudoAuth>,
+) -> Result<Option<(u64, Option<u64>)>, String> {
+    if let Some(sudo) = sudo {
+        return sudo_stat(config, sudo, remote_path).await;
+    }
+    if uses_scp(pool, session_id, config).await {
+        return Ok(scp_stat(config, remote_path).await?.map(|stat| (stat.size, stat.modified)));
+    }
+    let remote_path = remote_path.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let remote_path = remote_path.clone();
+        async move {
+            if !entry.sftp
+                .try_exists(&remote_path)
+                .await
+                .map_err(|e| format!("Failed to stat remote file: {}", e))?
+            {
+                return Ok(None);
+            }
+            let attrs = entry.sftp
+                .metadata(&remote_path)
+                .await
+                .map_err(|e| format!("Failed to stat remote file: {}", e))?;
+            Ok(Some((attrs.size.unwrap_or(0), attrs.mtime.map(|t| t as u64))))
+        }
+    })
+    .await
+}
+
+/// Record the remote state after downloading `remote_path` to `local_path`
+pub async fn fetch_baseline(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    local_path: &std::path::Path,
+    sudo: Option<&SudoAuth>,
+) -> Result<RemoteBaseline, String> {
+    let (size, modified) = remote_attrs(pool, session_id, config, remote_path, sudo)
+        .await?
+        .ok_or_else(|| format!("Remote file not found: {}", remote_path))?;
+    Ok(RemoteBaseline { size, modified, hash: local_sha256(local_path).await? })
+}
+
+/// Compare the remote file with the baseline.
+/// Returns the conflict details if someone else changed it.
+async fn check_conflict(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    baseline: &RemoteBaseline,
+    local_data: &[u8],
+    sudo: Option<&SudoAuth>,
+) -> Result<Option<ConflictInfo>, String> {
+    // Deleted remotely: uploading recreates it, nothing is lost
+    let Some((size, modified)) = remote_attrs(pool, session_id, config, remote_path, sudo).await? else {
+        return Ok(None);
+    };
+    if size == baseline.size && modified == baseline.modified {
+        return Ok(None);
+    }
+    // The login user may not be able to read elevated files: fetch them once
+    let theirs = match sudo {
+        Some(sudo) => Some(sudo_read_file(config, sudo, remote_path).await?),
+        None => None,
+    };
+    let remote_hash = match &theirs {
+        Some(data) => sha256_hex(data),
+        // Without sha256sum on the server, read the (small) file instead
+        None => match remote_sha256(pool, session_id, config, remote_path).await {
+            Ok(hash) => hash,
+            Err(_) => sha256_hex(&sftp_read_file(pool, session_id, config, remote_path).await?),
+        },
+    };
+    // Same content with a new mtime (touched, or rewritten identically)
+    if remote_hash == baseline.hash {
+        return Ok(None);
+    }
+
+    let diff = if size <= MAX_DIFF_SIZE && local_data.len() as u64 <= MAX_DIFF_SIZE {
+        let theirs = match theirs {
+            Some(data) => Some(data),
+            None => sftp_read_file(pool, session_id, config, remote_path).await.ok(),
+        };
+        theirs.and_then(|theirs| unified_diff(&theirs, local_data))
+    } else {
+        None
+    };
+
+    Ok(Some(ConflictInfo { remote_size: size, remote_modified: modified, diff }))
+}
+
+/// Unified diff from `theirs` to `ours`, if both are text
+fn unified_diff(theirs: &[u8], ours: &[u8]) -> Option<String> {
+    let theirs = std::str::from_utf8(theirs).ok()?;
+    let ours = std::str::from_utf8(ours).ok()?;
+    Some(
+        TextDiff::from_lines(theirs, ours)
+            .unified_diff()
+            .context_radius(3)
+            .header("remote", "local")
+            .to_string(),
+    )
+}
+
+/// Write a remote file, through sudo for elevated edits
+async fn write_remote(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    data: Vec<u8>,
+    sudo: Option<&SudoAuth>,
+) -> Result<(), String> {
+    match sudo {
+        Some(sudo) => sudo_write_file(pool, session_id, config, sudo, remote_path, data).await,
+        None => sftp_write_file(pool, session_id, config, remote_path, data).await,
+    }
+}
+
+/// Write `data` to the remote file and return the resulting baseline
+async fn upload_and_rebase(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    data: Vec<u8>,
+    sudo: Option<&SudoAuth>,
+) -> Result<RemoteBaseline, String> {
+    let hash = sha256_hex(&data);
+    write_remote(pool, session_id, config, remote_path, data, sudo).await?;
+    let (size, modified) = remote_attrs(pool, session_id, config, remote_path, sudo)
+        .await?
+        .unwrap_or_default();
+    Ok(RemoteBaseline { size, modified, hash })
+}
+
+/// Sibling path for a side-by-side copy: `dir/name.local-<stamp>.ext`
+fn side_by_side_path(remote_path: &str, stamp: &str) -> String {
+    let (dir, name) = match remote_path.rfind('/') {
+        Some(i) => (&remote_path[..=i], &remote_path[i + 1..]),
+        None => ("", remote_path),
+    };
+    match name.rfind('.') {
+        // Dotfiles have no extension
+        Some(i) if i > 0 => format!("{}{}.local-{}{}", dir, &name[..i], stamp, &name[i..]),
+        _ => format!("{}{}.local-{}", dir, name, stamp),
+    }
+}
+
+/// Settle a conflict. Returns the new baseline and, for side-by-side saves,
+/// the remote path the local version was written to.
+pub async fn resolve_conflict(
+    pool: &SftpPool,
+    config: &SshConfig,
+    file: &EditedFile,
+    resolution: ConflictResolution,
+) -> Result<(RemoteBaseline, Option<String>), String> {
+    let session_id = file.session_id.as_str();
+    let remote_path = file.remote_path.as_str();
+    let sudo = file.sudo.as_ref();
+
+    match resolution {
+        ConflictResolution::Overwrite => {
+            let data = std::fs::read(&file.local_path)
+                .map_err(|e| format!("Failed to read local file: {}", e))?;
+            let baseline = upload_and_rebase(pool, session_id, config, remote_path, data, sudo).await?;
+            Ok((baseline, None))
+        }
+        ConflictResolution::DownloadTheirs => {
+            let baseline = download_theirs(pool, session_id, config, remote_path, &file.local_path, sudo).await?;
+            Ok((baseline, None))
+        }
+        ConflictResolution::SaveSideBySide => {
+            let data = std::fs::read(&file.local_path)
+                .map_err(|e| format!("Failed to read local file: {}", e))?;
+            let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+            let side_path = side_by_side_path(remote_path, &stamp);
+            write_remote(pool, session_id, config, &side_path, data, sudo).await?;
+            let baseline = download_theirs(pool, session_id, config, remote_path, &file.local_path, sudo).await?;
+            Ok((baseline, Some(side_path)))
+        }
+    }
+}
+
+/// Replace the local copy with the current remote file
+async fn download_theirs(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    local_path: &std::path::Path,
+    sudo: Option<&SudoAuth>,
+) -> Result<RemoteBaseline, String> {
+    match sudo {
+        Some(sudo) => {
+            let data = sudo_read_file(config, sudo, remote_path).await?;
+            std::fs::write(local_path, data)
+                .map_err(|e| format!("Failed to write local file: {}", e))?;
+        }
+        None => {
+            let control = TransferControl::new();
+            download_to_local(pool, session_id, config, remote_path, local_path, 0, false, &control, |_| {}).await?;
+        }
+    }
+    fetch_baseline(pool, session_id, config, remote_path, local_path, sudo).await
+}
+
 /// Get the temp directory for edited files
 pub fn get_edit_temp_dir() -> Result<PathBuf, String> {
     let temp_dir = std::env::temp_dir().join("simplyterm-edit");
@@ -253,6 +800,43 @@ pub fn get_edit_temp_dir() -> Result<PathBuf, String> {
     Ok(temp_dir)
 }
 
+/// Delete a local copy, and its session directory once empty
+pub fn remove_local_copy(local_path: &std::path::Path) {
+    let _ = std::fs::remove_file(local_path);
+    if let Some(parent) = local_path.parent() {
+        // Fails (and is ignored) while other copies remain
+        let _ = std::fs::remove_dir(parent);
+    }
+}
+
+/// Delete copies left in the temp directory that no edit record refers to
+/// (edits stopped while the app wasn't running, crashes...)
+pub fn cleanup_stale_copies() -> Result<(), String> {
+    let known: std::collections::HashSet<PathBuf> = load_persisted_edits()?
+        .into_iter()
+        .map(|e| PathBuf::from(e.local_path))
+        .collect();
+
+    let temp_dir = get_edit_temp_dir()?;
+    let session_dirs = std::fs::read_dir(&temp_dir)
+        .map_err(|e| format!("Failed to read temp directory: {}", e))?;
+    for session_dir in session_dirs.flatten() {
+        let session_path = session_dir.path();
+        if !session_path.is_dir() {
+            continue;
+        }
+        let Ok(files) = std::fs::read_dir(&session_path) else { continue };
+        for file in files.flatten() {
+            let path = file.path();
+            if !known.contains(&path) {
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+        let _ = std::fs::remove_dir(&session_path);
+    }
+    Ok(())
+}
+
 /// Get the local path for a remote file being edited
 pub fn get_local_edit_path(session_id: &str, remote_path: &str) -> Result<PathBuf, String> {
     let temp_dir = get_edit_temp_dir()?;
@@ -285,3 +869,67 @@ fn md5_hash(s: &str) -> u64 {
     s.hash(&mut hasher);
     hasher.finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_side_by_side_keeps_extension() {
+        assert_eq!(side_by_side_path("/etc/app.conf", "1"), "/etc/app.local-1.conf");
+        assert_eq!(side_by_side_path("/etc/.bashrc", "1"), "/etc/.bashrc.local-1");
+        assert_eq!(side_by_side_path("/etc/hosts", "1"), "/etc/hosts.local-1");
+        assert_eq!(side_by_side_path("notes.txt", "1"), "notes.local-1.txt");
+    }
+
+    fn tracked(session_id: &str, host: &str, local_path: PathBuf, synced: &[u8]) -> EditedFile {
+        EditedFile {
+            session_id: session_id.to_string(),
+            host: host.to_string(),
+            port: 22,
+            username: "alice".to_string(),
+            remote_path: "/etc/app.conf".to_string(),
+            local_path,
+            last_upload: None,
+            baseline: RemoteBaseline { size: synced.len() as u64, modified: None, hash: sha256_hex(synced) },
+            syncing: false,
+            started_at: 0,
+            sudo: None,
+        }
+    }
+
+    #[test]
+    fn test_discard_keeps_unsynced_and_live_copies() {
+        let dir = std::env::temp_dir().join(format!("simplyterm-edits-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["synced", "changed", "live", "other"] {
+            std::fs::write(dir.join(name), b"new").unwrap();
+        }
+
+        let files = [
+            tracked("s1", "srv", dir.join("synced"), b"new"),
+            tracked("s1", "srv", dir.join("changed"), b"old"),
+            tracked("s2", "srv", dir.join("live"), b"new"),
+            tracked("s1", "other", dir.join("other"), b"new"),
+        ];
+        let (kept, discarded) = partition_tracked(&files, "srv", 22, "alice", |id| id == "s2");
+        assert_eq!(
+            kept,
+            vec![dir.join("changed").to_string_lossy().to_string(), dir.join("live").to_string_lossy().to_string()]
+        );
+        assert_eq!(discarded, vec![dir.join("synced")]);
+
+        // A copy that can no longer be read has nothing to keep
+        assert!(!differs_from(&dir.join("missing"), &sha256_hex(b"new")));
+        assert!(differs_from(&dir.join("changed"), &sha256_hex(b"old")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_only_for_text() {
+        let diff = unified_diff(b"a\nb\n", b"a\nc\n").unwrap();
+        assert!(diff.contains("-b") && diff.contains("+c"));
+        assert!(unified_diff(&[0xff, 0xfe], b"x").is_none());
+    }
+}
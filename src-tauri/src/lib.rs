@@ -6,31 +6,54 @@
 
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
 
 mod connectors;
 mod edit_watcher;
 mod plugins;
 mod session;
 mod storage;
+mod transfers;
 mod tunnels;
 
 use connectors::{
     connect_ssh, finalize_cached_ssh, drop_cached_session, check_host_key_only,
     create_local_session,
     ssh_exec::{ssh_exec, get_server_stats, ServerStats}, SshAuth, SshConfig, SshConnectionResult,
-    HostKeyCheckResult, FileEntry, sftp_read_file, sftp_upload_file, disconnect_sftp,
+    HostKeyCheckResult, FileEntry, FileStat, disconnect_sftp, sftp::join_remote_path,
+    sudo_file::{sudo_read_file, sudo_write_file, SudoAuth},
     accept_pending_key, accept_and_update_pending_key, remove_pending_key, lookup_fingerprints,
     connect_telnet, connect_serial, list_serial_ports, SerialConfig, SerialPortInfo,
+    attach_tmux, reattach_tmux, detach_tmux, TmuxEvent, TmuxLayout,
 };
 use plugins::{PluginManager, InstalledPlugin, PluginState, RegistrySource, RegistryPlugin, PluginUpdate};
 use session::SessionManager;
+use session::logging::LogTarget;
+use session::recording::{self, RecordingInfo, RecordingOptions};
+use session::scrollback::ScrollbackSnapshot;
+use session::encoding::SessionEncoding;
+use session::broadcast::{BroadcastResult, BroadcastState, GroupInfo};
+use session::flow::HiddenOutput;
+use session::introspection::ActiveSession;
+use session::triggers::{validate_rules, TriggerFire};
+use session::automation::{self, validate_scripts, CompiledScript, ScriptProgress, ScriptResult};
 use storage::{
     load_sessions, save_sessions, SavedSession, AuthType,
     load_settings as load_app_settings, save_settings as save_app_settings, AppSettings,
-    VaultState, VaultCredentialType,
+    VaultState, VaultCredentialType, InterruptedTransfer, TransferDirection, TransferSettings,
+    load_triggers, save_triggers, TriggerAction, TriggerRule,
+    load_scripts, save_scripts, AutomationScript,
 };
 
-use edit_watcher::EditWatcher;
+use edit_watcher::{ConflictResolution, EditWatcher};
+use transfers::{
+    sftp_download_file, sftp_upload_file, TransferOptions, UploadSource,
+    TransferQueue, TransferKind, TransferJobInfo,
+    upload_directory, download_directory, remote_is_dir, DirectoryTransferOptions, DirectoryTransferSummary,
+    plan_sync, execute_sync, SyncOptions, SyncPlan, SyncSummary,
+    copy_remote, RemoteCopyOptions, RemoteEndpoint,
+    create_archive, extract_archive, ArchiveFormat, ArchiveResult,
+};
 use parking_lot::Mutex;
 use tunnels::{TunnelManager, TunnelInfo};
 
@@ -41,6 +64,7 @@ struct AppState {
     vault: Arc<VaultState>,
     edit_watcher: Arc<Mutex<EditWatcher>>,
     tunnel_manager: Arc<TunnelManager>,
+    transfer_queue: Arc<TransferQueue>,
 }
 
 // ============================================================================
@@ -55,6 +79,7 @@ async fn create_pty_session(app: AppHandle, session_id: String) -> Result<(), St
     let app_clone = app.clone();
     let session_id_clone = session_id.clone();
 
+    start_session_log(&app, &session_id, None, LogTarget::local());
     let session = create_local_session(session_id.clone(), output_tx, move || {
         let _ = app_clone.emit(&format!("pty-exit-{}", session_id_clone), ());
     })?;
@@ -140,9 +165,13 @@ async fn create_ssh_session(
     jump_password: Option<String>,
     jump_key_path: Option<String>,
     jump_key_passphrase: Option<String>,
+    encoding: Option<String>,
+    // Saved session being opened, for its on-connect script
+    saved_session_id: Option<String>,
 ) -> Result<SshConnectionResult, String> {
     let state = app.state::<AppState>();
     let output_tx = state.session_manager.output_sender();
+    apply_session_encoding(&state, &session_id, encoding)?;
 
     let config = build_ssh_config(
         host, port, username, password, key_path, key_passphrase,
@@ -152,19 +181,39 @@ async fn create_ssh_session(
     // Store config for background commands (stats, etc.)
     state.session_manager.store_ssh_config(session_id.clone(), config.clone());
 
+    // Before connecting, so the banner is logged too
+    start_session_log(&app, &session_id, saved_session_id.as_deref(), LogTarget {
+        host: config.host.clone(),
+        port: Some(config.port),
+        user: config.username.clone(),
+    });
+    let on_connect = prepare_on_connect_script(&state, &session_id, saved_session_id.as_deref());
+
     let app_clone = app.clone();
     let session_id_clone = session_id.clone();
 
-    let (result, maybe_session) = connect_ssh(config, session_id.clone(), output_tx, move || {
+    let connected = connect_ssh(config, session_id.clone(), output_tx, move || {
         let _ = app_clone.emit(&format!("pty-exit-{}", session_id_clone), ());
     })
-    .await?;
+    .await;
+    let (result, maybe_session) = match connected {
+        Ok(connected) => connected,
+        Err(e) => {
+            let _ = state.session_manager.stop_logging(&session_id);
+            return Err(e);
+        }
+    };
 
     if let Some(session) = maybe_session {
         state
             .session_manager
-            .register(session_id, Box::new(session));
+            .register(session_id.clone(), Box::new(session));
+        restore_edits(&app, &session_id);
+        if let Some((script, output)) = on_connect {
+            start_script(&app, session_id, script, output);
+        }
     }
+    // Otherwise finalize_ssh_session starts the script once the host key is accepted
 
     Ok(result)
 }
@@ -175,9 +224,11 @@ async fn finalize_ssh_session(
     app: AppHandle,
     cache_id: String,
     session_id: String,
+    saved_session_id: Option<String>,
 ) -> Result<(), String> {
     let state = app.state::<AppState>();
     let output_tx = state.session_manager.output_sender();
+    let on_connect = prepare_on_connect_script(&state, &session_id, saved_session_id.as_deref());
 
     let app_clone = app.clone();
     let session_id_clone = session_id.clone();
@@ -189,7 +240,11 @@ async fn finalize_ssh_session(
 
     state
         .session_manager
-        .register(session_id, Box::new(session));
+        .register(session_id.clone(), Box::new(session));
+    restore_edits(&app, &session_id);
+    if let Some((script, output)) = on_connect {
+        start_script(&app, session_id, script, output);
+    }
 
     Ok(())
 }
@@ -207,25 +262,73 @@ async fn create_telnet_session(
     session_id: String,
     host: String,
     port: u16,
+    encoding: Option<String>,
+    saved_session_id: Option<String>,
 ) -> Result<(), String> {
     let state = app.state::<AppState>();
     let output_tx = state.session_manager.output_sender();
+    apply_session_encoding(&state, &session_id, encoding)?;
+    let on_connect = prepare_on_connect_script(&state, &session_id, saved_session_id.as_deref());
 
     let app_clone = app.clone();
     let session_id_clone = session_id.clone();
 
-    let session = connect_telnet(host, port, session_id.clone(), output_tx, move || {
+    start_session_log(&app, &session_id, saved_session_id.as_deref(), LogTarget {
+        host: host.clone(),
+        port: Some(port),
+        user: "telnet".to_string(),
+    });
+    let session = match connect_telnet(host, port, session_id.clone(), output_tx, move || {
         let _ = app_clone.emit(&format!("pty-exit-{}", session_id_clone), ());
     })
-    .await?;
+    .await
+    {
+        Ok(session) => session,
+        Err(e) => {
+            let _ = state.session_manager.stop_logging(&session_id);
+            return Err(e);
+        }
+    };
 
     state
         .session_manager
-        .register(session_id, Box::new(session));
+        .register(session_id.clone(), Box::new(session));
+    if let Some((script, output)) = on_connect {
+        start_script(&app, session_id, script, output);
+    }
+
+    Ok(())
+}
 
+/// Use a non-default character encoding for a session being created
+fn apply_session_encoding(state: &AppState, session_id: &str, encoding: Option<String>) -> Result<(), String> {
+    if let Some(label) = encoding.filter(|e| !e.is_empty()) {
+        let encoding = SessionEncoding::from_label(&label)?;
+        state.session_manager.set_encoding(session_id, encoding);
+    }
     Ok(())
 }
 
+/// Change the character encoding of a running session. Returns its canonical name.
+#[tauri::command]
+async fn set_session_encoding(app: AppHandle, session_id: String, encoding: String) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let encoding = SessionEncoding::from_label(&encoding)?;
+    state.session_manager.set_encoding(&session_id, encoding);
+    Ok(encoding.name().to_string())
+}
+
+#[tauri::command]
+async fn get_session_encoding(app: AppHandle, session_id: String) -> String {
+    let state = app.state::<AppState>();
+    state
+        .session_manager
+        .encoding(&session_id)
+        .unwrap_or_default()
+        .name()
+        .to_string()
+}
+
 #[tauri::command]
 fn get_serial_ports() -> Result<Vec<SerialPortInfo>, String> {
     list_serial_ports()
@@ -241,9 +344,11 @@ async fn create_serial_session(
     stop_bits: u8,
     parity: String,
     flow_control: String,
+    encoding: Option<String>,
 ) -> Result<(), String> {
     let state = app.state::<AppState>();
     let output_tx = state.session_manager.output_sender();
+    apply_session_encoding(&state, &session_id, encoding)?;
 
     let config = SerialConfig {
         port,
@@ -257,9 +362,20 @@ async fn create_serial_session(
     let app_clone = app.clone();
     let session_id_clone = session_id.clone();
 
-    let session = connect_serial(config, session_id.clone(), output_tx, move || {
+    start_session_log(&app, &session_id, None, LogTarget {
+        host: config.port.clone(),
+        port: None,
+        user: "serial".to_string(),
+    });
+    let session = match connect_serial(config, session_id.clone(), output_tx, move || {
         let _ = app_clone.emit(&format!("pty-exit-{}", session_id_clone), ());
-    })?;
+    }) {
+        Ok(session) => session,
+        Err(e) => {
+            let _ = state.session_manager.stop_logging(&session_id);
+            return Err(e);
+        }
+    };
 
     state
         .session_manager
@@ -324,10 +440,484 @@ async fn resize_pty(
         .resize(&session_id, cols as u32, rows as u32)
 }
 
+/// Open sessions with their target, traffic, tunnels and SFTP connection
+async fn active_sessions(state: &AppState) -> Vec<ActiveSession> {
+    let mut sessions = state.session_manager.list_sessions().await;
+    for session in &mut sessions {
+        session.tunnels = state.tunnel_manager.list(Some(&session.session_id));
+    }
+    sessions
+}
+
+#[tauri::command]
+async fn list_active_sessions(app: AppHandle) -> Result<Vec<ActiveSession>, String> {
+    let state = app.state::<AppState>();
+    Ok(active_sessions(&state).await)
+}
+
+// ============================================================================
+// Automation Scripts
+// ============================================================================
+
+/// On-connect script of the saved session being opened. Output is tapped
+/// before connecting so the script also sees the login banner and prompt.
+fn prepare_on_connect_script(
+    state: &AppState,
+    session_id: &str,
+    saved_session_id: Option<&str>,
+) -> Option<(CompiledScript, tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>)> {
+    let saved_session_id = saved_session_id?;
+    let script_id = load_sessions()
+        .ok()?
+        .into_iter()
+        .find(|s| s.id == saved_session_id)?
+        .on_connect_script?;
+    let compiled = load_scripts().and_then(|scripts| {
+        let script = scripts
+            .iter()
+            .find(|s| s.id == script_id)
+            .ok_or_else(|| format!("Script {} not found", script_id))?;
+        CompiledScript::new(script)
+    });
+    match compiled {
+        Ok(compiled) => Some((compiled, state.session_manager.tap_output(session_id))),
+        Err(e) => {
+            eprintln!("[Automation] On-connect script of {}: {}", saved_session_id, e);
+            None
+        }
+    }
+}
+
+/// Run a script on a session in the background, reporting its steps on
+/// `script-progress-{session_id}` and how it ended on `script-finished-{session_id}`
+fn start_script(
+    app: &AppHandle,
+    session_id: String,
+    compiled: CompiledScript,
+    mut output: tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>,
+) {
+    let app = app.clone();
+    automation::spawn(session_id.clone(), async move {
+        let state = app.state::<AppState>();
+        let script = compiled.script();
+        let outcome = automation::run_script(
+            &compiled,
+            &mut output,
+            |text, secret| {
+                if secret {
+                    state.session_manager.write_secret(&session_id, text.as_bytes())
+                } else {
+                    state.session_manager.write(&session_id, text.as_bytes())
+                }
+            },
+            |key, kind| state.vault.get_credential(key, kind),
+            |step| {
+                let _ = app.emit(&format!("script-progress-{}", session_id), ScriptProgress {
+                    session_id: session_id.clone(),
+                    script_id: script.id.clone(),
+                    step,
+                });
+            },
+        )
+        .await;
+
+        if let Err(e) = &outcome {
+            eprintln!("[Automation] \"{}\" on {}: {}", script.name, session_id, e);
+        }
+        let result = ScriptResult {
+            session_id: session_id.clone(),
+            script_id: script.id.clone(),
+            script_name: script.name.clone(),
+            success: outcome.is_ok(),
+            steps_run: outcome.as_ref().map_or(0, |steps| *steps),
+            error: outcome.err(),
+        };
+        let _ = app.emit(&format!("script-finished-{}", session_id), result);
+    });
+}
+
+#[tauri::command]
+fn get_automation_scripts() -> Result<Vec<AutomationScript>, String> {
+    load_scripts()
+}
+
+/// Check and save the automation scripts (replacing all of them)
+#[tauri::command]
+fn save_automation_scripts(scripts: Vec<AutomationScript>) -> Result<(), String> {
+    validate_scripts(&scripts)?;
+    save_scripts(&scripts)
+}
+
+/// Run a script on an open session, replacing the one running there
+#[tauri::command]
+async fn run_automation_script(app: AppHandle, session_id: String, script_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let scripts = load_scripts()?;
+    let script = scripts
+        .iter()
+        .find(|s| s.id == script_id)
+        .ok_or_else(|| format!("Script {} not found", script_id))?;
+    let compiled = CompiledScript::new(script)?;
+
+    // Stopped first, so it doesn't report the replaced tap as a closed session
+    automation::cancel(&session_id);
+    let output = state.session_manager.tap_output(&session_id);
+    start_script(&app, session_id, compiled, output);
+    Ok(())
+}
+
+/// Stop the script running on a session. Returns whether one was running.
+#[tauri::command]
+fn cancel_automation_script(session_id: String) -> bool {
+    automation::cancel(&session_id)
+}
+
+// ============================================================================
+// Output Triggers
+// ============================================================================
+
+/// Run the actions of a trigger that fired. `trigger-fired-{session_id}` is
+/// emitted in any case, so the view can highlight the match.
+fn run_trigger_actions(app: &AppHandle, fire: TriggerFire) {
+    let state = app.state::<AppState>();
+    let session_id = &fire.session_id;
+
+    for action in &fire.actions {
+        let result = match action {
+            TriggerAction::Notify { title, body } => app
+                .notification()
+                .builder()
+                .title(title.as_deref().unwrap_or(&fire.rule_name))
+                .body(body.as_deref().unwrap_or(&fire.line))
+                .show()
+                .map_err(|e| e.to_string()),
+            TriggerAction::SendText { text } => state.session_manager.write(session_id, text.as_bytes()),
+            TriggerAction::StartLogging => load_app_settings()
+                .and_then(|settings| state.session_manager.start_logging(session_id, &settings.logging))
+                .map(|_| ()),
+            TriggerAction::StopLogging => state.session_manager.stop_logging(session_id),
+            TriggerAction::PluginEvent { event } => {
+                let data = serde_json::to_value(&fire).unwrap_or_default();
+                plugins::api_v1::events::EVENT_MANAGER.emit_plugin_event("triggers", event, data);
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("[Triggers] \"{}\" on {}: {}", fire.rule_name, session_id, e);
+        }
+    }
+
+    let _ = app.emit(&format!("trigger-fired-{}", session_id), &fire);
+}
+
+#[tauri::command]
+fn get_trigger_rules() -> Result<Vec<TriggerRule>, String> {
+    load_triggers()
+}
+
+/// Check, save and apply the trigger rules (replacing all of them)
+#[tauri::command]
+fn save_trigger_rules(app: AppHandle, rules: Vec<TriggerRule>) -> Result<(), String> {
+    validate_rules(&rules)?;
+    save_triggers(&rules)?;
+    let state = app.state::<AppState>();
+    state.session_manager.set_trigger_rules(&rules)
+}
+
+/// Recent output of a session, to repaint a terminal view after a reload
+#[tauri::command]
+async fn get_session_scrollback(
+    app: AppHandle,
+    session_id: String,
+    max_bytes: Option<usize>,
+) -> Result<ScrollbackSnapshot, String> {
+    let state = app.state::<AppState>();
+    state
+        .session_manager
+        .scrollback(&session_id, max_bytes)
+        .ok_or_else(|| "Session not found".to_string())
+}
+
+/// Tell the backend whether a session's tab is shown. While hidden, output
+/// is emitted as usual (`keep`), not emitted (`drop`), or not emitted and
+/// summarized when the tab is shown again (`summarize`); it still reaches
+/// the scrollback, recordings and logs. When shown again after output was
+/// skipped, `pty-output-skipped-{id}` tells the view how much it missed.
+#[tauri::command]
+async fn set_session_visibility(
+    app: AppHandle,
+    session_id: String,
+    visible: bool,
+    hidden_output: Option<HiddenOutput>,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let skipped = state
+        .session_manager
+        .set_visibility(&session_id, visible, hidden_output.unwrap_or_default());
+
+    if let Some(skipped) = skipped {
+        if let Some(summary) = &skipped.summary {
+            let note = format!(
+                "\r\n\x1b[2m[{} KB of output skipped while hidden]\x1b[0m\r\n{}",
+                skipped.bytes.div_ceil(1024),
+                summary
+            );
+            let _ = app.emit(&format!("pty-output-{}", session_id), note);
+        }
+        let _ = app.emit(&format!("pty-output-skipped-{}", session_id), skipped);
+    }
+    Ok(())
+}
+
 #[tauri::command]
 async fn close_pty_session(app: AppHandle, session_id: String) -> Result<(), String> {
     let state = app.state::<AppState>();
-    state.session_manager.close(&session_id)
+    state.transfer_queue.cancel_session(&session_id);
+    let in_group = state.session_manager.broadcast_state(&session_id).group_id.is_some();
+    let result = state.session_manager.close(&session_id);
+    if in_group {
+        emit_broadcast_change(&app, &[]);
+    }
+    result
+}
+
+// ============================================================================
+// Broadcast Commands (cluster mode)
+// ============================================================================
+
+/// Emit `broadcast-state-{id}` for each affected session, so its tab can
+/// show whether it is in broadcast mode, and the updated group list as
+/// `broadcast-groups-changed`
+fn emit_broadcast_change(app: &AppHandle, affected: &[String]) {
+    let state = app.state::<AppState>();
+    for session_id in affected {
+        let _ = app.emit(
+            &format!("broadcast-state-{}", session_id),
+            state.session_manager.broadcast_state(session_id),
+        );
+    }
+    let _ = app.emit("broadcast-groups-changed", state.session_manager.list_groups());
+}
+
+#[tauri::command]
+async fn broadcast_create_group(
+    app: AppHandle,
+    name: String,
+    session_ids: Vec<String>,
+) -> Result<GroupInfo, String> {
+    let state = app.state::<AppState>();
+    let (group, affected) = state.session_manager.create_group(name, session_ids);
+    emit_broadcast_change(&app, &affected);
+    Ok(group)
+}
+
+#[tauri::command]
+async fn broadcast_delete_group(app: AppHandle, group_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let members = state.session_manager.delete_group(&group_id)?;
+    emit_broadcast_change(&app, &members);
+    Ok(())
+}
+
+/// Add a session to a group (it leaves its current group, if any)
+#[tauri::command]
+async fn broadcast_add_session(app: AppHandle, group_id: String, session_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let affected = state.session_manager.add_to_group(&group_id, &session_id)?;
+    emit_broadcast_change(&app, &affected);
+    Ok(())
+}
+
+#[tauri::command]
+async fn broadcast_remove_session(app: AppHandle, session_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if state.session_manager.remove_from_group(&session_id).is_some() {
+        emit_broadcast_change(&app, &[session_id]);
+    }
+    Ok(())
+}
+
+/// Temporarily stop (or resume) sending broadcast input to a member
+#[tauri::command]
+async fn broadcast_set_excluded(
+    app: AppHandle,
+    group_id: String,
+    session_id: String,
+    excluded: bool,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state
+        .session_manager
+        .set_broadcast_excluded(&group_id, &session_id, excluded)?;
+    emit_broadcast_change(&app, &[session_id]);
+    Ok(())
+}
+
+#[tauri::command]
+async fn broadcast_list_groups(app: AppHandle) -> Vec<GroupInfo> {
+    let state = app.state::<AppState>();
+    state.session_manager.list_groups()
+}
+
+#[tauri::command]
+async fn broadcast_get_state(app: AppHandle, session_id: String) -> BroadcastState {
+    let state = app.state::<AppState>();
+    state.session_manager.broadcast_state(&session_id)
+}
+
+/// Send input to every member of a group that isn't excluded
+#[tauri::command]
+async fn broadcast_write(app: AppHandle, group_id: String, data: String) -> Result<BroadcastResult, String> {
+    let state = app.state::<AppState>();
+    state.session_manager.broadcast(&group_id, data.as_bytes())
+}
+
+// ============================================================================
+// Logging Commands
+// ============================================================================
+
+/// Remember what a session's log files are named after, and start logging it
+/// if the settings ask for it (globally, or for the matching saved session)
+fn start_session_log(app: &AppHandle, session_id: &str, saved_session_id: Option<&str>, target: LogTarget) {
+    let state = app.state::<AppState>();
+    let settings = load_app_settings().map(|s| s.logging).unwrap_or_default();
+
+    let session_override = saved_session_id.and_then(|id| settings.sessions.get(id).copied());
+    state.session_manager.set_log_target(session_id, target);
+
+    if session_override.unwrap_or(settings.enabled) {
+        if let Err(e) = state.session_manager.start_logging(session_id, &settings) {
+            eprintln!("[Logging] Failed to start logging {}: {}", session_id, e);
+        }
+    }
+}
+
+/// Start logging a session now (with the logging settings). Returns the file path.
+#[tauri::command]
+async fn start_session_logging(app: AppHandle, session_id: String) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let settings = load_app_settings()?.logging;
+    state.session_manager.start_logging(&session_id, &settings)
+}
+
+#[tauri::command]
+async fn stop_session_logging(app: AppHandle, session_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.session_manager.stop_logging(&session_id)
+}
+
+/// Path of the file a session is being logged to, if any
+#[tauri::command]
+async fn get_session_log_path(app: AppHandle, session_id: String) -> Option<String> {
+    let state = app.state::<AppState>();
+    state.session_manager.log_path(&session_id)
+}
+
+// ============================================================================
+// Recording Commands
+// ============================================================================
+
+/// Start recording a session (asciicast v2). Without `path`, the file goes to
+/// ~/.simplyterm/recordings.
+#[tauri::command]
+async fn start_session_recording(
+    app: AppHandle,
+    session_id: String,
+    path: Option<String>,
+    options: Option<RecordingOptions>,
+) -> Result<RecordingInfo, String> {
+    let state = app.state::<AppState>();
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let label = state
+                .session_manager
+                .get_ssh_config(&session_id)
+                .map(|c| c.host)
+                .unwrap_or_else(|| "session".to_string());
+            recording::default_recording_path(&label)?
+        }
+    };
+
+    state
+        .session_manager
+        .start_recording(&session_id, &path, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+async fn stop_session_recording(app: AppHandle, session_id: String) -> Result<RecordingInfo, String> {
+    let state = app.state::<AppState>();
+    state.session_manager.stop_recording(&session_id)
+}
+
+#[tauri::command]
+async fn get_session_recording(app: AppHandle, session_id: String) -> Option<RecordingInfo> {
+    let state = app.state::<AppState>();
+    state.session_manager.recording_info(&session_id)
+}
+
+/// Terminal size and title of a recording being played
+#[derive(serde::Serialize)]
+struct PlaybackInfo {
+    width: u32,
+    height: u32,
+    title: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct PlaybackResize {
+    cols: u32,
+    rows: u32,
+}
+
+/// Replay a recording into a new read-only session. Emits `pty-output-{id}`
+/// like a live session, `playback-resize-{id}` for recorded resizes and
+/// `pty-exit-{id}` at the end.
+#[tauri::command]
+async fn play_recording(
+    app: AppHandle,
+    session_id: String,
+    path: String,
+    speed: Option<f64>,
+    max_idle: Option<f64>,
+) -> Result<PlaybackInfo, String> {
+    let state = app.state::<AppState>();
+    let output_tx = state.session_manager.output_sender();
+
+    let resize_app = app.clone();
+    let resize_event = format!("playback-resize-{}", session_id);
+    let exit_app = app.clone();
+    let exit_event = format!("pty-exit-{}", session_id);
+
+    let (session, header) = recording::start_playback(
+        std::path::Path::new(&path),
+        session_id.clone(),
+        output_tx,
+        speed.unwrap_or(1.0),
+        max_idle,
+        move |cols, rows| {
+            let _ = resize_app.emit(&resize_event, PlaybackResize { cols, rows });
+        },
+        move || {
+            let _ = exit_app.emit(&exit_event, ());
+        },
+    )?;
+
+    state
+        .session_manager
+        .register(session_id, Box::new(session));
+
+    Ok(PlaybackInfo {
+        width: header.width,
+        height: header.height,
+        title: header.title,
+    })
+}
+
+/// Change the speed of a running playback (2.0 = twice as fast)
+#[tauri::command]
+async fn set_playback_speed(session_id: String, speed: f64) -> Result<(), String> {
+    recording::set_playback_speed(&session_id, speed)
 }
 
 /// Execute a command on an SSH session in background (doesn't pollute the visible terminal)
@@ -344,37 +934,119 @@ async fn ssh_exec_command(app: AppHandle, session_id: String, command: String) -
 }
 
 // ============================================================================
-// SFTP Commands
+// tmux Control Mode
 // ============================================================================
 
+/// Handle what a tmux client reports: pane sessions are registered and
+/// closed like other sessions, the layout is emitted as
+/// `tmux-layout-{control_id}` and the end of the connection as `tmux-exit-{control_id}`
+fn tmux_event_handler(app: &AppHandle, control_id: String) -> impl Fn(TmuxEvent) + Send + Sync + 'static {
+    let app = app.clone();
+    move |event| {
+        let state = app.state::<AppState>();
+        match event {
+            TmuxEvent::PaneOpened { session_id, pane } => {
+                state.session_manager.register(session_id, Box::new(pane));
+            }
+            TmuxEvent::PaneClosed { session_id } => {
+                let _ = state.session_manager.close(&session_id);
+                let _ = app.emit(&format!("pty-exit-{}", session_id), ());
+            }
+            TmuxEvent::Layout(layout) => {
+                let _ = app.emit(&format!("tmux-layout-{}", control_id), layout);
+            }
+            TmuxEvent::Exit { reason } => {
+                let _ = app.emit(&format!("tmux-exit-{}", control_id), reason);
+            }
+        }
+    }
+}
+
+/// Attach to tmux on the host of an open SSH session, over a connection of
+/// its own. Each tmux pane becomes a session (`{control_id}:%{pane}`).
 #[tauri::command]
-async fn sftp_list(app: AppHandle, session_id: String, path: String) -> Result<Vec<FileEntry>, String> {
+async fn tmux_attach(
+    app: AppHandle,
+    control_id: String,
+    ssh_session_id: String,
+    tmux_session: Option<String>,
+) -> Result<(), String> {
     let state = app.state::<AppState>();
     let config = state
         .session_manager
-        .get_ssh_config(&session_id)
-        .ok_or_else(|| "SSH session not found".to_string())?;
-    let pool = state.session_manager.sftp_pool();
+        .get_ssh_config(&ssh_session_id)
+        .ok_or_else(|| "SSH session not found or not an SSH session".to_string())?;
+    let tmux_session = tmux_session.filter(|name| !name.is_empty());
 
-    connectors::sftp_list_dir(pool, &session_id, &config, &path).await
+    let handler = tmux_event_handler(&app, control_id.clone());
+    attach_tmux(control_id, config, tmux_session, state.session_manager.output_sender(), handler).await
 }
 
+/// Attach again after the connection was lost: the panes come back as the
+/// same sessions
 #[tauri::command]
-async fn sftp_read(app: AppHandle, session_id: String, path: String) -> Result<Vec<u8>, String> {
+async fn tmux_reattach(app: AppHandle, control_id: String) -> Result<(), String> {
     let state = app.state::<AppState>();
-    let config = state
-        .session_manager
-        .get_ssh_config(&session_id)
-        .ok_or_else(|| "SSH session not found".to_string())?;
-    let pool = state.session_manager.sftp_pool();
+    let handler = tmux_event_handler(&app, control_id.clone());
+    reattach_tmux(control_id, state.session_manager.output_sender(), handler).await
+}
 
-    connectors::sftp_read_file(pool, &session_id, &config, &path).await
+/// Detach from tmux, leaving its session running
+#[tauri::command]
+fn tmux_detach(control_id: String) -> bool {
+    detach_tmux(&control_id)
 }
 
+/// Last known windows and panes of a tmux client
 #[tauri::command]
-async fn sftp_write(app: AppHandle, session_id: String, path: String, data: Vec<u8>) -> Result<(), String> {
-    let state = app.state::<AppState>();
-    let config = state
+fn tmux_get_layout(control_id: String) -> Option<TmuxLayout> {
+    connectors::tmux::tmux_layout(&control_id)
+}
+
+/// Set the size of the area the tmux panes are laid out in
+#[tauri::command]
+fn tmux_resize_client(control_id: String, cols: u32, rows: u32) -> Result<(), String> {
+    connectors::tmux::resize_tmux_client(&control_id, cols, rows)
+}
+
+/// Run a tmux command, e.g. `new-window` or `split-window -h`
+#[tauri::command]
+fn tmux_send_command(control_id: String, command: String) -> Result<(), String> {
+    connectors::tmux::tmux_command(&control_id, &command)
+}
+
+// ============================================================================
+// SFTP Commands
+// ============================================================================
+
+#[tauri::command]
+async fn sftp_list(app: AppHandle, session_id: String, path: String) -> Result<Vec<FileEntry>, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_list_dir(pool, &session_id, &config, &path).await
+}
+
+#[tauri::command]
+async fn sftp_read(app: AppHandle, session_id: String, path: String) -> Result<Vec<u8>, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_read_file(pool, &session_id, &config, &path).await
+}
+
+#[tauri::command]
+async fn sftp_write(app: AppHandle, session_id: String, path: String, data: Vec<u8>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
         .session_manager
         .get_ssh_config(&session_id)
         .ok_or_else(|| "SSH session not found".to_string())?;
@@ -383,8 +1055,450 @@ async fn sftp_write(app: AppHandle, session_id: String, path: String, data: Vec<
     connectors::sftp_write_file(pool, &session_id, &config, &path, data).await
 }
 
+/// Sudo credentials for a session: the given password, else the one stored in
+/// the vault for the matching saved session, else the login password
+fn sudo_auth(state: &AppState, config: &SshConfig, password: Option<String>) -> SudoAuth {
+    let password = password
+        .or_else(|| {
+            if !state.vault.is_unlocked() {
+                return None;
+            }
+            let sessions = load_sessions().ok()?;
+            let saved = sessions.iter().find(|s| {
+                s.host == config.host && s.port == config.port && s.username == config.username
+            })?;
+            state.vault.get_credential(&saved.id, VaultCredentialType::Password).ok().flatten()
+        })
+        .or_else(|| match &config.auth {
+            SshAuth::Password(password) => Some(password.clone()),
+            SshAuth::KeyFile { .. } => None,
+        });
+    SudoAuth { password }
+}
+
+/// Read a file as root (for files the login user can't read)
+#[tauri::command]
+async fn sftp_read_elevated(
+    app: AppHandle,
+    session_id: String,
+    path: String,
+    password: Option<String>,
+) -> Result<Vec<u8>, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let sudo = sudo_auth(&state, &config, password);
+
+    sudo_read_file(&config, &sudo, &path).await
+}
+
+/// Replace a file as root, keeping its owner and mode
+#[tauri::command]
+async fn sftp_write_elevated(
+    app: AppHandle,
+    session_id: String,
+    path: String,
+    data: Vec<u8>,
+    password: Option<String>,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+    let sudo = sudo_auth(&state, &config, password);
+
+    sudo_write_file(pool, &session_id, &config, &sudo, &path, data).await
+}
+
+#[tauri::command]
+async fn sftp_remove(app: AppHandle, session_id: String, path: String, is_dir: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_delete(pool, &session_id, &config, &path, is_dir).await
+}
+
+#[tauri::command]
+async fn sftp_rename(app: AppHandle, session_id: String, old_path: String, new_path: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_rename(pool, &session_id, &config, &old_path, &new_path).await
+}
+
+#[tauri::command]
+async fn sftp_mkdir(app: AppHandle, session_id: String, path: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_mkdir(pool, &session_id, &config, &path).await
+}
+
+/// Detailed attributes of a remote path (symlinks are not followed)
+#[tauri::command]
+async fn sftp_stat(app: AppHandle, session_id: String, path: String) -> Result<FileStat, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_stat(pool, &session_id, &config, &path).await
+}
+
+/// Change permission bits (e.g. 0o4755)
+#[tauri::command]
+async fn sftp_chmod(app: AppHandle, session_id: String, path: String, mode: u32) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_chmod(pool, &session_id, &config, &path, mode).await
+}
+
+/// Change owner and/or group (names or numeric ids)
+#[tauri::command]
+async fn sftp_chown(
+    app: AppHandle,
+    session_id: String,
+    path: String,
+    owner: Option<String>,
+    group: Option<String>,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_chown(pool, &session_id, &config, &path, owner.as_deref(), group.as_deref()).await
+}
+
+/// Set access/modification times (seconds since epoch)
+#[tauri::command]
+async fn sftp_utime(
+    app: AppHandle,
+    session_id: String,
+    path: String,
+    accessed: Option<u32>,
+    modified: u32,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_utime(pool, &session_id, &config, &path, accessed, modified).await
+}
+
+/// Create a symbolic link at `link_path` pointing to `target`
+#[tauri::command]
+async fn sftp_symlink(
+    app: AppHandle,
+    session_id: String,
+    link_path: String,
+    target: String,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_symlink(pool, &session_id, &config, &link_path, &target).await
+}
+
+/// Read the target of a symbolic link
+#[tauri::command]
+async fn sftp_readlink(app: AppHandle, session_id: String, path: String) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    connectors::sftp_readlink(pool, &session_id, &config, &path).await
+}
+
+/// Start a remote file search; results arrive as `sftp-search-results` events
+/// and the search ends with `sftp-search-done`. Returns the search id.
+#[tauri::command]
+async fn sftp_search_start(
+    app: AppHandle,
+    session_id: String,
+    query: connectors::sftp_search::SearchQuery,
+) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool().clone();
+
+    connectors::sftp_search::start_search(app.clone(), pool, session_id, config, query)
+}
+
+/// Cancel a running search
+#[tauri::command]
+async fn sftp_search_cancel(search_id: String) -> Result<bool, String> {
+    Ok(connectors::sftp_search::cancel_search(&search_id))
+}
+
+/// Disconnect an SFTP pooled connection (called on unmount / tab close)
+#[tauri::command]
+async fn sftp_disconnect(app: AppHandle, session_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let pool = state.session_manager.sftp_pool();
+    disconnect_sftp(pool, &session_id).await;
+    Ok(())
+}
+
+/// Upload a file via SFTP with progress events
+/// (`verify` compares SHA-256 of the sent data and the remote file)
+#[tauri::command]
+async fn sftp_upload(
+    app: AppHandle,
+    session_id: String,
+    remote_path: String,
+    file_name: String,
+    data: Vec<u8>,
+    file_index: u32,
+    total_files: u32,
+    verify: Option<bool>,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    sftp_upload_file(
+        pool,
+        &session_id,
+        &config,
+        &remote_path,
+        UploadSource::Bytes(data),
+        file_name,
+        file_index,
+        total_files,
+        TransferOptions {
+            verify: verify.unwrap_or(false),
+            ..Default::default()
+        },
+        app.app_handle(),
+    )
+    .await
+}
+
+/// Download a file via SFTP to a local path chosen by the user
+/// (`resume` continues from an existing partial local file,
+/// `verify` compares SHA-256 of both sides afterwards)
+#[tauri::command]
+async fn sftp_download(
+    app: AppHandle,
+    session_id: String,
+    remote_path: String,
+    local_path: String,
+    resume: Option<bool>,
+    verify: Option<bool>,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    // Directories are downloaded recursively into the chosen local path
+    if remote_is_dir(pool, &session_id, &config, &remote_path).await? {
+        let summary = download_directory(
+            pool,
+            &session_id,
+            &config,
+            &remote_path,
+            std::path::Path::new(&local_path),
+            &DirectoryTransferOptions {
+                verify: verify.unwrap_or(false),
+                ..Default::default()
+            },
+            app.app_handle(),
+        )
+        .await?;
+        return match summary.failure_message() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        };
+    }
+
+    // Stream the remote file to the chosen local path
+    sftp_download_file(
+        pool,
+        &session_id,
+        &config,
+        &remote_path,
+        std::path::Path::new(&local_path),
+        TransferOptions {
+            resume: resume.unwrap_or(false),
+            verify: verify.unwrap_or(false),
+            ..Default::default()
+        },
+        app.app_handle(),
+    )
+    .await
+}
+
+/// Upload files or folders from local paths via SFTP (used by native drag & drop)
+/// (`resume` continues from existing partial remote files,
+/// `verify` compares SHA-256 of both sides afterwards)
+#[tauri::command]
+async fn sftp_upload_files(
+    app: AppHandle,
+    session_id: String,
+    remote_dir: String,
+    local_paths: Vec<String>,
+    resume: Option<bool>,
+    verify: Option<bool>,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    let total_files = local_paths.len() as u32;
+    let options = TransferOptions {
+        resume: resume.unwrap_or(false),
+        verify: verify.unwrap_or(false),
+        ..Default::default()
+    };
+
+    for (i, local_path) in local_paths.iter().enumerate() {
+        let path = std::path::Path::new(local_path);
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| format!("Invalid file path: {}", local_path))?
+            .to_string_lossy()
+            .to_string();
+
+        let remote_path = join_remote_path(&remote_dir, &file_name);
+
+        // Dropped folders are uploaded recursively
+        if path.is_dir() {
+            let summary = upload_directory(
+                pool,
+                &session_id,
+                &config,
+                path,
+                &remote_path,
+                &DirectoryTransferOptions {
+                    verify: options.verify,
+                    ..Default::default()
+                },
+                app.app_handle(),
+            )
+            .await?;
+            if let Some(error) = summary.failure_message() {
+                return Err(error);
+            }
+            continue;
+        }
+
+        if !path.is_file() {
+            return Err(format!("Not a regular file: {}", local_path));
+        }
+
+        sftp_upload_file(
+            pool,
+            &session_id,
+            &config,
+            &remote_path,
+            UploadSource::Path(path.to_path_buf()),
+            file_name,
+            i as u32,
+            total_files,
+            options,
+            app.app_handle(),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Upload a local directory tree into `remote_dir/<directory name>`
+#[tauri::command]
+async fn sftp_upload_directory(
+    app: AppHandle,
+    session_id: String,
+    local_path: String,
+    remote_dir: String,
+    options: Option<DirectoryTransferOptions>,
+) -> Result<DirectoryTransferSummary, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+    let pool = state.session_manager.sftp_pool();
+
+    let path = std::path::Path::new(&local_path);
+    if !path.is_dir() {
+        return Err(format!("Not a directory: {}", local_path));
+    }
+    let dir_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid directory path: {}", local_path))?
+        .to_string_lossy()
+        .to_string();
+
+    upload_directory(
+        pool,
+        &session_id,
+        &config,
+        path,
+        &join_remote_path(&remote_dir, &dir_name),
+        &options.unwrap_or_default(),
+        app.app_handle(),
+    )
+    .await
+}
+
+/// Download a remote directory tree into `local_dir/<directory name>`
 #[tauri::command]
-async fn sftp_remove(app: AppHandle, session_id: String, path: String, is_dir: bool) -> Result<(), String> {
+async fn sftp_download_directory(
+    app: AppHandle,
+    session_id: String,
+    remote_path: String,
+    local_dir: String,
+    options: Option<DirectoryTransferOptions>,
+) -> Result<DirectoryTransferSummary, String> {
     let state = app.state::<AppState>();
     let config = state
         .session_manager
@@ -392,11 +1506,35 @@ async fn sftp_remove(app: AppHandle, session_id: String, path: String, is_dir: b
         .ok_or_else(|| "SSH session not found".to_string())?;
     let pool = state.session_manager.sftp_pool();
 
-    connectors::sftp_delete(pool, &session_id, &config, &path, is_dir).await
+    let dir_name = remote_path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| format!("Invalid remote path: {}", remote_path))?
+        .to_string();
+
+    download_directory(
+        pool,
+        &session_id,
+        &config,
+        &remote_path,
+        &std::path::Path::new(&local_dir).join(dir_name),
+        &options.unwrap_or_default(),
+        app.app_handle(),
+    )
+    .await
 }
 
+/// Compare a local and a remote directory and return the sync plan (changes nothing)
 #[tauri::command]
-async fn sftp_rename(app: AppHandle, session_id: String, old_path: String, new_path: String) -> Result<(), String> {
+async fn sftp_sync_plan(
+    app: AppHandle,
+    session_id: String,
+    local_dir: String,
+    remote_dir: String,
+    options: SyncOptions,
+) -> Result<SyncPlan, String> {
     let state = app.state::<AppState>();
     let config = state
         .session_manager
@@ -404,41 +1542,85 @@ async fn sftp_rename(app: AppHandle, session_id: String, old_path: String, new_p
         .ok_or_else(|| "SSH session not found".to_string())?;
     let pool = state.session_manager.sftp_pool();
 
-    connectors::sftp_rename(pool, &session_id, &config, &old_path, &new_path).await
+    let local_path = std::path::Path::new(&local_dir);
+    if !local_path.is_dir() {
+        return Err(format!("Not a directory: {}", local_dir));
+    }
+
+    plan_sync(pool, &session_id, &config, local_path, &remote_dir, &options).await
 }
 
+/// Execute a (possibly edited) sync plan with `sftp-sync-progress` events
 #[tauri::command]
-async fn sftp_mkdir(app: AppHandle, session_id: String, path: String) -> Result<(), String> {
+async fn sftp_sync_execute(app: AppHandle, plan: SyncPlan) -> Result<SyncSummary, String> {
     let state = app.state::<AppState>();
     let config = state
         .session_manager
-        .get_ssh_config(&session_id)
+        .get_ssh_config(&plan.session_id)
         .ok_or_else(|| "SSH session not found".to_string())?;
     let pool = state.session_manager.sftp_pool();
 
-    connectors::sftp_mkdir(pool, &session_id, &config, &path).await
+    execute_sync(pool, &config, &plan, app.app_handle()).await
 }
 
-/// Disconnect an SFTP pooled connection (called on unmount / tab close)
+/// Copy a remote file or directory into `dest_dir` on another (or the same) session,
+/// streaming between the two SFTP connections without touching local disk
 #[tauri::command]
-async fn sftp_disconnect(app: AppHandle, session_id: String) -> Result<(), String> {
-    let state = app.state::<AppState>();
+async fn sftp_copy_remote(
+    app: AppHandle,
+    source_session_id: String,
+    source_path: String,
+    dest_session_id: String,
+    dest_dir: String,
+    options: Option<RemoteCopyOptions>,
+) -> Result<DirectoryTransferSummary, String> {
+    let state = app.state::<AppState>();
+    let source_config = state
+        .session_manager
+        .get_ssh_config(&source_session_id)
+        .ok_or_else(|| "Source SSH session not found".to_string())?;
+    let dest_config = state
+        .session_manager
+        .get_ssh_config(&dest_session_id)
+        .ok_or_else(|| "Destination SSH session not found".to_string())?;
     let pool = state.session_manager.sftp_pool();
-    disconnect_sftp(pool, &session_id).await;
-    Ok(())
+
+    let name = source_path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| format!("Invalid remote path: {}", source_path))?
+        .to_string();
+    let dest_path = join_remote_path(&dest_dir, &name);
+
+    copy_remote(
+        pool,
+        RemoteEndpoint {
+            session_id: &source_session_id,
+            config: &source_config,
+            path: &source_path,
+        },
+        RemoteEndpoint {
+            session_id: &dest_session_id,
+            config: &dest_config,
+            path: &dest_path,
+        },
+        &options.unwrap_or_default(),
+        app.app_handle(),
+    )
+    .await
 }
 
-/// Upload a file via SFTP with progress events
+/// Create a tar.gz/tar/zip archive on the server from the selected remote paths
 #[tauri::command]
-async fn sftp_upload(
+async fn sftp_archive_create(
     app: AppHandle,
     session_id: String,
-    remote_path: String,
-    file_name: String,
-    data: Vec<u8>,
-    file_index: u32,
-    total_files: u32,
-) -> Result<(), String> {
+    paths: Vec<String>,
+    archive_path: String,
+    format: ArchiveFormat,
+) -> Result<ArchiveResult, String> {
     let state = app.state::<AppState>();
     let config = state
         .session_manager
@@ -446,28 +1628,17 @@ async fn sftp_upload(
         .ok_or_else(|| "SSH session not found".to_string())?;
     let pool = state.session_manager.sftp_pool();
 
-    sftp_upload_file(
-        pool,
-        &session_id,
-        &config,
-        &remote_path,
-        data,
-        file_name,
-        file_index,
-        total_files,
-        app.app_handle(),
-    )
-    .await
+    create_archive(pool, &session_id, &config, &paths, &archive_path, format, app.app_handle()).await
 }
 
-/// Download a file via SFTP to a local path chosen by the user
+/// Extract a remote archive into a remote directory
 #[tauri::command]
-async fn sftp_download(
+async fn sftp_archive_extract(
     app: AppHandle,
     session_id: String,
-    remote_path: String,
-    local_path: String,
-) -> Result<(), String> {
+    archive_path: String,
+    dest_dir: String,
+) -> Result<ArchiveResult, String> {
     let state = app.state::<AppState>();
     let config = state
         .session_manager
@@ -475,67 +1646,240 @@ async fn sftp_download(
         .ok_or_else(|| "SSH session not found".to_string())?;
     let pool = state.session_manager.sftp_pool();
 
-    // Read file from remote
-    let data = connectors::sftp_read_file(pool, &session_id, &config, &remote_path).await?;
-
-    // Write to chosen local path
-    std::fs::write(&local_path, &data)
-        .map_err(|e| format!("Failed to save file: {}", e))?;
+    extract_archive(pool, &session_id, &config, &archive_path, &dest_dir, app.app_handle()).await
+}
 
-    Ok(())
+/// List transfers that were interrupted and can be resumed
+#[tauri::command]
+fn transfer_list_interrupted() -> Result<Vec<InterruptedTransfer>, String> {
+    storage::transfers::load_interrupted_transfers()
 }
 
-/// Upload files from local paths via SFTP (used by native drag & drop)
+/// Resume an interrupted transfer, optionally on a different (reconnected) session
 #[tauri::command]
-async fn sftp_upload_files(
+async fn transfer_resume(
     app: AppHandle,
-    session_id: String,
-    remote_dir: String,
-    local_paths: Vec<String>,
+    id: String,
+    session_id: Option<String>,
+    verify: Option<bool>,
 ) -> Result<(), String> {
     let state = app.state::<AppState>();
+    let transfer = storage::transfers::get_interrupted_transfer(&id)?
+        .ok_or_else(|| format!("Transfer not found: {}", id))?;
+
+    let session_id = session_id.unwrap_or_else(|| transfer.session_id.clone());
     let config = state
         .session_manager
         .get_ssh_config(&session_id)
         .ok_or_else(|| "SSH session not found".to_string())?;
     let pool = state.session_manager.sftp_pool();
 
-    let total_files = local_paths.len() as u32;
+    let options = TransferOptions {
+        resume: true,
+        verify_resume: verify.unwrap_or(true),
+        ..Default::default()
+    };
+    let local_path = std::path::PathBuf::from(&transfer.local_path);
+
+    match transfer.direction {
+        TransferDirection::Upload => {
+            let file_name = local_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| transfer.local_path.clone());
+            sftp_upload_file(
+                pool,
+                &session_id,
+                &config,
+                &transfer.remote_path,
+                UploadSource::Path(local_path),
+                file_name,
+                0,
+                1,
+                options,
+                app.app_handle(),
+            )
+            .await
+        }
+        TransferDirection::Download => {
+            sftp_download_file(
+                pool,
+                &session_id,
+                &config,
+                &transfer.remote_path,
+                &local_path,
+                options,
+                app.app_handle(),
+            )
+            .await
+        }
+    }
+}
 
-    for (i, local_path) in local_paths.iter().enumerate() {
-        let path = std::path::Path::new(local_path);
+/// Forget an interrupted transfer, optionally deleting the partial local download
+#[tauri::command]
+fn transfer_discard(id: String, delete_partial: Option<bool>) -> Result<bool, String> {
+    if delete_partial.unwrap_or(false) {
+        if let Some(transfer) = storage::transfers::get_interrupted_transfer(&id)? {
+            if transfer.direction == TransferDirection::Download {
+                let _ = std::fs::remove_file(&transfer.local_path);
+            }
+        }
+    }
+    storage::transfers::remove_interrupted_transfer(&id)
+}
+
+// ============================================================================
+// Transfer Queue Commands
+// ============================================================================
+
+/// Queue local files for upload into a remote directory
+#[tauri::command]
+async fn transfer_queue_upload(
+    app: AppHandle,
+    session_id: String,
+    local_paths: Vec<String>,
+    remote_dir: String,
+    verify: Option<bool>,
+) -> Result<Vec<TransferJobInfo>, String> {
+    let state = app.state::<AppState>();
+    let mut jobs = Vec::new();
+    for local_path in local_paths {
+        let path = std::path::Path::new(&local_path);
+        if !path.is_file() {
+            return Err(format!("Not a regular file: {}", local_path));
+        }
         let file_name = path
             .file_name()
             .ok_or_else(|| format!("Invalid file path: {}", local_path))?
             .to_string_lossy()
             .to_string();
+        let remote_path = join_remote_path(&remote_dir, &file_name);
+        jobs.push(state.transfer_queue.enqueue(
+            TransferKind::Upload,
+            session_id.clone(),
+            local_path,
+            None,
+            remote_path,
+            verify.unwrap_or(false),
+        ));
+    }
+    Ok(jobs)
+}
 
-        let data = std::fs::read(local_path)
-            .map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
-
-        let remote_path = if remote_dir == "/" {
-            format!("/{}", file_name)
-        } else {
-            format!("{}/{}", remote_dir, file_name)
-        };
+/// Queue remote files for download into a local directory
+#[tauri::command]
+async fn transfer_queue_download(
+    app: AppHandle,
+    session_id: String,
+    remote_paths: Vec<String>,
+    local_dir: String,
+    verify: Option<bool>,
+) -> Result<Vec<TransferJobInfo>, String> {
+    let state = app.state::<AppState>();
+    let mut jobs = Vec::new();
+    for remote_path in remote_paths {
+        let file_name = remote_path
+            .rsplit('/')
+            .next()
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| format!("Invalid remote path: {}", remote_path))?
+            .to_string();
+        let local_path = std::path::Path::new(&local_dir).join(file_name);
+        jobs.push(state.transfer_queue.enqueue(
+            TransferKind::Download,
+            session_id.clone(),
+            remote_path,
+            None,
+            local_path.to_string_lossy().to_string(),
+            verify.unwrap_or(false),
+        ));
+    }
+    Ok(jobs)
+}
 
-        sftp_upload_file(
-            pool,
-            &session_id,
-            &config,
-            &remote_path,
-            data,
-            file_name,
-            i as u32,
-            total_files,
-            app.app_handle(),
-        )
-        .await?;
+/// Queue remote files for copy into a directory on the same or another session
+#[tauri::command]
+async fn transfer_queue_copy(
+    app: AppHandle,
+    session_id: String,
+    remote_paths: Vec<String>,
+    dest_session_id: String,
+    dest_dir: String,
+    verify: Option<bool>,
+) -> Result<Vec<TransferJobInfo>, String> {
+    let state = app.state::<AppState>();
+    let mut jobs = Vec::new();
+    for remote_path in remote_paths {
+        let file_name = remote_path
+            .rsplit('/')
+            .next()
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| format!("Invalid remote path: {}", remote_path))?
+            .to_string();
+        let dest_path = join_remote_path(&dest_dir, &file_name);
+        jobs.push(state.transfer_queue.enqueue(
+            TransferKind::Copy,
+            session_id.clone(),
+            remote_path,
+            Some(dest_session_id.clone()),
+            dest_path,
+            verify.unwrap_or(false),
+        ));
     }
+    Ok(jobs)
+}
+
+/// List queued transfers, optionally filtered by session
+#[tauri::command]
+async fn transfer_queue_list(app: AppHandle, session_id: Option<String>) -> Result<Vec<TransferJobInfo>, String> {
+    let state = app.state::<AppState>();
+    Ok(state.transfer_queue.list(session_id.as_deref()))
+}
+
+#[tauri::command]
+async fn transfer_queue_pause(app: AppHandle, job_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.transfer_queue.pause(&job_id)
+}
+
+#[tauri::command]
+async fn transfer_queue_resume(app: AppHandle, job_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.transfer_queue.resume(&job_id)
+}
+
+#[tauri::command]
+async fn transfer_queue_cancel(app: AppHandle, job_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.transfer_queue.cancel(&job_id)
+}
+
+#[tauri::command]
+async fn transfer_queue_retry(app: AppHandle, job_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.transfer_queue.retry(&job_id)
+}
 
+/// Remove completed, failed and cancelled jobs from the list
+#[tauri::command]
+async fn transfer_queue_clear(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.transfer_queue.clear_finished();
     Ok(())
 }
 
+/// Update concurrency/retry limits and persist them in settings
+#[tauri::command]
+async fn transfer_queue_set_limits(app: AppHandle, limits: TransferSettings) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.transfer_queue.set_limits(limits.clone());
+
+    let mut settings = load_app_settings()?;
+    settings.transfers = limits;
+    save_app_settings(&settings)
+}
+
 /// Response for sftp_edit_external command
 #[derive(serde::Serialize)]
 struct EditExternalResponse {
@@ -543,12 +1887,15 @@ struct EditExternalResponse {
     remote_path: String,
 }
 
-/// Download a remote file, open it in the default editor, and watch for changes
+/// Download a remote file, open it in the default editor, and watch for changes.
+/// With `elevated`, the file is read and written back through sudo.
 #[tauri::command]
 async fn sftp_edit_external(
     app: AppHandle,
     session_id: String,
     remote_path: String,
+    elevated: Option<bool>,
+    password: Option<String>,
 ) -> Result<EditExternalResponse, String> {
     let state = app.state::<AppState>();
     let config = state
@@ -561,17 +1908,38 @@ async fn sftp_edit_external(
     // Get local path for this file
     let local_path = edit_watcher::get_local_edit_path(&session_id, &remote_path)?;
 
-    // Download the file
-    let content = sftp_read_file(pool, &session_id, &config, &remote_path).await?;
+    let sudo = elevated
+        .unwrap_or(false)
+        .then(|| sudo_auth(&state, &config, password));
+
+    // Download the file straight to the local copy
+    match &sudo {
+        Some(sudo) => {
+            let data = sudo_read_file(&config, sudo, &remote_path).await?;
+            std::fs::write(&local_path, data)
+                .map_err(|e| format!("Failed to write local file: {}", e))?;
+        }
+        None => {
+            sftp_download_file(
+                pool,
+                &session_id,
+                &config,
+                &remote_path,
+                &local_path,
+                TransferOptions::default(),
+                app.app_handle(),
+            )
+            .await?;
+        }
+    }
 
-    // Write to local file
-    std::fs::write(&local_path, &content)
-        .map_err(|e| format!("Failed to write local file: {}", e))?;
+    // Remember what we downloaded to detect concurrent remote changes
+    let baseline = edit_watcher::fetch_baseline(pool, &session_id, &config, &remote_path, &local_path, sudo.as_ref()).await?;
 
     // Start tracking the file for changes
     {
         let mut watcher = state.edit_watcher.lock();
-        watcher.track_file(session_id.clone(), remote_path.clone(), local_path.clone())?;
+        watcher.track_file(session_id.clone(), &config, remote_path.clone(), local_path.clone(), baseline, sudo)?;
     }
 
     // Open with default editor
@@ -584,6 +1952,39 @@ async fn sftp_edit_external(
     })
 }
 
+/// Settle an `sftp-edit-conflict`: overwrite the remote file, download theirs,
+/// or save the local version side by side. Returns the side-by-side remote path.
+#[tauri::command]
+async fn sftp_edit_resolve_conflict(
+    app: AppHandle,
+    local_path: String,
+    resolution: ConflictResolution,
+) -> Result<Option<String>, String> {
+    let state = app.state::<AppState>();
+    let path = std::path::PathBuf::from(&local_path);
+
+    // Change events are ignored while the local copy is rewritten
+    let file = state.edit_watcher.lock().begin_sync(&path)?;
+    let result = match state.session_manager.get_ssh_config(&file.session_id) {
+        Some(config) => {
+            let pool = state.session_manager.sftp_pool();
+            edit_watcher::resolve_conflict(pool, &config, &file, resolution).await
+        }
+        None => Err("SSH session not found".to_string()),
+    };
+
+    match result {
+        Ok((baseline, side_path)) => {
+            state.edit_watcher.lock().end_sync(&path, Some(baseline));
+            Ok(side_path)
+        }
+        Err(e) => {
+            state.edit_watcher.lock().end_sync(&path, None);
+            Err(e)
+        }
+    }
+}
+
 /// Stop tracking a file for external editing
 #[tauri::command]
 async fn sftp_stop_editing(
@@ -596,8 +1997,8 @@ async fn sftp_stop_editing(
     let mut watcher = state.edit_watcher.lock();
     watcher.untrack_file(&path)?;
 
-    // Optionally delete the local file
-    let _ = std::fs::remove_file(&path);
+    // Delete the local copy (and its session temp directory once empty)
+    edit_watcher::remove_local_copy(&path);
 
     Ok(())
 }
@@ -612,18 +2013,112 @@ async fn sftp_get_editing_files(
     let watcher = state.edit_watcher.lock();
 
     let files = watcher.get_tracked_files(&session_id);
-    Ok(files.into_iter().map(|f| EditingFileInfo {
-        session_id: f.session_id,
-        remote_path: f.remote_path,
-        local_path: f.local_path.to_string_lossy().to_string(),
-    }).collect())
+    Ok(files.iter().map(EditingFileInfo::from).collect())
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Clone)]
 struct EditingFileInfo {
     session_id: String,
     remote_path: String,
     local_path: String,
+    /// Local changes not uploaded yet
+    unsynced: bool,
+    /// Written back through sudo
+    elevated: bool,
+}
+
+impl From<&edit_watcher::EditedFile> for EditingFileInfo {
+    fn from(f: &edit_watcher::EditedFile) -> Self {
+        EditingFileInfo {
+            session_id: f.session_id.clone(),
+            remote_path: f.remote_path.clone(),
+            local_path: f.local_path.to_string_lossy().to_string(),
+            unsynced: f.has_local_changes(),
+            elevated: f.sudo.is_some(),
+        }
+    }
+}
+
+/// Resume watching the edits persisted for this session's host (after a
+/// restart) and emit `sftp-edits-restored` with the files picked up
+fn restore_edits(app: &AppHandle, session_id: &str) {
+    let state = app.state::<AppState>();
+    let Some(config) = state.session_manager.get_ssh_config(session_id) else { return };
+
+    let sudo = sudo_auth(&state, &config, None);
+    let restored = state.edit_watcher.lock().restore_for_session(session_id, &config, &sudo);
+    match restored {
+        Ok(files) if !files.is_empty() => {
+            let infos: Vec<EditingFileInfo> = files.iter().map(EditingFileInfo::from).collect();
+            let _ = app.emit("sftp-edits-restored", infos);
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("[EditWatcher] Failed to restore edits: {}", e),
+    }
+}
+
+/// Restore persisted edits for a session explicitly (also done on connect)
+#[tauri::command]
+async fn sftp_restore_edits(app: AppHandle, session_id: String) -> Result<Vec<EditingFileInfo>, String> {
+    let state = app.state::<AppState>();
+    let config = state
+        .session_manager
+        .get_ssh_config(&session_id)
+        .ok_or_else(|| "SSH session not found".to_string())?;
+
+    let sudo = sudo_auth(&state, &config, None);
+    let files = state.edit_watcher.lock().restore_for_session(&session_id, &config, &sudo)?;
+    Ok(files.iter().map(EditingFileInfo::from).collect())
+}
+
+/// Edit recorded on disk, whether or not a session currently watches it
+#[derive(serde::Serialize)]
+struct PendingEditInfo {
+    host: String,
+    port: u16,
+    username: String,
+    remote_path: String,
+    local_path: String,
+    /// Local changes not uploaded yet
+    unsynced: bool,
+    /// Session currently watching the file, if any
+    session_id: Option<String>,
+    elevated: bool,
+}
+
+/// List all persisted edits, including those waiting for their host to reconnect
+#[tauri::command]
+async fn sftp_get_pending_edits(app: AppHandle) -> Result<Vec<PendingEditInfo>, String> {
+    let state = app.state::<AppState>();
+    let edits = storage::edits::load_persisted_edits()?;
+    let watcher = state.edit_watcher.lock();
+
+    Ok(edits
+        .into_iter()
+        .map(|edit| {
+            let local_path = std::path::PathBuf::from(&edit.local_path);
+            let unsynced = std::fs::read(&local_path)
+                .map_or(false, |data| edit_watcher::sha256_hex(&data) != edit.remote_hash);
+            PendingEditInfo {
+                session_id: watcher.session_of(&local_path),
+                host: edit.host,
+                port: edit.port,
+                username: edit.username,
+                remote_path: edit.remote_path,
+                local_path: edit.local_path,
+                unsynced,
+                elevated: edit.elevated,
+            }
+        })
+        .collect())
+}
+
+/// Upload pending local changes of an edited file now (with conflict checks)
+#[tauri::command]
+async fn sftp_edit_sync(app: AppHandle, local_path: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let watcher = state.edit_watcher.lock();
+    watcher.sync_now(&std::path::PathBuf::from(&local_path))
 }
 
 /// Register SSH config for SFTP-only use (no terminal session)
@@ -660,7 +2155,8 @@ async fn register_sftp_session(
     };
 
     // Just store the config, don't create a session
-    state.session_manager.store_ssh_config(session_id, config);
+    state.session_manager.store_ssh_config(session_id.clone(), config);
+    restore_edits(&app, &session_id);
     Ok(())
 }
 
@@ -752,6 +2248,8 @@ struct SavedSessionResponse {
     key_path: Option<String>,
     ssh_key_id: Option<String>,
     folder_id: Option<String>,
+    encoding: Option<String>,
+    on_connect_script: Option<String>,
 }
 
 impl From<SavedSession> for SavedSessionResponse {
@@ -769,6 +2267,8 @@ impl From<SavedSession> for SavedSessionResponse {
             key_path: s.key_path,
             ssh_key_id: s.ssh_key_id,
             folder_id: s.folder_id,
+            encoding: s.encoding,
+            on_connect_script: s.on_connect_script,
         }
     }
 }
@@ -795,9 +2295,17 @@ fn save_session(
     key_passphrase: Option<String>,
     ssh_key_id: Option<String>,
     folder_id: Option<String>,
+    encoding: Option<String>,
+    on_connect_script: Option<String>,
 ) -> Result<(), String> {
     let state = app.state::<AppState>();
 
+    // Store the canonical name of the encoding
+    let encoding = encoding
+        .filter(|e| !e.is_empty())
+        .map(|e| SessionEncoding::from_label(&e).map(|enc| enc.name().to_string()))
+        .transpose()?;
+
     let mut sessions = load_sessions()?;
     sessions.retain(|s| s.id != id);
 
@@ -816,6 +2324,8 @@ fn save_session(
         key_path,
         ssh_key_id,
         folder_id,
+        encoding,
+        on_connect_script: on_connect_script.filter(|id| !id.is_empty()),
     };
 
     sessions.push(session);
@@ -838,13 +2348,26 @@ fn save_session(
     Ok(())
 }
 
+/// Returns the local copies of external edits that were kept (unsynced
+/// changes, or still edited through an open session)
 #[tauri::command]
-fn delete_saved_session(app: AppHandle, id: String) -> Result<(), String> {
+fn delete_saved_session(app: AppHandle, id: String) -> Result<Vec<String>, String> {
     let state = app.state::<AppState>();
 
     // Charger les sessions
     let mut sessions = load_sessions()?;
 
+    // Stop its external edits and delete their local copies
+    let mut kept_edits = Vec::new();
+    if let Some(session) = sessions.iter().find(|s| s.id == id) {
+        let mut watcher = state.edit_watcher.lock();
+        let is_live = |session_id: &str| state.session_manager.get_ssh_config(session_id).is_some();
+        match watcher.discard_for_target(&session.host, session.port, &session.username, is_live) {
+            Ok(kept) => kept_edits = kept,
+            Err(e) => eprintln!("[EditWatcher] Failed to discard edits: {}", e),
+        }
+    }
+
     // Supprimer la session
     sessions.retain(|s| s.id != id);
 
@@ -856,7 +2379,7 @@ fn delete_saved_session(app: AppHandle, id: String) -> Result<(), String> {
         let _ = state.vault.delete_all_credentials(&id);
     }
 
-    Ok(())
+    Ok(kept_edits)
 }
 
 /// Set a session's folder
@@ -1271,6 +2794,33 @@ fn plugin_api_write_to_terminal(
     state.session_manager.write(&session_id, data.as_bytes())
 }
 
+/// Read recent terminal output (for plugins)
+#[tauri::command]
+fn plugin_api_read_terminal(
+    app: AppHandle,
+    plugin_id: String,
+    session_id: String,
+    max_bytes: Option<usize>,
+) -> Result<String, String> {
+    use plugins::manifest::Permission;
+
+    let state = app.state::<AppState>();
+    let plugin = state.plugin_manager.get_plugin(&plugin_id)
+        .map_err(|e| e.message)?
+        .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+
+    // Check permission
+    if !plugin.granted_permissions.has(Permission::TerminalRead) {
+        return Err("Permission denied: terminal_read required".to_string());
+    }
+
+    state
+        .session_manager
+        .scrollback(&session_id, max_bytes)
+        .map(|snapshot| snapshot.data)
+        .ok_or_else(|| "Session not found".to_string())
+}
+
 /// Get plugin main file content (for loading in frontend)
 #[tauri::command]
 fn get_plugin_file(app: AppHandle, plugin_id: String, file_path: String) -> Result<String, String> {
@@ -1486,6 +3036,23 @@ fn plugin_api_list_sessions(app: AppHandle, plugin_id: String) -> Result<Vec<Sav
     Ok(sessions.into_iter().map(|s| s.into()).collect())
 }
 
+/// Sessions currently open (not the saved ones)
+#[tauri::command]
+async fn plugin_api_list_active_sessions(app: AppHandle, plugin_id: String) -> Result<Vec<ActiveSession>, String> {
+    use plugins::manifest::Permission;
+
+    let state = app.state::<AppState>();
+    let plugin = state.plugin_manager.get_plugin(&plugin_id)
+        .map_err(|e| e.message)?
+        .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+
+    if !plugin.granted_permissions.has(Permission::SessionsRead) {
+        return Err("Permission denied: sessions_read required".to_string());
+    }
+
+    Ok(active_sessions(&state).await)
+}
+
 #[tauri::command]
 fn plugin_api_get_session(app: AppHandle, plugin_id: String, id: String) -> Result<Option<SavedSessionResponse>, String> {
     use plugins::manifest::Permission;
@@ -1543,6 +3110,8 @@ fn plugin_api_create_session(
         key_path,
         ssh_key_id: None,
         folder_id: None,
+        encoding: None,
+        on_connect_script: None,
     };
 
     let mut sessions = load_sessions()?;
@@ -2023,8 +3592,19 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             let session_manager = SessionManager::new(app.handle().clone());
+            match load_triggers() {
+                Ok(rules) => {
+                    if let Err(e) = session_manager.set_trigger_rules(&rules) {
+                        eprintln!("[Triggers] {}", e);
+                    }
+                }
+                Err(e) => eprintln!("[Triggers] Failed to load rules: {}", e),
+            }
+            let trigger_app = app.handle().clone();
+            session_manager.on_trigger(move |fire| run_trigger_actions(&trigger_app, fire));
 
             // Plugins are stored with the app (removed with app uninstall)
             // In dev mode: uses project root/plugins
@@ -2047,17 +3627,27 @@ pub fn run() {
             );
 
             let vault = Arc::new(VaultState::new().expect("Failed to initialize vault"));
+            // Copies of edits stopped while the app wasn't running
+            if let Err(e) = edit_watcher::cleanup_stale_copies() {
+                eprintln!("[EditWatcher] Failed to clean up temp files: {}", e);
+            }
             let edit_watcher = Arc::new(Mutex::new(EditWatcher::new(
                 session_manager.clone(),
                 app.handle().clone(),
             )));
             let tunnel_manager = Arc::new(TunnelManager::new());
+            let transfer_queue = TransferQueue::new(
+                session_manager.clone(),
+                app.handle().clone(),
+                load_app_settings().map(|s| s.transfers).unwrap_or_default(),
+            );
             app.manage(AppState {
                 session_manager,
                 plugin_manager,
                 vault: vault.clone(),
                 edit_watcher,
                 tunnel_manager,
+                transfer_queue,
             });
             // Also manage vault directly for vault commands
             app.manage(vault);
@@ -2093,7 +3683,40 @@ pub fn run() {
             write_to_pty,
             resize_pty,
             close_pty_session,
+            broadcast_create_group,
+            broadcast_delete_group,
+            broadcast_add_session,
+            broadcast_remove_session,
+            broadcast_set_excluded,
+            broadcast_list_groups,
+            broadcast_get_state,
+            broadcast_write,
+            list_active_sessions,
+            get_session_scrollback,
+            set_session_visibility,
+            get_trigger_rules,
+            save_trigger_rules,
+            get_automation_scripts,
+            save_automation_scripts,
+            run_automation_script,
+            cancel_automation_script,
+            set_session_encoding,
+            get_session_encoding,
+            start_session_logging,
+            stop_session_logging,
+            get_session_log_path,
+            start_session_recording,
+            stop_session_recording,
+            get_session_recording,
+            play_recording,
+            set_playback_speed,
             ssh_exec_command,
+            tmux_attach,
+            tmux_reattach,
+            tmux_detach,
+            tmux_get_layout,
+            tmux_resize_client,
+            tmux_send_command,
             get_home_dir,
             // Local file system
             local_list_dir,
@@ -2106,14 +3729,50 @@ pub fn run() {
             sftp_list,
             sftp_read,
             sftp_write,
+            sftp_read_elevated,
+            sftp_write_elevated,
             sftp_remove,
             sftp_rename,
             sftp_mkdir,
+            sftp_stat,
+            sftp_chmod,
+            sftp_chown,
+            sftp_utime,
+            sftp_symlink,
+            sftp_readlink,
+            sftp_search_start,
+            sftp_search_cancel,
             sftp_disconnect,
             sftp_upload,
             sftp_upload_files,
             sftp_download,
+            sftp_upload_directory,
+            sftp_download_directory,
+            sftp_sync_plan,
+            sftp_sync_execute,
+            sftp_copy_remote,
+            sftp_archive_create,
+            sftp_archive_extract,
+            // Interrupted transfers (resume)
+            transfer_list_interrupted,
+            transfer_resume,
+            transfer_discard,
+            // Transfer queue
+            transfer_queue_upload,
+            transfer_queue_download,
+            transfer_queue_copy,
+            transfer_queue_list,
+            transfer_queue_pause,
+            transfer_queue_resume,
+            transfer_queue_cancel,
+            transfer_queue_retry,
+            transfer_queue_clear,
+            transfer_queue_set_limits,
             sftp_edit_external,
+            sftp_edit_resolve_conflict,
+            sftp_edit_sync,
+            sftp_restore_edits,
+            sftp_get_pending_edits,
             sftp_stop_editing,
             sftp_get_editing_files,
             // Saved sessions (core only - plugin metadata via session_metadata API)
@@ -2155,7 +3814,9 @@ pub fn run() {
             plugin_storage_list,
             // Plugin API v1 - Core
             plugin_api_write_to_terminal,
+            plugin_api_read_terminal,
             plugin_api_list_sessions,
+            plugin_api_list_active_sessions,
             plugin_api_get_session,
             plugin_api_create_session,
             plugin_api_update_session,
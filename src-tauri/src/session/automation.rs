@@ -0,0 +1,412 @@
+//! Automation scripts
+//!
+//! Runs the steps of a script (see `storage::scripts`) against a session.
+//! Expect steps match a tap of the session's decoded output, with ANSI
+//! sequences removed; output that arrives while other steps run is kept
+//! until an expect step consumes it. One script runs per session at a time.
+
+use parking_lot::Mutex;
+use regex::Regex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
+
+use super::logging::AnsiStripper;
+use crate::storage::{AutomationScript, ScriptStep, VaultCredentialType};
+
+/// Steps run before a script is considered stuck in a loop
+const MAX_STEPS: usize = 10_000;
+/// Output kept for expect steps that haven't matched yet
+const MAX_BUFFER: usize = 64 * 1024;
+
+lazy_static::lazy_static! {
+    /// Script running on each session (run number, task)
+    static ref RUNNING: Mutex<HashMap<String, (u64, AbortHandle)>> = Mutex::new(HashMap::new());
+    static ref NEXT_RUN: Mutex<u64> = Mutex::new(0);
+}
+
+/// Step a script reached, emitted as it runs
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptProgress {
+    pub session_id: String,
+    pub script_id: String,
+    /// Index of the step in the script
+    pub step: usize,
+}
+
+/// How a script ended
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScriptResult {
+    pub session_id: String,
+    pub script_id: String,
+    pub script_name: String,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Steps run, including repeated ones
+    pub steps_run: usize,
+}
+
+/// A script with its patterns compiled and labels resolved
+pub(crate) struct CompiledScript {
+    script: AutomationScript,
+    /// Patterns of each expect step, by step index
+    patterns: HashMap<usize, Vec<Regex>>,
+    labels: HashMap<String, usize>,
+}
+
+impl CompiledScript {
+    pub(crate) fn new(script: &AutomationScript) -> Result<Self, String> {
+        let mut labels = HashMap::new();
+        for (index, step) in script.steps.iter().enumerate() {
+            if let ScriptStep::Label { name } = step {
+                if labels.insert(name.clone(), index).is_some() {
+                    return Err(format!("Duplicate label \"{}\" in script \"{}\"", name, script.name));
+                }
+            }
+        }
+        let check_label = |label: &str| {
+            if labels.contains_key(label) {
+                Ok(())
+            } else {
+                Err(format!("Unknown label \"{}\" in script \"{}\"", label, script.name))
+            }
+        };
+
+        let mut patterns = HashMap::new();
+        for (index, step) in script.steps.iter().enumerate() {
+            match step {
+                ScriptStep::Expect { cases, on_timeout, .. } => {
+                    if cases.is_empty() {
+                        return Err(format!("Expect step {} of script \"{}\" has no pattern", index + 1, script.name));
+                    }
+                    let mut compiled = Vec::with_capacity(cases.len());
+                    for case in cases {
+                        compiled.push(Regex::new(&case.pattern).map_err(|e| {
+                            format!("Invalid pattern in script \"{}\": {}", script.name, e)
+                        })?);
+                        case.goto.as_deref().map_or(Ok(()), check_label)?;
+                    }
+                    on_timeout.as_deref().map_or(Ok(()), check_label)?;
+                    patterns.insert(index, compiled);
+                }
+                ScriptStep::Goto { label } => check_label(label)?,
+                _ => {}
+            }
+        }
+
+        Ok(CompiledScript { script: script.clone(), patterns, labels })
+    }
+
+    pub(crate) fn script(&self) -> &AutomationScript {
+        &self.script
+    }
+}
+
+/// Check scripts before they are saved
+pub fn validate_scripts(scripts: &[AutomationScript]) -> Result<(), String> {
+    scripts.iter().try_for_each(|script| CompiledScript::new(script).map(|_| ()))
+}
+
+/// Replace `${vault:<key>}` (password) and `${vault:<key>:passphrase}` (key
+/// passphrase) references with the secrets `lookup` finds
+pub(crate) fn expand_secrets(
+    text: &str,
+    lookup: impl Fn(&str, VaultCredentialType) -> Result<Option<String>, String>,
+) -> Result<String, String> {
+    const PREFIX: &str = "${vault:";
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PREFIX) {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + PREFIX.len()..];
+        let end = after.find('}').ok_or("Unterminated vault reference")?;
+        let reference = &after[..end];
+        let (key, kind) = match reference.rsplit_once(':') {
+            Some((key, "passphrase")) => (key, VaultCredentialType::KeyPassphrase),
+            Some((key, "password")) => (key, VaultCredentialType::Password),
+            _ => (reference, VaultCredentialType::Password),
+        };
+        let secret = lookup(key, kind)?
+            .ok_or_else(|| format!("No {} in the vault for \"{}\"", kind.as_str(), key))?;
+        expanded.push_str(&secret);
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Output not consumed by an expect step yet
+struct ExpectBuffer {
+    stripper: AnsiStripper,
+    text: String,
+}
+
+impl ExpectBuffer {
+    fn new() -> Self {
+        ExpectBuffer { stripper: AnsiStripper::new(), text: String::new() }
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        let mut stripped = Vec::with_capacity(data.len());
+        self.stripper.strip(data, &mut stripped);
+        self.text.push_str(&String::from_utf8_lossy(&stripped));
+        if self.text.len() > MAX_BUFFER {
+            let mut cut = self.text.len() - MAX_BUFFER;
+            while !self.text.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.text.drain(..cut);
+        }
+    }
+
+    /// Index of the pattern matching earliest in the output (the first listed
+    /// on a tie). The output up to the end of the match is consumed.
+    fn consume(&mut self, patterns: &[Regex]) -> Option<usize> {
+        let (index, found) = patterns
+            .iter()
+            .enumerate()
+            .filter_map(|(index, regex)| regex.find(&self.text).map(|m| (index, m.start(), m.end())))
+            .min_by_key(|(index, start, _)| (*start, *index))
+            .map(|(index, _, end)| (index, end))?;
+        self.text.drain(..found);
+        Some(index)
+    }
+}
+
+/// Run a script. `send` writes text to the session (`true` when the text
+/// holds secrets), `progress` is told each step reached. Returns the number
+/// of steps run.
+pub(crate) async fn run_script(
+    compiled: &CompiledScript,
+    output: &mut UnboundedReceiver<Vec<u8>>,
+    send: impl Fn(&str, bool) -> Result<(), String>,
+    secret: impl Fn(&str, VaultCredentialType) -> Result<Option<String>, String>,
+    progress: impl Fn(usize),
+) -> Result<usize, String> {
+    let script = &compiled.script;
+    let mut buffer = ExpectBuffer::new();
+    let mut next = 0;
+    let mut steps_run = 0;
+
+    while let Some(step) = script.steps.get(next) {
+        steps_run += 1;
+        if steps_run > MAX_STEPS {
+            return Err(format!("Stopped after {} steps (endless loop?)", MAX_STEPS));
+        }
+        progress(next);
+        let index = next;
+        next += 1;
+
+        match step {
+            ScriptStep::Send { text } => {
+                let expanded = expand_secrets(text, &secret)?;
+                send(&expanded, expanded != *text)?;
+            }
+            ScriptStep::Expect { cases, timeout_ms, on_timeout } => {
+                let patterns = &compiled.patterns[&index];
+                let timeout = Duration::from_millis(timeout_ms.unwrap_or(script.default_timeout_ms));
+                let deadline = Instant::now() + timeout;
+                let matched = loop {
+                    if let Some(case) = buffer.consume(patterns) {
+                        break Some(case);
+                    }
+                    match tokio::time::timeout_at(deadline, output.recv()).await {
+                        Ok(Some(data)) => buffer.feed(&data),
+                        Ok(None) => return Err("Session closed".to_string()),
+                        Err(_) => break None,
+                    }
+                };
+                let target = match matched {
+                    Some(case) => cases[case].goto.as_deref(),
+                    None => match on_timeout {
+                        Some(label) => Some(label.as_str()),
+                        None => {
+                            let expected: Vec<_> = cases.iter().map(|c| c.pattern.as_str()).collect();
+                            return Err(format!(
+                                "Timed out after {} ms waiting for {}",
+                                timeout.as_millis(),
+                                expected.join(" | ")
+                            ));
+                        }
+                    },
+                };
+                if let Some(label) = target {
+                    next = compiled.labels[label];
+                }
+            }
+            ScriptStep::Label { .. } => {}
+            ScriptStep::Goto { label } => next = compiled.labels[label],
+            ScriptStep::Sleep { ms } => tokio::time::sleep(Duration::from_millis(*ms)).await,
+            ScriptStep::Fail { message } => return Err(message.clone()),
+        }
+    }
+    Ok(steps_run)
+}
+
+/// Run `task` as the script of a session, aborting the one it replaces
+pub(crate) fn spawn(session_id: String, task: impl Future<Output = ()> + Send + 'static) {
+    let run = {
+        let mut next = NEXT_RUN.lock();
+        *next += 1;
+        *next
+    };
+    // Locked until the task is known, so it can't finish (and clean up) first
+    let mut running = RUNNING.lock();
+    let sid = session_id.clone();
+    let handle = tokio::spawn(async move {
+        task.await;
+        let mut running = RUNNING.lock();
+        if running.get(&sid).map_or(false, |(id, _)| *id == run) {
+            running.remove(&sid);
+        }
+    });
+    if let Some((_, previous)) = running.insert(session_id, (run, handle.abort_handle())) {
+        previous.abort();
+    }
+}
+
+/// Stop the script running on a session. Returns whether one was running.
+pub fn cancel(session_id: &str) -> bool {
+    match RUNNING.lock().remove(session_id) {
+        Some((_, handle)) => {
+            handle.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::scripts::ExpectCase;
+    use std::cell::RefCell;
+
+    fn script(steps: Vec<ScriptStep>) -> AutomationScript {
+        AutomationScript {
+            id: "s".to_string(),
+            name: "test".to_string(),
+            steps,
+            default_timeout_ms: 50,
+        }
+    }
+
+    fn expect(pattern: &str, goto: Option<&str>) -> ScriptStep {
+        ScriptStep::Expect {
+            cases: vec![ExpectCase { pattern: pattern.to_string(), goto: goto.map(str::to_string) }],
+            timeout_ms: None,
+            on_timeout: None,
+        }
+    }
+
+    fn send(text: &str) -> ScriptStep {
+        ScriptStep::Send { text: text.to_string() }
+    }
+
+    fn vault(key: &str, kind: VaultCredentialType) -> Result<Option<String>, String> {
+        Ok(match (key, kind) {
+            ("router", VaultCredentialType::Password) => Some("hunter2".to_string()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_expands_vault_references() {
+        assert_eq!(expand_secrets("${vault:router}\r", vault).unwrap(), "hunter2\r");
+        assert_eq!(expand_secrets("${vault:router:password}", vault).unwrap(), "hunter2");
+        assert_eq!(expand_secrets("no secret $1", vault).unwrap(), "no secret $1");
+        assert!(expand_secrets("${vault:router:passphrase}", vault).is_err());
+        assert!(expand_secrets("${vault:router", vault).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_labels_and_bad_patterns() {
+        assert!(CompiledScript::new(&script(vec![ScriptStep::Goto { label: "x".to_string() }])).is_err());
+        assert!(CompiledScript::new(&script(vec![expect("(", None)])).is_err());
+        assert!(CompiledScript::new(&script(vec![
+            ScriptStep::Label { name: "a".to_string() },
+            ScriptStep::Label { name: "a".to_string() },
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_earliest_match_is_consumed() {
+        let mut buffer = ExpectBuffer::new();
+        buffer.feed(b"\x1b[1mrouter>\x1b[0m Password:");
+        let patterns = [Regex::new("Password:").unwrap(), Regex::new(">").unwrap()];
+        assert_eq!(buffer.consume(&patterns), Some(1));
+        assert_eq!(buffer.consume(&patterns), Some(0));
+        assert_eq!(buffer.consume(&patterns), None);
+    }
+
+    #[tokio::test]
+    async fn test_runs_an_enable_sequence() {
+        let compiled = CompiledScript::new(&script(vec![
+            send("enable\r"),
+            ScriptStep::Expect {
+                cases: vec![
+                    ExpectCase { pattern: "Password:".to_string(), goto: None },
+                    ExpectCase { pattern: "#".to_string(), goto: Some("enabled".to_string()) },
+                ],
+                timeout_ms: None,
+                on_timeout: None,
+            },
+            send("${vault:router}\r"),
+            expect("#", None),
+            ScriptStep::Label { name: "enabled".to_string() },
+            send("terminal length 0\r"),
+        ]))
+        .unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(b"enable\r\nPassword: ".to_vec()).unwrap();
+        tx.send(b"\r\nrouter#".to_vec()).unwrap();
+        let sent = RefCell::new(Vec::new());
+        let steps = run_script(
+            &compiled,
+            &mut rx,
+            |text, secret| {
+                sent.borrow_mut().push((text.to_string(), secret));
+                Ok(())
+            },
+            vault,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(steps, 6);
+        assert_eq!(
+            sent.into_inner(),
+            vec![
+                ("enable\r".to_string(), false),
+                ("hunter2\r".to_string(), true),
+                ("terminal length 0\r".to_string(), false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_times_out_or_branches() {
+        let (_tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let failing = CompiledScript::new(&script(vec![expect("never", None)])).unwrap();
+        let error = run_script(&failing, &mut rx, |_, _| Ok(()), vault, |_| {}).await.unwrap_err();
+        assert!(error.contains("Timed out"));
+
+        let branching = CompiledScript::new(&script(vec![
+            ScriptStep::Expect {
+                cases: vec![ExpectCase { pattern: "never".to_string(), goto: None }],
+                timeout_ms: Some(10),
+                on_timeout: Some("fallback".to_string()),
+            },
+            ScriptStep::Fail { message: "matched".to_string() },
+            ScriptStep::Label { name: "fallback".to_string() },
+        ]))
+        .unwrap();
+        assert_eq!(run_script(&branching, &mut rx, |_, _| Ok(()), vault, |_| {}).await, Ok(2));
+    }
+}
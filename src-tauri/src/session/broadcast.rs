@@ -0,0 +1,205 @@
+//! Session groups for broadcasting input (cluster mode)
+//!
+//! Input written to a group is sent to every member that isn't temporarily
+//! excluded. A session belongs to at most one group: adding it to another
+//! moves it.
+
+use std::collections::{HashMap, HashSet};
+
+/// A group as shown to the frontend
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GroupInfo {
+    pub id: String,
+    pub name: String,
+    pub members: Vec<String>,
+    /// Members that currently don't receive broadcast input
+    pub excluded: Vec<String>,
+}
+
+/// Broadcast state of one session, emitted as `broadcast-state-{session_id}`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BroadcastState {
+    pub session_id: String,
+    pub group_id: Option<String>,
+    pub group_name: Option<String>,
+    pub excluded: bool,
+}
+
+/// Outcome of a broadcast write
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct BroadcastResult {
+    pub sent: Vec<String>,
+    pub failed: Vec<BroadcastFailure>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BroadcastFailure {
+    pub session_id: String,
+    pub error: String,
+}
+
+struct Group {
+    name: String,
+    /// In the order they were added
+    members: Vec<String>,
+    excluded: HashSet<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct SessionGroups {
+    groups: HashMap<String, Group>,
+}
+
+impl SessionGroups {
+    fn group(&self, group_id: &str) -> Result<&Group, String> {
+        self.groups.get(group_id).ok_or_else(|| "Group not found".to_string())
+    }
+
+    fn info(&self, id: &str, group: &Group) -> GroupInfo {
+        GroupInfo {
+            id: id.to_string(),
+            name: group.name.clone(),
+            members: group.members.clone(),
+            excluded: group
+                .members
+                .iter()
+                .filter(|m| group.excluded.contains(*m))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Create a group. Returns it and the sessions whose state changed
+    /// (including those moved out of other groups).
+    pub(crate) fn create(&mut self, name: String, members: Vec<String>) -> (GroupInfo, Vec<String>) {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.groups.insert(id.clone(), Group { name, members: Vec::new(), excluded: HashSet::new() });
+        let mut affected = Vec::new();
+        for session_id in members {
+            // Can't fail: the group was just created
+            if let Ok(changed) = self.add(&id, &session_id) {
+                affected.extend(changed);
+            }
+        }
+        (self.info(&id, &self.groups[&id]), affected)
+    }
+
+    /// Delete a group. Returns its former members.
+    pub(crate) fn delete(&mut self, group_id: &str) -> Result<Vec<String>, String> {
+        self.groups
+            .remove(group_id)
+            .map(|g| g.members)
+            .ok_or_else(|| "Group not found".to_string())
+    }
+
+    /// Add a session, moving it out of its current group.
+    /// Returns the sessions whose state changed.
+    pub(crate) fn add(&mut self, group_id: &str, session_id: &str) -> Result<Vec<String>, String> {
+        self.group(group_id)?;
+        if self.group_of(session_id).as_deref() == Some(group_id) {
+            return Ok(Vec::new());
+        }
+        self.remove_session(session_id);
+        if let Some(group) = self.groups.get_mut(group_id) {
+            group.members.push(session_id.to_string());
+        }
+        Ok(vec![session_id.to_string()])
+    }
+
+    /// Take a session out of its group. Returns the group it was in.
+    pub(crate) fn remove_session(&mut self, session_id: &str) -> Option<String> {
+        let group_id = self.group_of(session_id)?;
+        if let Some(group) = self.groups.get_mut(&group_id) {
+            group.members.retain(|m| m != session_id);
+            group.excluded.remove(session_id);
+        }
+        Some(group_id)
+    }
+
+    /// Temporarily stop (or resume) sending broadcast input to a member
+    pub(crate) fn set_excluded(&mut self, group_id: &str, session_id: &str, excluded: bool) -> Result<(), String> {
+        let group = self
+            .groups
+            .get_mut(group_id)
+            .ok_or_else(|| "Group not found".to_string())?;
+        if !group.members.iter().any(|m| m == session_id) {
+            return Err("Session is not in this group".to_string());
+        }
+        if excluded {
+            group.excluded.insert(session_id.to_string());
+        } else {
+            group.excluded.remove(session_id);
+        }
+        Ok(())
+    }
+
+    /// Members that receive broadcast input
+    pub(crate) fn targets(&self, group_id: &str) -> Result<Vec<String>, String> {
+        let group = self.group(group_id)?;
+        Ok(group
+            .members
+            .iter()
+            .filter(|m| !group.excluded.contains(*m))
+            .cloned()
+            .collect())
+    }
+
+    pub(crate) fn group_of(&self, session_id: &str) -> Option<String> {
+        self.groups
+            .iter()
+            .find(|(_, g)| g.members.iter().any(|m| m == session_id))
+            .map(|(id, _)| id.clone())
+    }
+
+    pub(crate) fn state_of(&self, session_id: &str) -> BroadcastState {
+        let group = self.group_of(session_id).and_then(|id| self.groups.get(&id).map(|g| (id, g)));
+        BroadcastState {
+            session_id: session_id.to_string(),
+            excluded: group.as_ref().map_or(false, |(_, g)| g.excluded.contains(session_id)),
+            group_name: group.as_ref().map(|(_, g)| g.name.clone()),
+            group_id: group.map(|(id, _)| id),
+        }
+    }
+
+    pub(crate) fn list(&self) -> Vec<GroupInfo> {
+        let mut groups: Vec<GroupInfo> = self.groups.iter().map(|(id, g)| self.info(id, g)).collect();
+        groups.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_broadcasts_to_members_not_excluded() {
+        let mut groups = SessionGroups::default();
+        let (group, _) = groups.create("web".to_string(), ids(&["a", "b", "c"]));
+        groups.set_excluded(&group.id, "b", true).unwrap();
+        assert_eq!(groups.targets(&group.id).unwrap(), ids(&["a", "c"]));
+        assert!(groups.state_of("b").excluded);
+
+        groups.set_excluded(&group.id, "b", false).unwrap();
+        assert_eq!(groups.targets(&group.id).unwrap(), ids(&["a", "b", "c"]));
+        assert!(groups.set_excluded(&group.id, "z", true).is_err());
+    }
+
+    #[test]
+    fn test_sessions_move_between_groups() {
+        let mut groups = SessionGroups::default();
+        let (web, _) = groups.create("web".to_string(), ids(&["a", "b"]));
+        let (db, affected) = groups.create("db".to_string(), ids(&["b", "c"]));
+        assert_eq!(affected, ids(&["b", "c"]));
+        assert_eq!(groups.targets(&web.id).unwrap(), ids(&["a"]));
+        assert_eq!(groups.state_of("b").group_id.as_deref(), Some(db.id.as_str()));
+
+        assert_eq!(groups.remove_session("c"), Some(db.id.clone()));
+        assert_eq!(groups.state_of("c").group_id, None);
+        assert_eq!(groups.delete(&db.id).unwrap(), ids(&["b"]));
+    }
+}
@@ -0,0 +1,152 @@
+//! Character encodings of session I/O
+//!
+//! Output is decoded as it is received with a stateful decoder, so
+//! multi-byte characters split between reads or batches are kept intact.
+//! Serial and telnet devices often use legacy encodings: any WHATWG label
+//! known to encoding_rs is accepted (latin1, shift_jis, koi8-r...), plus
+//! CP437, which encoding_rs doesn't provide. Input is encoded back before
+//! being written to the session.
+
+use encoding_rs::{Decoder, Encoding, UTF_8};
+
+/// Characters of CP437 bytes 0x80-0xFF (lower half is ASCII)
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+lazy_static::lazy_static! {
+    static ref CP437_TABLE: Vec<char> = CP437_HIGH.chars().collect();
+}
+
+/// Character encoding of a session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionEncoding {
+    Standard(&'static Encoding),
+    Cp437,
+}
+
+impl Default for SessionEncoding {
+    fn default() -> Self {
+        SessionEncoding::Standard(UTF_8)
+    }
+}
+
+impl SessionEncoding {
+    /// Look up an encoding by label ("utf-8", "latin1", "cp437", "shift_jis"...)
+    pub fn from_label(label: &str) -> Result<Self, String> {
+        let label = label.trim();
+        match label.to_ascii_lowercase().as_str() {
+            "cp437" | "ibm437" | "437" => Ok(SessionEncoding::Cp437),
+            _ => Encoding::for_label(label.as_bytes())
+                .map(SessionEncoding::Standard)
+                .ok_or_else(|| format!("Unknown encoding: {}", label)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionEncoding::Standard(encoding) => encoding.name(),
+            SessionEncoding::Cp437 => "IBM437",
+        }
+    }
+
+    pub fn is_utf8(&self) -> bool {
+        *self == SessionEncoding::Standard(UTF_8)
+    }
+
+    pub(crate) fn decoder(&self) -> OutputDecoder {
+        OutputDecoder {
+            encoding: *self,
+            decoder: match self {
+                // No BOM sniffing: output is a stream, not a document
+                SessionEncoding::Standard(encoding) => Some(encoding.new_decoder_without_bom_handling()),
+                SessionEncoding::Cp437 => None,
+            },
+        }
+    }
+
+    /// Encode typed text. Unmappable characters become numeric character
+    /// references for encoding_rs encodings and `?` for CP437.
+    pub(crate) fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            SessionEncoding::Standard(encoding) => encoding.encode(text).0.into_owned(),
+            SessionEncoding::Cp437 => text
+                .chars()
+                .map(|c| match c {
+                    '\0'..='\u{7f}' => c as u8,
+                    _ => CP437_TABLE
+                        .iter()
+                        .position(|&t| t == c)
+                        .map_or(b'?', |i| 0x80 + i as u8),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Stateful decoder for a session's output
+pub(crate) struct OutputDecoder {
+    encoding: SessionEncoding,
+    /// None for CP437 (single-byte, needs no state)
+    decoder: Option<Decoder>,
+}
+
+impl OutputDecoder {
+    pub(crate) fn encoding(&self) -> SessionEncoding {
+        self.encoding
+    }
+
+    /// Decode a chunk; an incomplete trailing sequence is kept for the next one
+    pub(crate) fn decode(&mut self, bytes: &[u8]) -> String {
+        match &mut self.decoder {
+            Some(decoder) => {
+                let capacity = decoder
+                    .max_utf8_buffer_length(bytes.len())
+                    .unwrap_or(bytes.len() * 3 + 16);
+                let mut text = String::with_capacity(capacity);
+                let _ = decoder.decode_to_string(bytes, &mut text, false);
+                text
+            }
+            None => bytes
+                .iter()
+                .map(|&b| if b < 0x80 { b as char } else { CP437_TABLE[(b - 0x80) as usize] })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cp437_table_is_complete() {
+        assert_eq!(CP437_TABLE.len(), 128);
+        let encoding = SessionEncoding::from_label("CP437").unwrap();
+        assert_eq!(encoding.decoder().decode(&[0x41, 0xc9, 0xcd, 0xbb]), "A╔═╗");
+        assert_eq!(encoding.encode("A╔═╗€"), vec![0x41, 0xc9, 0xcd, 0xbb, b'?']);
+    }
+
+    #[test]
+    fn test_utf8_split_across_chunks() {
+        let mut decoder = SessionEncoding::default().decoder();
+        let bytes = "→ ok".as_bytes();
+        assert_eq!(decoder.decode(&bytes[..2]), "");
+        assert_eq!(decoder.decode(&bytes[2..]), "→ ok");
+    }
+
+    #[test]
+    fn test_legacy_encodings() {
+        let latin1 = SessionEncoding::from_label("latin1").unwrap();
+        assert_eq!(latin1.decoder().decode(&[0x63, 0x61, 0x66, 0xe9]), "café");
+        assert_eq!(latin1.encode("café"), vec![0x63, 0x61, 0x66, 0xe9]);
+
+        let sjis = SessionEncoding::from_label("shift_jis").unwrap();
+        let mut decoder = sjis.decoder();
+        // "日本" split in the middle of the first character
+        assert_eq!(decoder.decode(&[0x93]), "");
+        assert_eq!(decoder.decode(&[0xfa, 0x96, 0x7b]), "日本");
+
+        assert!(SessionEncoding::from_label("klingon").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Flow control and batching of session output
+//!
+//! Each session has a budget of output received but not yet emitted to the
+//! frontend. Once it goes over `HIGH_WATERMARK` the connector stops reading
+//! from its source (PTY, SSH channel, socket, serial port) until the batch
+//! worker has emitted enough to bring it under `LOW_WATERMARK`: the kernel
+//! and TCP buffers, then the program producing the output, are held back
+//! instead of memory growing without bounds.
+//!
+//! Batches adapt to the throughput of the session: interactive output is
+//! emitted after a few milliseconds, bulk output in fewer, larger events,
+//! with a cap on what each event carries so the webview keeps up.
+
+use parking_lot::{Condvar, Mutex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc::{SendError, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::manager::OutputMessage;
+
+/// Pending output at which a session's source is paused
+pub const HIGH_WATERMARK: usize = 1024 * 1024;
+/// Pending output under which a paused source resumes
+pub const LOW_WATERMARK: usize = 256 * 1024;
+
+const MIN_BATCH_INTERVAL: Duration = Duration::from_millis(4);
+const MAX_BATCH_INTERVAL: Duration = Duration::from_millis(32);
+/// A batch at least this large is bulk output: the next one waits longer
+const BULK_BATCH_SIZE: usize = 16 * 1024;
+/// Most output emitted in one event (~8 MB/s at the longest interval)
+const MAX_EMIT_SIZE: usize = 256 * 1024;
+
+/// What happens to the output of a session whose tab is hidden
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HiddenOutput {
+    /// Emit it as usual
+    #[default]
+    Keep,
+    /// Don't emit it; the view is told how much it missed when shown again
+    Drop,
+    /// Don't emit it; when shown again, emit a note and the end of it
+    Summarize,
+}
+
+/// Output of a hidden session that wasn't emitted
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct HiddenState {
+    pub(crate) mode: HiddenOutput,
+    pub(crate) skipped: u64,
+}
+
+#[derive(Default)]
+struct Gate {
+    pending: usize,
+    paused: bool,
+}
+
+/// Pending output of each session, and the sources waiting for it to drain
+#[derive(Default)]
+pub(crate) struct FlowControl {
+    gates: Mutex<HashMap<String, Gate>>,
+    /// Wakes reader threads
+    resumed: Condvar,
+    /// Wakes async reader tasks
+    notify: tokio::sync::Notify,
+}
+
+impl FlowControl {
+    pub(crate) fn queued(&self, session_id: &str, bytes: usize) {
+        let mut gates = self.gates.lock();
+        let gate = gates.entry(session_id.to_string()).or_default();
+        gate.pending += bytes;
+        if gate.pending >= HIGH_WATERMARK {
+            gate.paused = true;
+        }
+    }
+
+    /// Output was emitted (or dropped) by the batch worker
+    pub(crate) fn released(&self, session_id: &str, bytes: usize) {
+        let mut gates = self.gates.lock();
+        let Some(gate) = gates.get_mut(session_id) else { return };
+        gate.pending = gate.pending.saturating_sub(bytes);
+        if gate.paused && gate.pending <= LOW_WATERMARK {
+            gate.paused = false;
+            drop(gates);
+            self.wake();
+        }
+    }
+
+    pub(crate) fn is_paused(&self, session_id: &str) -> bool {
+        self.gates.lock().get(session_id).map_or(false, |g| g.paused)
+    }
+
+    /// Forget a closed session, releasing its source if it was paused
+    pub(crate) fn remove(&self, session_id: &str) {
+        if self.gates.lock().remove(session_id).is_some() {
+            self.wake();
+        }
+    }
+
+    fn wake(&self) {
+        self.resumed.notify_all();
+        self.notify.notify_waiters();
+    }
+
+    fn wait_blocking(&self, session_id: &str) {
+        let mut gates = self.gates.lock();
+        while gates.get(session_id).map_or(false, |g| g.paused) {
+            self.resumed.wait(&mut gates);
+        }
+    }
+
+    async fn wait(&self, session_id: &str) {
+        loop {
+            // Registered before checking, so a wake in between isn't missed
+            let notified = self.notify.notified();
+            if !self.is_paused(session_id) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Sends session output to the batch worker, accounting for it so the
+/// connector can be paused while too much is pending
+#[derive(Clone)]
+pub struct OutputSender {
+    tx: Sender<OutputMessage>,
+    flow: Arc<FlowControl>,
+}
+
+impl OutputSender {
+    pub(crate) fn new(tx: Sender<OutputMessage>, flow: Arc<FlowControl>) -> Self {
+        OutputSender { tx, flow }
+    }
+
+    pub fn send(&self, msg: OutputMessage) -> Result<(), SendError<OutputMessage>> {
+        self.flow.queued(&msg.session_id, msg.data.len());
+        self.tx.send(msg)
+    }
+
+    /// Whether the session's source should stop reading for now
+    pub fn is_paused(&self, session_id: &str) -> bool {
+        self.flow.is_paused(session_id)
+    }
+
+    /// Block until the session may read again (for reader threads)
+    pub fn wait_ready(&self, session_id: &str) {
+        self.flow.wait_blocking(session_id);
+    }
+
+    /// Wait until the session may read again (for async reader tasks)
+    pub async fn ready(&self, session_id: &str) {
+        self.flow.wait(session_id).await;
+    }
+}
+
+/// Output of one session waiting to be emitted
+pub(crate) struct OutputBatch {
+    data: Vec<u8>,
+    /// When the oldest byte not yet emitted arrived
+    since: Option<Instant>,
+    interval: Duration,
+}
+
+impl OutputBatch {
+    pub(crate) fn new() -> Self {
+        OutputBatch {
+            data: Vec::new(),
+            since: None,
+            interval: MIN_BATCH_INTERVAL,
+        }
+    }
+
+    pub(crate) fn push(&mut self, data: &[u8], now: Instant) {
+        if self.since.is_none() {
+            self.since = Some(now);
+        }
+        self.data.extend_from_slice(data);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// When the batch should be emitted
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.since.map(|since| since + self.interval)
+    }
+
+    pub(crate) fn is_due(&self, now: Instant) -> bool {
+        self.deadline().map_or(false, |deadline| now >= deadline)
+    }
+
+    /// Take what may be emitted now: at most `MAX_EMIT_SIZE`, cut before a
+    /// UTF-8 continuation byte. A large batch makes the next one wait
+    /// longer, a small one brings the interval back down.
+    pub(crate) fn take(&mut self, now: Instant) -> Vec<u8> {
+        let mut end = self.data.len().min(MAX_EMIT_SIZE);
+        while end > 0 && end < self.data.len() && self.data[end] & 0xc0 == 0x80 {
+            end -= 1;
+        }
+        let rest = self.data.split_off(end);
+        let data = std::mem::replace(&mut self.data, rest);
+
+        self.interval = if data.len() >= BULK_BATCH_SIZE {
+            (self.interval * 2).min(MAX_BATCH_INTERVAL)
+        } else {
+            (self.interval / 2).max(MIN_BATCH_INTERVAL)
+        };
+        self.since = (!self.data.is_empty()).then_some(now);
+        data
+    }
+
+    /// Take everything (output that won't be emitted)
+    pub(crate) fn take_all(&mut self) -> Vec<u8> {
+        self.since = None;
+        std::mem::take(&mut self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pauses_over_high_watermark_until_under_low() {
+        let flow = FlowControl::default();
+        flow.queued("s", HIGH_WATERMARK - 1);
+        assert!(!flow.is_paused("s"));
+        flow.queued("s", 1);
+        assert!(flow.is_paused("s"));
+
+        flow.released("s", HIGH_WATERMARK - LOW_WATERMARK - 1);
+        assert!(flow.is_paused("s"));
+        flow.released("s", 1);
+        assert!(!flow.is_paused("s"));
+
+        flow.queued("s", HIGH_WATERMARK);
+        flow.remove("s");
+        assert!(!flow.is_paused("s"));
+    }
+
+    #[test]
+    fn test_batch_interval_follows_throughput() {
+        let start = Instant::now();
+        let mut batch = OutputBatch::new();
+        batch.push(&vec![b'x'; BULK_BATCH_SIZE], start);
+        assert!(!batch.is_due(start));
+        assert!(batch.is_due(start + MIN_BATCH_INTERVAL));
+
+        batch.take(start);
+        assert_eq!(batch.interval, MIN_BATCH_INTERVAL * 2);
+        assert!(batch.deadline().is_none());
+
+        batch.push(b"$ ", start);
+        batch.take(start);
+        assert_eq!(batch.interval, MIN_BATCH_INTERVAL);
+    }
+
+    #[test]
+    fn test_large_batches_are_cut_on_character_boundaries() {
+        let now = Instant::now();
+        let mut batch = OutputBatch::new();
+        let mut data = vec![b'x'; MAX_EMIT_SIZE - 1];
+        data.extend("é".as_bytes());
+        batch.push(&data, now);
+
+        assert_eq!(batch.take(now).len(), MAX_EMIT_SIZE - 1);
+        assert_eq!(batch.deadline(), Some(now + batch.interval));
+        assert_eq!(batch.take_all(), "é".as_bytes());
+        assert!(batch.is_empty());
+    }
+}
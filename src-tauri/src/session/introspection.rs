@@ -0,0 +1,67 @@
+//! Introspection of open sessions
+//!
+//! The manager keeps counters and timestamps for each registered session
+//! and lists them, with what else it knows of the session (target, logging,
+//! recording, SFTP connection), for the UI and plugins.
+
+use crate::connectors::sftp::SftpPoolInfo;
+use crate::tunnels::TunnelInfo;
+
+/// Traffic and activity of one session
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SessionStats {
+    pub(crate) started_at: i64,
+    pub(crate) last_activity: i64,
+    pub(crate) bytes_in: u64,
+    pub(crate) bytes_out: u64,
+}
+
+impl SessionStats {
+    pub(crate) fn new() -> Self {
+        let now = chrono::Utc::now().timestamp();
+        SessionStats {
+            started_at: now,
+            last_activity: now,
+            bytes_in: 0,
+            bytes_out: 0,
+        }
+    }
+
+    /// Output received from the session
+    pub(crate) fn received(&mut self, bytes: usize) {
+        self.bytes_in += bytes as u64;
+        self.last_activity = chrono::Utc::now().timestamp();
+    }
+
+    /// Input written to the session
+    pub(crate) fn sent(&mut self, bytes: usize) {
+        self.bytes_out += bytes as u64;
+        self.last_activity = chrono::Utc::now().timestamp();
+    }
+}
+
+/// An open session, as listed for the UI and plugins
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ActiveSession {
+    pub session_id: String,
+    /// "local", "ssh", "telnet", "serial", "tmux" or "playback"
+    pub session_type: String,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    /// Unix timestamps (seconds)
+    pub started_at: i64,
+    pub last_activity: i64,
+    /// Output received from the session (before decoding)
+    pub bytes_in: u64,
+    /// Input written to the session
+    pub bytes_out: u64,
+    pub encoding: String,
+    pub recording: bool,
+    pub log_path: Option<String>,
+    pub broadcast_group: Option<String>,
+    /// Tunnels opened through the session's connection
+    pub tunnels: Vec<TunnelInfo>,
+    /// Pooled SFTP connection of the session
+    pub sftp: Option<SftpPoolInfo>,
+}
@@ -0,0 +1,307 @@
+//! Plain-text session logging
+//!
+//! Session output is appended to a log file named from a template, with an
+//! optional timestamp at the start of each line and ANSI escape sequences
+//! optionally removed. Files are rotated once they reach a size limit:
+//! `name.log` becomes `name.log.1`, `name.log.1` becomes `name.log.2`, and
+//! so on up to the configured number of files.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::storage::LoggingSettings;
+
+/// What a log file is named after
+#[derive(Debug, Clone)]
+pub struct LogTarget {
+    pub host: String,
+    pub port: Option<u16>,
+    pub user: String,
+}
+
+impl LogTarget {
+    /// The local machine, for local shells
+    pub fn local() -> Self {
+        LogTarget {
+            host: "localhost".to_string(),
+            port: None,
+            user: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| "user".to_string()),
+        }
+    }
+}
+
+/// Default log directory: ~/.simplyterm/logs
+fn default_log_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    Ok(home.join(".simplyterm").join("logs"))
+}
+
+/// Keep template values from adding path components
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '.' | '_' | '@') { c } else { '_' })
+        .collect()
+}
+
+/// Expand `{host}`, `{user}`, `{port}`, `{session}`, `{date}` (YYYY-MM-DD)
+/// and `{time}` (HHMMSS) in a file name template
+fn expand_template(
+    template: &str,
+    target: &LogTarget,
+    session_id: &str,
+    now: chrono::DateTime<chrono::Local>,
+) -> String {
+    let name = template
+        .replace("{host}", &sanitize(&target.host))
+        .replace("{user}", &sanitize(&target.user))
+        .replace("{port}", &target.port.map(|p| p.to_string()).unwrap_or_default())
+        .replace("{session}", &sanitize(session_id))
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string());
+    let name = sanitize_file_name(&name);
+    if name.is_empty() {
+        format!("{}.log", sanitize(session_id))
+    } else {
+        name
+    }
+}
+
+/// Remove path separators and characters not allowed in file names
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AnsiState {
+    Normal,
+    Escape,
+    /// ESC followed by an intermediate byte (charset selection etc.)
+    EscapeIntermediate,
+    Csi,
+    /// OSC, DCS, APC...: string terminated by BEL or ESC \
+    String,
+    StringEscape,
+}
+
+/// Removes escape sequences and control characters (except newline and tab)
+/// from a byte stream. Sequences may be split across chunks.
+pub(crate) struct AnsiStripper {
+    state: AnsiState,
+}
+
+impl AnsiStripper {
+    pub(crate) fn new() -> Self {
+        AnsiStripper { state: AnsiState::Normal }
+    }
+
+    pub(crate) fn strip(&mut self, data: &[u8], out: &mut Vec<u8>) {
+        for &byte in data {
+            self.state = match self.state {
+                AnsiState::Normal => match byte {
+                    0x1b => AnsiState::Escape,
+                    b'\n' | b'\t' => {
+                        out.push(byte);
+                        AnsiState::Normal
+                    }
+                    0x00..=0x1f | 0x7f => AnsiState::Normal,
+                    _ => {
+                        out.push(byte);
+                        AnsiState::Normal
+                    }
+                },
+                AnsiState::Escape => match byte {
+                    b'[' => AnsiState::Csi,
+                    b']' | b'P' | b'X' | b'^' | b'_' => AnsiState::String,
+                    0x20..=0x2f => AnsiState::EscapeIntermediate,
+                    _ => AnsiState::Normal,
+                },
+                AnsiState::EscapeIntermediate => AnsiState::Normal,
+                AnsiState::Csi => match byte {
+                    0x40..=0x7e => AnsiState::Normal,
+                    _ => AnsiState::Csi,
+                },
+                AnsiState::String => match byte {
+                    0x07 => AnsiState::Normal,
+                    0x1b => AnsiState::StringEscape,
+                    _ => AnsiState::String,
+                },
+                AnsiState::StringEscape => match byte {
+                    b'\\' => AnsiState::Normal,
+                    _ => AnsiState::String,
+                },
+            };
+        }
+    }
+}
+
+/// Appends one session's output to its log file
+pub(crate) struct SessionLogger {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    /// Bytes in the current file
+    written: u64,
+    at_line_start: bool,
+    timestamps: bool,
+    stripper: Option<AnsiStripper>,
+    max_size: Option<u64>,
+    max_files: u32,
+    dirty: bool,
+}
+
+impl SessionLogger {
+    pub(crate) fn open(session_id: &str, target: &LogTarget, settings: &LoggingSettings) -> Result<Self, String> {
+        let dir = match settings.directory.as_deref().filter(|d| !d.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => default_log_dir()?,
+        };
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+
+        let name = expand_template(&settings.filename_template, target, session_id, chrono::Local::now());
+        let path = dir.join(name);
+        let file = open_append(&path)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(SessionLogger {
+            path,
+            writer: BufWriter::new(file),
+            written,
+            at_line_start: true,
+            timestamps: settings.timestamps,
+            stripper: settings.strip_ansi.then(AnsiStripper::new),
+            max_size: (settings.max_file_size_mb > 0).then(|| settings.max_file_size_mb * 1024 * 1024),
+            max_files: settings.max_files,
+            dirty: false,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        let stripped;
+        let data = match &mut self.stripper {
+            Some(stripper) => {
+                let mut out = Vec::with_capacity(data.len());
+                stripper.strip(data, &mut out);
+                stripped = out;
+                &stripped[..]
+            }
+            None => data,
+        };
+
+        for line in data.split_inclusive(|&b| b == b'\n') {
+            if self.at_line_start {
+                // Rotate between lines so a line is never split across files
+                if self.max_size.map_or(false, |max| self.written >= max) {
+                    self.rotate()?;
+                }
+                if self.timestamps {
+                    let stamp = chrono::Local::now().format("[%Y-%m-%d %H:%M:%S%.3f] ").to_string();
+                    self.write_raw(stamp.as_bytes())?;
+                }
+            }
+            self.write_raw(line)?;
+            self.at_line_start = line.ends_with(b"\n");
+        }
+        Ok(())
+    }
+
+    fn write_raw(&mut self, data: &[u8]) -> Result<(), String> {
+        self.writer
+            .write_all(data)
+            .map_err(|e| format!("Failed to write log: {}", e))?;
+        self.written += data.len() as u64;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Shift `path.N` to `path.N+1` (dropping the oldest) and start a new file
+    fn rotate(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| format!("Failed to write log: {}", e))?;
+
+        if self.max_files == 0 {
+            // No backups kept: start the file over
+            let file = File::create(&self.path).map_err(|e| format!("Failed to rotate log: {}", e))?;
+            self.writer = BufWriter::new(file);
+            self.written = 0;
+            return Ok(());
+        }
+
+        let numbered = |n: u32| PathBuf::from(format!("{}.{}", self.path.to_string_lossy(), n));
+        let _ = std::fs::remove_file(numbered(self.max_files));
+        for n in (1..self.max_files).rev() {
+            let _ = std::fs::rename(numbered(n), numbered(n + 1));
+        }
+        std::fs::rename(&self.path, numbered(1)).map_err(|e| format!("Failed to rotate log: {}", e))?;
+
+        self.writer = BufWriter::new(open_append(&self.path)?);
+        self.written = 0;
+        Ok(())
+    }
+
+    /// Write buffered output to disk (called when output goes idle)
+    pub(crate) fn flush(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        self.writer.flush().map_err(|e| format!("Failed to write log: {}", e))
+    }
+}
+
+fn open_append(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open log file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn strip(chunks: &[&[u8]]) -> String {
+        let mut stripper = AnsiStripper::new();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            stripper.strip(chunk, &mut out);
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_strips_escape_sequences() {
+        assert_eq!(strip(&[b"\x1b[1;31mred\x1b[0m\r\n"]), "red\n");
+        assert_eq!(strip(&[b"\x1b]0;title\x07prompt$ "]), "prompt$ ");
+        assert_eq!(strip(&[b"\x1b]8;;http://x\x1b\\link"]), "link");
+        assert_eq!(strip(&[b"\x1b(Bok\x1b=\x07"]), "ok");
+    }
+
+    #[test]
+    fn test_strips_sequences_split_across_chunks() {
+        assert_eq!(strip(&[b"a\x1b", b"[3", b"2mb"]), "ab");
+    }
+
+    #[test]
+    fn test_expands_file_name_templates() {
+        let target = LogTarget { host: "db/1".to_string(), port: Some(22), user: "root".to_string() };
+        let now = chrono::Local.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        assert_eq!(
+            expand_template("{user}@{host}_{date}_{time}.log", &target, "s1", now),
+            "root@db_1_2026-03-04_050607.log"
+        );
+        assert_eq!(expand_template("../{host}:{port}.log", &target, "s1", now), "db_122.log");
+        assert_eq!(expand_template("", &target, "s1", now), "s1.log");
+    }
+}
@@ -1,86 +1,296 @@
-//! Centralized session manager with output batching
+//! Centralized session manager with flow-controlled output batching,
+//! recording and logging
 
 use parking_lot::Mutex;
 use std::collections::HashMap;
-use std::sync::mpsc::{self, Sender};
+use std::path::Path;
+use std::sync::mpsc;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
 
+use super::broadcast::{BroadcastFailure, BroadcastResult, BroadcastState, GroupInfo, SessionGroups};
+use super::encoding::{OutputDecoder, SessionEncoding};
+use super::flow::{FlowControl, HiddenOutput, HiddenState, OutputBatch, OutputSender};
+use super::introspection::{ActiveSession, SessionStats};
+use super::logging::{LogTarget, SessionLogger};
+use super::recording::{Recorder, RecordingInfo, RecordingOptions};
+use super::scrollback::{Scrollback, ScrollbackSnapshot, SCROLLBACK_BYTES};
 use super::traits::Session;
+use super::triggers::{TriggerEngine, TriggerFire};
 use crate::connectors::{SshConfig, SftpPool, new_sftp_pool, disconnect_sftp};
+use crate::connectors::sftp::sftp_pool_info;
+use crate::storage::{LoggingSettings, TriggerRule};
 
-const BATCH_INTERVAL_MS: u64 = 16; // ~60fps
-const BATCH_MAX_SIZE: usize = 64 * 1024; // 64KB max before forced flush
+/// How long the worker waits when no output is pending (recordings and logs
+/// are flushed to disk when it times out)
+const IDLE_INTERVAL: Duration = Duration::from_millis(16);
+/// End of the missed output shown when a summarized hidden session is shown
+const SUMMARY_BYTES: usize = 8 * 1024;
+const DEFAULT_SIZE: (u32, u32) = (80, 24);
+
+type Recordings = Arc<Mutex<HashMap<String, Recorder>>>;
+type Loggers = Arc<Mutex<HashMap<String, SessionLogger>>>;
+type Scrollbacks = Arc<Mutex<HashMap<String, Scrollback>>>;
+type Decoders = Arc<Mutex<HashMap<String, OutputDecoder>>>;
+type Hidden = Arc<Mutex<HashMap<String, HiddenState>>>;
+type Stats = Arc<Mutex<HashMap<String, SessionStats>>>;
+type LogTargets = Arc<Mutex<HashMap<String, LogTarget>>>;
+type OutputTaps = Arc<Mutex<HashMap<String, tokio::sync::mpsc::UnboundedSender<Vec<u8>>>>>;
+/// Runs the actions of triggers that fired (called from the batch worker)
+type TriggerHandler = Arc<dyn Fn(TriggerFire) + Send + Sync>;
+
+/// Worker state shared with the manager
+#[derive(Clone)]
+struct OutputSinks {
+    recordings: Recordings,
+    loggers: Loggers,
+    scrollbacks: Scrollbacks,
+    decoders: Decoders,
+    hidden: Hidden,
+    flow: Arc<FlowControl>,
+    stats: Stats,
+    log_targets: LogTargets,
+    triggers: Arc<Mutex<TriggerEngine>>,
+    trigger_handler: Arc<Mutex<Option<TriggerHandler>>>,
+    taps: OutputTaps,
+}
 
 pub struct OutputMessage {
     pub session_id: String,
     pub data: Vec<u8>,
 }
 
+/// Output a hidden session produced without emitting it
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SkippedOutput {
+    pub session_id: String,
+    pub bytes: u64,
+    /// End of the skipped output, when it was summarized
+    pub summary: Option<String>,
+}
+
 pub struct SessionManager {
     sessions: Mutex<HashMap<String, Box<dyn Session>>>,
     ssh_configs: Mutex<HashMap<String, SshConfig>>,
-    output_tx: Sender<OutputMessage>,
+    output_tx: OutputSender,
     sftp_pool: SftpPool,
+    /// Active recordings, written by the batch worker
+    recordings: Recordings,
+    /// Last known terminal size (cols, rows) of each session
+    sizes: Mutex<HashMap<String, (u32, u32)>>,
+    /// Active log files, written by the batch worker
+    loggers: Loggers,
+    /// Host/user each session's log files are named after
+    log_targets: LogTargets,
+    /// Recent output emitted for each session, to rebuild reattached views
+    scrollbacks: Scrollbacks,
+    /// Output decoders of sessions with a chosen encoding (others use UTF-8)
+    decoders: Decoders,
+    /// Broadcast groups (cluster mode)
+    groups: Mutex<SessionGroups>,
+    /// Sessions whose tab is hidden, and what happens to their output
+    hidden: Hidden,
+    /// Output pending per session, pausing connectors that produce too much
+    flow: Arc<FlowControl>,
+    /// Traffic and activity of each registered session
+    stats: Stats,
+    /// Output triggers, matched by the batch worker
+    triggers: Arc<Mutex<TriggerEngine>>,
+    trigger_handler: Arc<Mutex<Option<TriggerHandler>>>,
+    /// Copies of decoded output, for automation scripts
+    taps: OutputTaps,
 }
 
 impl SessionManager {
     pub fn new(app: AppHandle) -> Arc<Self> {
         let (output_tx, output_rx) = mpsc::channel();
+        let sinks = OutputSinks {
+            recordings: Arc::new(Mutex::new(HashMap::new())),
+            loggers: Arc::new(Mutex::new(HashMap::new())),
+            scrollbacks: Arc::new(Mutex::new(HashMap::new())),
+            decoders: Arc::new(Mutex::new(HashMap::new())),
+            hidden: Arc::new(Mutex::new(HashMap::new())),
+            flow: Arc::new(FlowControl::default()),
+            stats: Arc::new(Mutex::new(HashMap::new())),
+            log_targets: Arc::new(Mutex::new(HashMap::new())),
+            triggers: Arc::new(Mutex::new(TriggerEngine::default())),
+            trigger_handler: Arc::new(Mutex::new(None)),
+            taps: Arc::new(Mutex::new(HashMap::new())),
+        };
 
         let manager = Arc::new(Self {
             sessions: Mutex::new(HashMap::new()),
             ssh_configs: Mutex::new(HashMap::new()),
-            output_tx,
+            output_tx: OutputSender::new(output_tx, sinks.flow.clone()),
             sftp_pool: new_sftp_pool(),
+            recordings: sinks.recordings.clone(),
+            sizes: Mutex::new(HashMap::new()),
+            loggers: sinks.loggers.clone(),
+            log_targets: sinks.log_targets.clone(),
+            scrollbacks: sinks.scrollbacks.clone(),
+            decoders: sinks.decoders.clone(),
+            groups: Mutex::new(SessionGroups::default()),
+            hidden: sinks.hidden.clone(),
+            flow: sinks.flow.clone(),
+            stats: sinks.stats.clone(),
+            triggers: sinks.triggers.clone(),
+            trigger_handler: sinks.trigger_handler.clone(),
+            taps: sinks.taps.clone(),
         });
 
-        Self::spawn_batch_worker(app, output_rx);
+        Self::spawn_batch_worker(app, output_rx, sinks);
 
         manager
     }
 
-    fn spawn_batch_worker(app: AppHandle, output_rx: mpsc::Receiver<OutputMessage>) {
+    fn spawn_batch_worker(app: AppHandle, output_rx: mpsc::Receiver<OutputMessage>, sinks: OutputSinks) {
         thread::spawn(move || {
-            let mut buffers: HashMap<String, Vec<u8>> = HashMap::new();
-            let batch_duration = Duration::from_millis(BATCH_INTERVAL_MS);
+            let mut batches: HashMap<String, OutputBatch> = HashMap::new();
 
             loop {
-                match output_rx.recv_timeout(batch_duration) {
+                // Wake up for the next batch due, or after a while when idle
+                let timeout = batches
+                    .values()
+                    .filter_map(OutputBatch::deadline)
+                    .min()
+                    .map_or(IDLE_INTERVAL, |deadline| deadline.saturating_duration_since(Instant::now()));
+
+                match output_rx.recv_timeout(timeout) {
                     Ok(msg) => {
-                        let buffer = buffers.entry(msg.session_id.clone()).or_default();
-                        buffer.extend(msg.data);
+                        if let Some(stats) = sinks.stats.lock().get_mut(&msg.session_id) {
+                            stats.received(msg.data.len());
+                        }
+
+                        // Decode as received: batches then only hold whole characters
+                        let text = sinks
+                            .decoders
+                            .lock()
+                            .entry(msg.session_id.clone())
+                            .or_insert_with(|| SessionEncoding::default().decoder())
+                            .decode(&msg.data);
+                        let data = text.into_bytes();
 
-                        if buffer.len() >= BATCH_MAX_SIZE {
-                            Self::flush_buffer(&app, &msg.session_id, buffer);
+                        // Record and log as received, for accurate timing
+                        Self::apply(&sinks.recordings, "Recording", &msg.session_id, |r| r.output(&data));
+                        Self::apply(&sinks.loggers, "Logging", &msg.session_id, |l| l.write(&data));
+                        Self::run_triggers(&sinks, &msg.session_id, &data);
+                        Self::feed_tap(&sinks, &msg.session_id, &data);
+
+                        // From here on, pending output is counted decoded
+                        if data.len() != msg.data.len() {
+                            sinks.flow.released(&msg.session_id, msg.data.len());
+                            sinks.flow.queued(&msg.session_id, data.len());
                         }
+                        batches
+                            .entry(msg.session_id)
+                            .or_insert_with(OutputBatch::new)
+                            .push(&data, Instant::now());
                     }
                     Err(mpsc::RecvTimeoutError::Timeout) => {
-                        for (session_id, buffer) in buffers.iter_mut() {
-                            if !buffer.is_empty() {
-                                Self::flush_buffer(&app, session_id, buffer);
-                            }
+                        if batches.values().all(OutputBatch::is_empty) {
+                            Self::flush_files(&sinks);
                         }
                     }
                     Err(mpsc::RecvTimeoutError::Disconnected) => break,
                 }
+
+                let now = Instant::now();
+                for (session_id, batch) in batches.iter_mut() {
+                    if batch.is_due(now) {
+                        Self::flush_batch(&app, &sinks, session_id, batch, now);
+                    }
+                }
             }
         });
     }
 
-    fn flush_buffer(app: &AppHandle, session_id: &str, buffer: &mut Vec<u8>) {
-        if buffer.is_empty() {
+    /// Emit what is due of a session's output. Output of a hidden session
+    /// that isn't kept only goes to the scrollback.
+    fn flush_batch(app: &AppHandle, sinks: &OutputSinks, session_id: &str, batch: &mut OutputBatch, now: Instant) {
+        let (data, skipped) = {
+            let mut hidden = sinks.hidden.lock();
+            match hidden.get_mut(session_id).filter(|h| h.mode != HiddenOutput::Keep) {
+                Some(state) => {
+                    let data = batch.take_all();
+                    state.skipped += data.len() as u64;
+                    (data, true)
+                }
+                None => (batch.take(now), false),
+            }
+        };
+
+        sinks.flow.released(session_id, data.len());
+        sinks
+            .scrollbacks
+            .lock()
+            .entry(session_id.to_string())
+            .or_insert_with(|| Scrollback::new(SCROLLBACK_BYTES))
+            .push(&data);
+        if skipped {
+            return;
+        }
+        // Already decoded: batches only hold whole UTF-8 characters
+        let text = String::from_utf8(data)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).to_string());
+        let _ = app.emit(&format!("pty-output-{}", session_id), text);
+    }
+
+    fn run_triggers(sinks: &OutputSinks, session_id: &str, data: &[u8]) {
+        let host = || sinks.log_targets.lock().get(session_id).map(|t| t.host.clone());
+        let fires = sinks.triggers.lock().scan(session_id, data, host, Instant::now());
+        if fires.is_empty() {
             return;
         }
+        let handler = sinks.trigger_handler.lock().clone();
+        if let Some(handler) = handler {
+            for fire in fires {
+                handler(fire);
+            }
+        }
+    }
 
-        let data = std::mem::take(buffer);
-        let text = String::from_utf8_lossy(&data).to_string();
-        let _ = app.emit(&format!("pty-output-{}", session_id), text);
+    fn feed_tap(sinks: &OutputSinks, session_id: &str, data: &[u8]) {
+        let mut taps = sinks.taps.lock();
+        if let Some(tap) = taps.get(session_id) {
+            if tap.send(data.to_vec()).is_err() {
+                taps.remove(session_id);
+            }
+        }
+    }
+
+    fn flush_files(sinks: &OutputSinks) {
+        for recorder in sinks.recordings.lock().values_mut() {
+            if let Err(e) = recorder.flush() {
+                eprintln!("[Recording] {}", e);
+            }
+        }
+        for logger in sinks.loggers.lock().values_mut() {
+            if let Err(e) = logger.flush() {
+                eprintln!("[Logging] {}", e);
+            }
+        }
+    }
+
+    /// Apply `f` to the session's recorder or logger, if any. One that fails
+    /// to write is stopped.
+    fn apply<T>(
+        writers: &Arc<Mutex<HashMap<String, T>>>,
+        label: &str,
+        session_id: &str,
+        f: impl FnOnce(&mut T) -> Result<(), String>,
+    ) {
+        let mut writers = writers.lock();
+        if let Some(writer) = writers.get_mut(session_id) {
+            if let Err(e) = f(writer) {
+                eprintln!("[{}] Stopping for {}: {}", label, session_id, e);
+                writers.remove(session_id);
+            }
+        }
     }
 
-    pub fn output_sender(&self) -> Sender<OutputMessage> {
+    pub fn output_sender(&self) -> OutputSender {
         self.output_tx.clone()
     }
 
@@ -91,6 +301,7 @@ impl SessionManager {
 
     /// Registers a new session
     pub fn register(&self, session_id: String, session: Box<dyn Session>) {
+        self.stats.lock().insert(session_id.clone(), SessionStats::new());
         self.sessions.lock().insert(session_id, session);
     }
 
@@ -101,9 +312,33 @@ impl SessionManager {
 
     /// Écrit des données vers une session
     pub fn write(&self, session_id: &str, data: &[u8]) -> Result<(), String> {
+        self.write_input(session_id, data, true)
+    }
+
+    /// Write input that must not end up in recordings, such as a password
+    pub fn write_secret(&self, session_id: &str, data: &[u8]) -> Result<(), String> {
+        self.write_input(session_id, data, false)
+    }
+
+    fn write_input(&self, session_id: &str, data: &[u8], record: bool) -> Result<(), String> {
         let sessions = self.sessions.lock();
         if let Some(session) = sessions.get(session_id) {
-            session.write(data)
+            if record {
+                Self::apply(&self.recordings, "Recording", session_id, |r| r.input(data));
+            }
+            let encoded;
+            let data = match self.encoding(session_id) {
+                Some(encoding) if !encoding.is_utf8() => {
+                    encoded = encoding.encode(&String::from_utf8_lossy(data));
+                    &encoded[..]
+                }
+                _ => data,
+            };
+            session.write(data)?;
+            if let Some(stats) = self.stats.lock().get_mut(session_id) {
+                stats.sent(data.len());
+            }
+            Ok(())
         } else {
             Err("Session not found".to_string())
         }
@@ -111,6 +346,10 @@ impl SessionManager {
 
     /// Redimensionne une session
     pub fn resize(&self, session_id: &str, cols: u32, rows: u32) -> Result<(), String> {
+        if self.sizes.lock().insert(session_id.to_string(), (cols, rows)) != Some((cols, rows)) {
+            Self::apply(&self.recordings, "Recording", session_id, |r| r.resize(cols, rows));
+        }
+
         let sessions = self.sessions.lock();
         if let Some(session) = sessions.get(session_id) {
             session.resize(cols, rows)
@@ -123,6 +362,24 @@ impl SessionManager {
     pub fn close(&self, session_id: &str) -> Result<(), String> {
         // Also remove SSH config
         self.ssh_configs.lock().remove(session_id);
+        self.sizes.lock().remove(session_id);
+
+        // Finish the recording and log so the files are complete
+        if let Some(recorder) = self.recordings.lock().remove(session_id) {
+            if let Err(e) = recorder.finish() {
+                eprintln!("[Recording] {}", e);
+            }
+        }
+        let _ = self.stop_logging(session_id);
+        self.log_targets.lock().remove(session_id);
+        self.scrollbacks.lock().remove(session_id);
+        self.decoders.lock().remove(session_id);
+        self.groups.lock().remove_session(session_id);
+        self.hidden.lock().remove(session_id);
+        self.flow.remove(session_id);
+        self.stats.lock().remove(session_id);
+        self.triggers.lock().remove_session(session_id);
+        self.taps.lock().remove(session_id);
 
         // Clean up SFTP pool entry for this session
         let pool = self.sftp_pool.clone();
@@ -147,4 +404,236 @@ impl SessionManager {
     pub fn get_ssh_config(&self, session_id: &str) -> Option<SshConfig> {
         self.ssh_configs.lock().get(session_id).cloned()
     }
+
+    /// Start recording a session to an asciicast file
+    pub fn start_recording(
+        &self,
+        session_id: &str,
+        path: &Path,
+        options: &RecordingOptions,
+    ) -> Result<RecordingInfo, String> {
+        if !self.sessions.lock().contains_key(session_id) {
+            return Err("Session not found".to_string());
+        }
+        let mut recordings = self.recordings.lock();
+        if recordings.contains_key(session_id) {
+            return Err("Session is already being recorded".to_string());
+        }
+
+        let (cols, rows) = self.sizes.lock().get(session_id).copied().unwrap_or(DEFAULT_SIZE);
+        let recorder = Recorder::create(session_id, path, cols, rows, options)?;
+        let info = recorder.info();
+        recordings.insert(session_id.to_string(), recorder);
+        Ok(info)
+    }
+
+    /// Stop recording a session and close the file
+    pub fn stop_recording(&self, session_id: &str) -> Result<RecordingInfo, String> {
+        let recorder = self
+            .recordings
+            .lock()
+            .remove(session_id)
+            .ok_or_else(|| "Session is not being recorded".to_string())?;
+        recorder.finish()
+    }
+
+    /// Current recording of a session
+    pub fn recording_info(&self, session_id: &str) -> Option<RecordingInfo> {
+        self.recordings.lock().get(session_id).map(|r| r.info())
+    }
+
+    /// Remember what a session's log files are named after
+    pub fn set_log_target(&self, session_id: &str, target: LogTarget) {
+        self.log_targets.lock().insert(session_id.to_string(), target);
+    }
+
+    pub fn log_target(&self, session_id: &str) -> Option<LogTarget> {
+        self.log_targets.lock().get(session_id).cloned()
+    }
+
+    /// Start appending a session's output to a log file. Returns its path.
+    pub fn start_logging(&self, session_id: &str, settings: &LoggingSettings) -> Result<String, String> {
+        let mut loggers = self.loggers.lock();
+        if let Some(logger) = loggers.get(session_id) {
+            return Ok(logger.path().to_string_lossy().to_string());
+        }
+
+        let target = self.log_target(session_id).unwrap_or_else(LogTarget::local);
+        let logger = SessionLogger::open(session_id, &target, settings)?;
+        let path = logger.path().to_string_lossy().to_string();
+        loggers.insert(session_id.to_string(), logger);
+        Ok(path)
+    }
+
+    /// Stop logging a session (the file is flushed and closed)
+    pub fn stop_logging(&self, session_id: &str) -> Result<(), String> {
+        let mut logger = self
+            .loggers
+            .lock()
+            .remove(session_id)
+            .ok_or_else(|| "Session is not being logged".to_string())?;
+        logger.flush()
+    }
+
+    /// Path of the file a session is logged to
+    pub fn log_path(&self, session_id: &str) -> Option<String> {
+        self.loggers
+            .lock()
+            .get(session_id)
+            .map(|l| l.path().to_string_lossy().to_string())
+    }
+
+    /// Recent output of a session (the last `max_bytes`, or all that is kept)
+    pub fn scrollback(&self, session_id: &str, max_bytes: Option<usize>) -> Option<ScrollbackSnapshot> {
+        self.scrollbacks.lock().get(session_id).map(|s| s.snapshot(max_bytes))
+    }
+
+    /// Set the character encoding of a session's output and input.
+    /// Can be called before the session is registered.
+    pub fn set_encoding(&self, session_id: &str, encoding: SessionEncoding) {
+        self.decoders.lock().insert(session_id.to_string(), encoding.decoder());
+    }
+
+    pub fn encoding(&self, session_id: &str) -> Option<SessionEncoding> {
+        self.decoders.lock().get(session_id).map(|d| d.encoding())
+    }
+
+    /// Tell whether a session's tab is shown. While hidden, its output is
+    /// handled according to `mode`. When shown again, returns what it missed.
+    pub fn set_visibility(&self, session_id: &str, visible: bool, mode: HiddenOutput) -> Option<SkippedOutput> {
+        if !visible {
+            self.hidden
+                .lock()
+                .insert(session_id.to_string(), HiddenState { mode, skipped: 0 });
+            return None;
+        }
+
+        let state = self.hidden.lock().remove(session_id)?;
+        if state.skipped == 0 {
+            return None;
+        }
+        let summary = (state.mode == HiddenOutput::Summarize)
+            .then(|| {
+                let max = (state.skipped as usize).min(SUMMARY_BYTES);
+                self.scrollback(session_id, Some(max)).map(|s| s.data)
+            })
+            .flatten();
+        Some(SkippedOutput {
+            session_id: session_id.to_string(),
+            bytes: state.skipped,
+            summary,
+        })
+    }
+
+    /// Every registered session, oldest first. Tunnels are filled in by the
+    /// caller (they belong to the tunnel manager).
+    pub async fn list_sessions(&self) -> Vec<ActiveSession> {
+        let types: Vec<(String, &'static str)> = self
+            .sessions
+            .lock()
+            .iter()
+            .map(|(id, session)| (id.clone(), session.session_type()))
+            .collect();
+
+        let mut list = Vec::with_capacity(types.len());
+        for (session_id, session_type) in types {
+            let Some(stats) = self.stats.lock().get(&session_id).copied() else { continue };
+            let (host, port, username) = match (self.get_ssh_config(&session_id), self.log_target(&session_id)) {
+                (Some(config), _) => (Some(config.host), Some(config.port), Some(config.username)),
+                // Telnet and serial targets have no user name
+                (None, Some(target)) => {
+                    (Some(target.host), target.port, (session_type == "local").then_some(target.user))
+                }
+                (None, None) => (None, None, None),
+            };
+            // Before building the entry: no lock may be held across the await
+            let sftp = sftp_pool_info(&self.sftp_pool, &session_id).await;
+
+            list.push(ActiveSession {
+                session_type: session_type.to_string(),
+                host,
+                port,
+                username,
+                started_at: stats.started_at,
+                last_activity: stats.last_activity,
+                bytes_in: stats.bytes_in,
+                bytes_out: stats.bytes_out,
+                encoding: self.encoding(&session_id).unwrap_or_default().name().to_string(),
+                recording: self.recordings.lock().contains_key(&session_id),
+                log_path: self.log_path(&session_id),
+                broadcast_group: self.broadcast_state(&session_id).group_id,
+                tunnels: Vec::new(),
+                sftp,
+                session_id,
+            });
+        }
+        list.sort_by_key(|s| s.started_at);
+        list
+    }
+
+    /// Replace the output trigger rules
+    pub fn set_trigger_rules(&self, rules: &[TriggerRule]) -> Result<(), String> {
+        self.triggers.lock().set_rules(rules)
+    }
+
+    /// Set what runs the actions of triggers that fire
+    pub fn on_trigger(&self, handler: impl Fn(TriggerFire) + Send + Sync + 'static) {
+        *self.trigger_handler.lock() = Some(Arc::new(handler));
+    }
+
+    /// Receive a copy of the session's decoded output from now on (replacing
+    /// any previous receiver). Works before the session is registered; the
+    /// tap is removed once the receiver is dropped.
+    pub fn tap_output(&self, session_id: &str) -> tokio::sync::mpsc::UnboundedReceiver<Vec<u8>> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.taps.lock().insert(session_id.to_string(), tx);
+        rx
+    }
+
+    /// Create a broadcast group. Also returns the sessions whose group changed.
+    pub fn create_group(&self, name: String, members: Vec<String>) -> (GroupInfo, Vec<String>) {
+        self.groups.lock().create(name, members)
+    }
+
+    /// Delete a broadcast group. Returns its former members.
+    pub fn delete_group(&self, group_id: &str) -> Result<Vec<String>, String> {
+        self.groups.lock().delete(group_id)
+    }
+
+    /// Add a session to a group (leaving its current one).
+    /// Returns the sessions whose group changed.
+    pub fn add_to_group(&self, group_id: &str, session_id: &str) -> Result<Vec<String>, String> {
+        self.groups.lock().add(group_id, session_id)
+    }
+
+    /// Take a session out of its group. Returns the group it left.
+    pub fn remove_from_group(&self, session_id: &str) -> Option<String> {
+        self.groups.lock().remove_session(session_id)
+    }
+
+    /// Temporarily exclude a member from (or include it again in) broadcasts
+    pub fn set_broadcast_excluded(&self, group_id: &str, session_id: &str, excluded: bool) -> Result<(), String> {
+        self.groups.lock().set_excluded(group_id, session_id, excluded)
+    }
+
+    pub fn list_groups(&self) -> Vec<GroupInfo> {
+        self.groups.lock().list()
+    }
+
+    pub fn broadcast_state(&self, session_id: &str) -> BroadcastState {
+        self.groups.lock().state_of(session_id)
+    }
+
+    /// Write the same input to every member of a group that isn't excluded
+    pub fn broadcast(&self, group_id: &str, data: &[u8]) -> Result<BroadcastResult, String> {
+        let targets = self.groups.lock().targets(group_id)?;
+        let mut result = BroadcastResult::default();
+        for session_id in targets {
+            match self.write(&session_id, data) {
+                Ok(()) => result.sent.push(session_id),
+                Err(error) => result.failed.push(BroadcastFailure { session_id, error }),
+            }
+        }
+        Ok(result)
+    }
 }
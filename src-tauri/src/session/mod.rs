@@ -1,7 +1,17 @@
 //! Module de gestion des sessions
 
+pub mod automation;
+pub mod broadcast;
+pub mod encoding;
+pub mod flow;
+pub mod introspection;
+pub mod logging;
 mod manager;
+pub mod recording;
+pub mod scrollback;
+pub mod triggers;
 mod traits;
 
+pub use flow::OutputSender;
 pub use manager::{OutputMessage, SessionManager};
 pub use traits::Session;
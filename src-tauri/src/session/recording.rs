@@ -0,0 +1,382 @@
+//! Session recording and playback (asciicast v2)
+//!
+//! A recording is a JSON header line followed by one `[time, code, data]`
+//! line per event, where `code` is `o` (output), `i` (input) or `r` (resize,
+//! data = "COLSxROWS"). Files play in asciinema and in our own playback
+//! session, which replays the output into a read-only terminal.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::flow::OutputSender;
+use super::manager::OutputMessage;
+use super::traits::Session;
+
+/// Longest sleep between checks for cancellation and speed changes
+const PLAYBACK_TICK: Duration = Duration::from_millis(50);
+
+lazy_static::lazy_static! {
+    /// Speed (f64 bits) of running playbacks, keyed by session id
+    static ref PLAYBACK_SPEEDS: Mutex<HashMap<String, Arc<AtomicU64>>> = Mutex::new(HashMap::new());
+}
+
+/// Options for a new recording
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingOptions {
+    /// Also record what is typed (may capture passwords)
+    #[serde(default)]
+    pub record_input: bool,
+    pub title: Option<String>,
+}
+
+/// A recording in progress (or just finished)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RecordingInfo {
+    pub session_id: String,
+    pub path: String,
+    /// Unix timestamp (seconds)
+    pub started_at: i64,
+    pub record_input: bool,
+    /// Seconds since the start
+    pub duration: f64,
+}
+
+/// Header line of an asciicast v2 file
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+}
+
+/// Default location of a new recording: ~/.simplyterm/recordings/<label>-<date>.cast
+pub fn default_recording_path(label: &str) -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let dir = home.join(".simplyterm").join("recordings");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+
+    let label: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    Ok(dir.join(format!("{}-{}.cast", label, stamp)))
+}
+
+/// Decode the complete UTF-8 part of `pending` + `data`, keeping an
+/// incomplete trailing sequence for the next chunk. Invalid bytes become U+FFFD.
+fn take_utf8(pending: &mut Vec<u8>, data: &[u8]) -> String {
+    pending.extend_from_slice(data);
+    let mut text = String::new();
+    let mut rest: &[u8] = pending.as_slice();
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                rest = &[];
+                break;
+            }
+            Err(e) => {
+                let (valid, after) = rest.split_at(e.valid_up_to());
+                text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                match e.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        rest = &after[len..];
+                    }
+                    // Sequence cut at the end of the chunk
+                    None => {
+                        rest = after;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    *pending = rest.to_vec();
+    text
+}
+
+/// Writes the events of one session to a cast file
+pub(crate) struct Recorder {
+    session_id: String,
+    path: PathBuf,
+    writer: BufWriter<File>,
+    start: Instant,
+    started_at: i64,
+    record_input: bool,
+    pending_output: Vec<u8>,
+    pending_input: Vec<u8>,
+    dirty: bool,
+}
+
+impl Recorder {
+    pub(crate) fn create(
+        session_id: &str,
+        path: &Path,
+        cols: u32,
+        rows: u32,
+        options: &RecordingOptions,
+    ) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create recording: {}", e))?;
+        let started_at = chrono::Utc::now().timestamp();
+        let header = CastHeader {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: Some(started_at),
+            title: options.title.clone(),
+            env: Some(HashMap::from([("TERM".to_string(), "xterm-256color".to_string())])),
+        };
+
+        let mut recorder = Recorder {
+            session_id: session_id.to_string(),
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            start: Instant::now(),
+            started_at,
+            record_input: options.record_input,
+            pending_output: Vec::new(),
+            pending_input: Vec::new(),
+            dirty: false,
+        };
+        let line = serde_json::to_string(&header).map_err(|e| format!("Failed to write recording: {}", e))?;
+        recorder.write_line(&line)?;
+        recorder.flush()?;
+        Ok(recorder)
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        self.dirty = true;
+        writeln!(self.writer, "{}", line).map_err(|e| format!("Failed to write recording: {}", e))
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> Result<(), String> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        // Microsecond precision, like asciinema
+        let time = (self.start.elapsed().as_secs_f64() * 1e6).round() / 1e6;
+        let line = serde_json::to_string(&(time, code, data))
+            .map_err(|e| format!("Failed to write recording: {}", e))?;
+        self.write_line(&line)
+    }
+
+    pub(crate) fn output(&mut self, data: &[u8]) -> Result<(), String> {
+        let text = take_utf8(&mut self.pending_output, data);
+        self.event("o", &text)
+    }
+
+    pub(crate) fn input(&mut self, data: &[u8]) -> Result<(), String> {
+        if !self.record_input {
+            return Ok(());
+        }
+        let text = take_utf8(&mut self.pending_input, data);
+        self.event("i", &text)
+    }
+
+    pub(crate) fn resize(&mut self, cols: u32, rows: u32) -> Result<(), String> {
+        self.event("r", &format!("{}x{}", cols, rows))
+    }
+
+    /// Write buffered events to disk (called when output goes idle)
+    pub(crate) fn flush(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        self.writer.flush().map_err(|e| format!("Failed to write recording: {}", e))
+    }
+
+    pub(crate) fn info(&self) -> RecordingInfo {
+        RecordingInfo {
+            session_id: self.session_id.clone(),
+            path: self.path.to_string_lossy().to_string(),
+            started_at: self.started_at,
+            record_input: self.record_input,
+            duration: self.start.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// Flush and close the file
+    pub(crate) fn finish(mut self) -> Result<RecordingInfo, String> {
+        let info = self.info();
+        self.flush()?;
+        Ok(info)
+    }
+}
+
+// ============================================================================
+// Playback
+// ============================================================================
+
+/// Read-only session replaying a recording
+#[derive(Debug)]
+pub struct PlaybackSession {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Session for PlaybackSession {
+    fn write(&self, _data: &[u8]) -> Result<(), String> {
+        Err("Recording playback is read-only".to_string())
+    }
+
+    fn resize(&self, _cols: u32, _rows: u32) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn session_type(&self) -> &'static str {
+        "playback"
+    }
+
+    fn close(&self) -> Result<(), String> {
+        self.cancelled.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Parse one `[time, code, data]` event line
+fn parse_event(line: &str) -> Option<(f64, String, String)> {
+    serde_json::from_str(line).ok()
+}
+
+/// Open a recording and replay its output into `session_id` at `speed`
+/// (2.0 = twice as fast). Pauses are capped to `max_idle` seconds when set.
+/// `on_resize` receives the recorded resize events and `on_exit` runs when the
+/// playback ends or is closed.
+pub fn start_playback(
+    path: &Path,
+    session_id: String,
+    output_tx: OutputSender,
+    speed: f64,
+    max_idle: Option<f64>,
+    on_resize: impl Fn(u32, u32) + Send + 'static,
+    on_exit: impl FnOnce() + Send + 'static,
+) -> Result<(PlaybackSession, CastHeader), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open recording: {}", e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header_line = lines
+        .next()
+        .ok_or_else(|| "Recording is empty".to_string())?
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    let header: CastHeader = serde_json::from_str(&header_line)
+        .map_err(|_| "Not an asciicast recording".to_string())?;
+    if header.version != 2 {
+        return Err(format!("Unsupported asciicast version: {}", header.version));
+    }
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let speed_bits = Arc::new(AtomicU64::new(speed.max(0.01).to_bits()));
+    PLAYBACK_SPEEDS.lock().insert(session_id.clone(), speed_bits.clone());
+
+    let cancel = cancelled.clone();
+    thread::spawn(move || {
+        let mut previous = 0.0;
+        for line in lines {
+            let Ok(line) = line else { break };
+            let Some((time, code, data)) = parse_event(&line) else { continue };
+
+            let mut delay = (time - previous).max(0.0);
+            if let Some(max) = max_idle {
+                delay = delay.min(max);
+            }
+            previous = time;
+
+            // Sleep in ticks so closing and speed changes take effect quickly
+            while delay > 0.0 {
+                if cancel.load(Ordering::SeqCst) {
+                    break;
+                }
+                let speed = f64::from_bits(speed_bits.load(Ordering::Relaxed));
+                let wall = Duration::from_secs_f64(delay / speed).min(PLAYBACK_TICK);
+                thread::sleep(wall);
+                delay -= wall.as_secs_f64() * speed;
+            }
+            if cancel.load(Ordering::SeqCst) {
+                break;
+            }
+
+            match code.as_str() {
+                "o" => {
+                    output_tx.wait_ready(&session_id);
+                    let _ = output_tx.send(OutputMessage {
+                        session_id: session_id.clone(),
+                        data: data.into_bytes(),
+                    });
+                }
+                "r" => {
+                    if let Some((cols, rows)) = data.split_once('x') {
+                        if let (Ok(cols), Ok(rows)) = (cols.parse(), rows.parse()) {
+                            on_resize(cols, rows);
+                        }
+                    }
+                }
+                // Input is already echoed in the output
+                _ => {}
+            }
+        }
+        PLAYBACK_SPEEDS.lock().remove(&session_id);
+        on_exit();
+    });
+
+    Ok((PlaybackSession { cancelled }, header))
+}
+
+/// Change the speed of a running playback
+pub fn set_playback_speed(session_id: &str, speed: f64) -> Result<(), String> {
+    if speed.is_nan() || speed <= 0.0 {
+        return Err("Playback speed must be positive".to_string());
+    }
+    let speeds = PLAYBACK_SPEEDS.lock();
+    let bits = speeds
+        .get(session_id)
+        .ok_or_else(|| "Playback not found".to_string())?;
+    bits.store(speed.to_bits(), Ordering::Relaxed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_sequences_split_across_chunks() {
+        let mut pending = Vec::new();
+        let bytes = "é€".as_bytes();
+        assert_eq!(take_utf8(&mut pending, &bytes[..1]), "");
+        assert_eq!(take_utf8(&mut pending, &bytes[1..3]), "é");
+        assert_eq!(take_utf8(&mut pending, &bytes[3..]), "€");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_bytes_are_replaced() {
+        let mut pending = Vec::new();
+        assert_eq!(take_utf8(&mut pending, b"a\xffb"), "a\u{FFFD}b");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_parses_events() {
+        assert_eq!(
+            parse_event(r#"[1.5, "o", "hi\r\n"]"#),
+            Some((1.5, "o".to_string(), "hi\r\n".to_string()))
+        );
+        assert_eq!(parse_event("not json"), None);
+    }
+}
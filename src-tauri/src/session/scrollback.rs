@@ -0,0 +1,115 @@
+//! Backend copy of recent session output
+//!
+//! The frontend terminal loses its content when the webview reloads. The
+//! batch worker also appends everything it emits to a bounded per-session
+//! buffer, so a view can be rebuilt when it reattaches (and plugins with
+//! `terminal_read` can read recent output).
+
+use std::collections::VecDeque;
+
+/// Output kept per session
+pub const SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+/// Recent output of a session, returned to reattaching views
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScrollbackSnapshot {
+    pub data: String,
+    /// Bytes of output the session produced so far
+    pub total_bytes: u64,
+    /// Older output was dropped
+    pub truncated: bool,
+}
+
+/// Ring buffer of the last `capacity` bytes of output
+pub(crate) struct Scrollback {
+    data: VecDeque<u8>,
+    capacity: usize,
+    total: u64,
+}
+
+impl Scrollback {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Scrollback {
+            data: VecDeque::new(),
+            capacity,
+            total: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        self.total += bytes.len() as u64;
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.capacity);
+        self.data.drain(..overflow);
+        self.data.extend(bytes);
+    }
+
+    /// The last `max_bytes` (default: everything kept). When older output is
+    /// cut off, the snapshot starts at the next line so it doesn't begin in
+    /// the middle of a character or escape sequence.
+    pub(crate) fn snapshot(&self, max_bytes: Option<usize>) -> ScrollbackSnapshot {
+        let len = self.data.len();
+        let take = max_bytes.map_or(len, |max| max.min(len));
+        let mut start = len - take;
+        let truncated = self.total > take as u64;
+
+        if truncated {
+            let newline = self.data.range(start..).position(|&b| b == b'\n');
+            match newline {
+                Some(i) => start += i + 1,
+                // No line break: at least skip a partial UTF-8 character
+                None => {
+                    start += self.data.range(start..).take_while(|&&b| b & 0xc0 == 0x80).count();
+                }
+            }
+        }
+
+        let bytes: Vec<u8> = self.data.range(start..).copied().collect();
+        ScrollbackSnapshot {
+            data: String::from_utf8_lossy(&bytes).to_string(),
+            total_bytes: self.total,
+            truncated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_only_the_last_bytes() {
+        let mut scrollback = Scrollback::new(8);
+        scrollback.push(b"abc\n");
+        scrollback.push(b"defgh\n");
+        let snapshot = scrollback.snapshot(None);
+        // "c\ndefgh\n" kept, cut at the first line break
+        assert_eq!(snapshot.data, "defgh\n");
+        assert_eq!(snapshot.total_bytes, 10);
+        assert!(snapshot.truncated);
+    }
+
+    #[test]
+    fn test_full_history_is_not_cut() {
+        let mut scrollback = Scrollback::new(64);
+        scrollback.push(b"$ ls\r\nfile\r\n$ ");
+        let snapshot = scrollback.snapshot(None);
+        assert_eq!(snapshot.data, "$ ls\r\nfile\r\n$ ");
+        assert!(!snapshot.truncated);
+    }
+
+    #[test]
+    fn test_skips_partial_characters_without_line_breaks() {
+        let mut scrollback = Scrollback::new(64);
+        scrollback.push("aé€".as_bytes());
+        // Last 4 bytes start inside "é"
+        assert_eq!(scrollback.snapshot(Some(4)).data, "€");
+    }
+
+    #[test]
+    fn test_oversized_chunks_keep_their_tail() {
+        let mut scrollback = Scrollback::new(4);
+        scrollback.push(b"0123456789");
+        assert_eq!(scrollback.data.iter().copied().collect::<Vec<u8>>(), b"6789");
+    }
+}
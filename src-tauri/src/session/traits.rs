@@ -10,8 +10,7 @@ pub trait Session: Send + Sync + Debug {
     /// Redimensionne le terminal
     fn resize(&self, cols: u32, rows: u32) -> Result<(), String>;
 
-    /// Retourne le type de session pour le logging/debug
-    #[allow(dead_code)]
+    /// Retourne le type de session (logging/debug, introspection)
     fn session_type(&self) -> &'static str;
 
     /// Closes the session
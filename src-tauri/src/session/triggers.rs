@@ -0,0 +1,284 @@
+//! Output triggers
+//!
+//! Rules (see `storage::triggers`) are matched against each line of a
+//! session's output once ANSI sequences are removed. Rules with
+//! `match_prompt` also see the line before it ends, so a prompt such as
+//! "Password: " can be answered; a rule fires at most once per line. Each
+//! rule has a cooldown per session, and firings are capped globally so a
+//! flood of matching output doesn't turn into a storm of actions.
+
+use regex::{Captures, Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::logging::AnsiStripper;
+use crate::storage::{TriggerAction, TriggerRule};
+
+/// Firings allowed per second, all rules and sessions together
+const MAX_FIRES_PER_SECOND: usize = 10;
+/// Longest unterminated line kept for matching
+const MAX_LINE_LEN: usize = 4096;
+
+/// A rule that matched, with capture references in its actions expanded
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TriggerFire {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub session_id: String,
+    /// The line that matched
+    pub line: String,
+    /// The part of it matched by the pattern
+    pub matched: String,
+    pub actions: Vec<TriggerAction>,
+}
+
+struct CompiledRule {
+    rule: TriggerRule,
+    regex: Regex,
+}
+
+impl CompiledRule {
+    fn new(rule: &TriggerRule) -> Result<Self, String> {
+        let regex = RegexBuilder::new(&rule.pattern)
+            .case_insensitive(rule.case_insensitive)
+            .build()
+            .map_err(|e| format!("Invalid pattern in trigger \"{}\": {}", rule.name, e))?;
+        Ok(CompiledRule { rule: rule.clone(), regex })
+    }
+
+    fn applies(&self, session_id: &str, host: Option<&str>) -> bool {
+        self.rule.session_id.as_deref().map_or(true, |id| id == session_id)
+            && self
+                .rule
+                .host
+                .as_deref()
+                .map_or(true, |h| host.map_or(false, |host| host.eq_ignore_ascii_case(h)))
+    }
+
+    fn fire(&self, session_id: &str, line: &str, captures: &Captures) -> TriggerFire {
+        let expand = |template: &str| {
+            let mut text = String::new();
+            captures.expand(template, &mut text);
+            text
+        };
+        let actions = self
+            .rule
+            .actions
+            .iter()
+            .map(|action| match action {
+                TriggerAction::Notify { title, body } => TriggerAction::Notify {
+                    title: Some(title.as_deref().map_or_else(|| self.rule.name.clone(), expand)),
+                    body: Some(body.as_deref().map_or_else(|| line.trim().to_string(), expand)),
+                },
+                TriggerAction::SendText { text } => TriggerAction::SendText { text: expand(text) },
+                other => other.clone(),
+            })
+            .collect();
+
+        TriggerFire {
+            rule_id: self.rule.id.clone(),
+            rule_name: self.rule.name.clone(),
+            session_id: session_id.to_string(),
+            line: line.to_string(),
+            matched: captures.get(0).map_or("", |m| m.as_str()).to_string(),
+            actions,
+        }
+    }
+}
+
+/// Check the patterns of rules before they are saved
+pub fn validate_rules(rules: &[TriggerRule]) -> Result<(), String> {
+    rules.iter().try_for_each(|rule| CompiledRule::new(rule).map(|_| ()))
+}
+
+/// Line being matched for one session
+struct LineState {
+    host: Option<String>,
+    stripper: AnsiStripper,
+    line: String,
+    /// Rules (by index) that already matched the current line
+    fired: HashSet<usize>,
+    last_fired: HashMap<usize, Instant>,
+}
+
+#[derive(Default)]
+pub(crate) struct TriggerEngine {
+    rules: Vec<CompiledRule>,
+    sessions: HashMap<String, LineState>,
+    /// Recent firings, for the global cap
+    recent: VecDeque<Instant>,
+}
+
+impl TriggerEngine {
+    /// Replace the rules (disabled ones are left out)
+    pub(crate) fn set_rules(&mut self, rules: &[TriggerRule]) -> Result<(), String> {
+        self.rules = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .map(CompiledRule::new)
+            .collect::<Result<_, _>>()?;
+        // Rule indices changed
+        for state in self.sessions.values_mut() {
+            state.fired.clear();
+            state.last_fired.clear();
+        }
+        Ok(())
+    }
+
+    /// Match a chunk of a session's output. `host` is asked for the first
+    /// time the session is seen.
+    pub(crate) fn scan(
+        &mut self,
+        session_id: &str,
+        data: &[u8],
+        host: impl FnOnce() -> Option<String>,
+        now: Instant,
+    ) -> Vec<TriggerFire> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        let TriggerEngine { rules, sessions, recent } = self;
+        let state = sessions.entry(session_id.to_string()).or_insert_with(|| LineState {
+            host: host(),
+            stripper: AnsiStripper::new(),
+            line: String::new(),
+            fired: HashSet::new(),
+            last_fired: HashMap::new(),
+        });
+
+        let mut stripped = Vec::with_capacity(data.len());
+        state.stripper.strip(data, &mut stripped);
+        let text = String::from_utf8_lossy(&stripped);
+
+        let mut fires = Vec::new();
+        let mut pieces = text.split('\n').peekable();
+        while let Some(piece) = pieces.next() {
+            state.line.push_str(piece);
+            let complete = pieces.peek().is_some();
+
+            for (index, rule) in rules.iter().enumerate() {
+                if !(complete || (rule.rule.match_prompt && !state.line.is_empty()))
+                    || state.fired.contains(&index)
+                    || !rule.applies(session_id, state.host.as_deref())
+                {
+                    continue;
+                }
+                let Some(captures) = rule.regex.captures(&state.line) else { continue };
+                state.fired.insert(index);
+
+                let cooldown = Duration::from_millis(rule.rule.cooldown_ms);
+                if state.last_fired.get(&index).map_or(false, |last| now.duration_since(*last) < cooldown) {
+                    continue;
+                }
+                while recent.front().map_or(false, |t| now.duration_since(*t) >= Duration::from_secs(1)) {
+                    recent.pop_front();
+                }
+                if recent.len() >= MAX_FIRES_PER_SECOND {
+                    eprintln!("[Triggers] Rate limit reached, \"{}\" not fired", rule.rule.name);
+                    continue;
+                }
+                recent.push_back(now);
+                state.last_fired.insert(index, now);
+                fires.push(rule.fire(session_id, &state.line, &captures));
+            }
+
+            if complete {
+                state.line.clear();
+                state.fired.clear();
+            } else if state.line.len() > MAX_LINE_LEN {
+                let mut cut = state.line.len() - MAX_LINE_LEN;
+                while !state.line.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                state.line.drain(..cut);
+            }
+        }
+        fires
+    }
+
+    pub(crate) fn remove_session(&mut self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, actions: Vec<TriggerAction>) -> TriggerRule {
+        TriggerRule {
+            id: pattern.to_string(),
+            name: "test".to_string(),
+            pattern: pattern.to_string(),
+            case_insensitive: false,
+            enabled: true,
+            match_prompt: false,
+            host: None,
+            session_id: None,
+            actions,
+            cooldown_ms: 0,
+        }
+    }
+
+    fn engine(rules: &[TriggerRule]) -> TriggerEngine {
+        let mut engine = TriggerEngine::default();
+        engine.set_rules(rules).unwrap();
+        engine
+    }
+
+    #[test]
+    fn test_matches_complete_lines_without_escape_sequences() {
+        let mut engine = engine(&[rule(r"BUILD (\w+)", vec![TriggerAction::Notify { title: None, body: Some("build $1".to_string()) }])]);
+        let now = Instant::now();
+        assert!(engine.scan("s", b"\x1b[31mBUILD FAI", || None, now).is_empty());
+
+        let fires = engine.scan("s", b"LED\x1b[0m\r\n", || None, now);
+        assert_eq!(fires.len(), 1);
+        assert_eq!(fires[0].line, "BUILD FAILED");
+        match &fires[0].actions[0] {
+            TriggerAction::Notify { title, body } => {
+                assert_eq!(title.as_deref(), Some("test"));
+                assert_eq!(body.as_deref(), Some("build FAILED"));
+            }
+            _ => panic!("unexpected action"),
+        }
+    }
+
+    #[test]
+    fn test_prompts_fire_once_before_the_line_ends() {
+        let mut prompt = rule(r"[Pp]assword: $", vec![TriggerAction::SendText { text: "secret\n".to_string() }]);
+        prompt.match_prompt = true;
+        let mut engine = engine(&[prompt]);
+        let now = Instant::now();
+
+        assert_eq!(engine.scan("s", b"Password: ", || None, now).len(), 1);
+        // Same line, more output: no second firing
+        assert!(engine.scan("s", b"\r\n", || None, now).is_empty());
+        assert_eq!(engine.scan("s", b"password: ", || None, now).len(), 1);
+    }
+
+    #[test]
+    fn test_cooldown_and_global_cap() {
+        let mut slow = rule("ERROR", Vec::new());
+        slow.cooldown_ms = 1000;
+        let mut engine = engine(&[slow]);
+        let now = Instant::now();
+        assert_eq!(engine.scan("a", b"ERROR\nERROR\n", || None, now).len(), 1);
+        assert_eq!(engine.scan("a", b"ERROR\n", || None, now + Duration::from_secs(1)).len(), 1);
+
+        let mut engine = self::engine(&[rule("x", Vec::new())]);
+        let flood = b"x\n".repeat(50);
+        assert_eq!(engine.scan("a", &flood, || None, now).len(), MAX_FIRES_PER_SECOND);
+    }
+
+    #[test]
+    fn test_rules_are_scoped_by_host() {
+        let mut scoped = rule("ready", Vec::new());
+        scoped.host = Some("DB1".to_string());
+        let mut engine = engine(&[scoped]);
+        let now = Instant::now();
+        assert!(engine.scan("a", b"ready\n", || Some("web1".to_string()), now).is_empty());
+        assert_eq!(engine.scan("b", b"ready\n", || Some("db1".to_string()), now).len(), 1);
+        assert!(validate_rules(&[rule("(", Vec::new())]).is_err());
+    }
+}
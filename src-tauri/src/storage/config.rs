@@ -27,6 +27,12 @@ pub struct SavedSession {
     pub ssh_key_id: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub folder_id: Option<String>,
+    /// Terminal character encoding (UTF-8 when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Automation script run once connected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_connect_script: Option<String>,
 }
 
 fn get_config_path() -> Result<PathBuf, String> {
@@ -87,6 +93,8 @@ mod tests {
             key_path: None,
             ssh_key_id: None,
             folder_id: None,
+            encoding: None,
+            on_connect_script: None,
         };
 
         let json = serde_json::to_string(&session).unwrap();
@@ -0,0 +1,162 @@
+//! External edit records (~/.simplyterm/edits.json)
+//!
+//! Files opened in an external editor are recorded so watching can resume
+//! after a restart, once a session to the same host reconnects.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// A remote file being edited through a local copy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedEdit {
+    /// Target host, so a reconnected session can pick the edit up again
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub remote_path: String,
+    pub local_path: String,
+    /// Remote state the local copy was last synced with
+    pub remote_size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_modified: Option<u64>,
+    pub remote_hash: String,
+    /// Unix timestamp (seconds) when editing started
+    pub started_at: i64,
+    /// Read and written through sudo
+    #[serde(default)]
+    pub elevated: bool,
+}
+
+impl PersistedEdit {
+    pub fn same_target(&self, host: &str, port: u16, username: &str) -> bool {
+        self.host == host && self.port == port && self.username == username
+    }
+}
+
+fn get_edits_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let config_dir = home.join(".simplyterm");
+
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    Ok(config_dir.join("edits.json"))
+}
+
+/// Loads all recorded edits
+pub fn load_persisted_edits() -> Result<Vec<PersistedEdit>, String> {
+    let path = get_edits_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read edits file: {}", e))?;
+
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse edits file: {}", e))
+}
+
+fn save_persisted_edits(edits: &[PersistedEdit]) -> Result<(), String> {
+    let path = get_edits_path()?;
+
+    let content = serde_json::to_string_pretty(edits)
+        .map_err(|e| format!("Failed to serialize edits: {}", e))?;
+
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write edits file: {}", e))
+}
+
+/// Record an edit, replacing any older record for the same local copy
+pub fn record_edit(edit: PersistedEdit) -> Result<(), String> {
+    let mut edits = load_persisted_edits()?;
+    edits.retain(|e| e.local_path != edit.local_path);
+    edits.push(edit);
+    save_persisted_edits(&edits)
+}
+
+/// Remove the record of a local copy. Returns it if it existed.
+pub fn remove_edit(local_path: &str) -> Result<Option<PersistedEdit>, String> {
+    let mut edits = load_persisted_edits()?;
+    let Some(index) = edits.iter().position(|e| e.local_path == local_path) else {
+        return Ok(None);
+    };
+    let removed = edits.remove(index);
+    save_persisted_edits(&edits)?;
+    Ok(Some(removed))
+}
+
+/// Remove the records for a host/user, except those `keep` accepts.
+/// Returns the removed records.
+pub fn remove_edits_for_target(
+    host: &str,
+    port: u16,
+    username: &str,
+    keep: impl Fn(&PersistedEdit) -> bool,
+) -> Result<Vec<PersistedEdit>, String> {
+    let (removed, kept) = split_for_target(load_persisted_edits()?, host, port, username, keep);
+    if !removed.is_empty() {
+        save_persisted_edits(&kept)?;
+    }
+    Ok(removed)
+}
+
+/// Split records into those of a host/user that `keep` rejects, and the rest
+fn split_for_target(
+    edits: Vec<PersistedEdit>,
+    host: &str,
+    port: u16,
+    username: &str,
+    keep: impl Fn(&PersistedEdit) -> bool,
+) -> (Vec<PersistedEdit>, Vec<PersistedEdit>) {
+    edits
+        .into_iter()
+        .partition(|e| e.same_target(host, port, username) && !keep(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(host: &str, username: &str, local_path: &str) -> PersistedEdit {
+        PersistedEdit {
+            host: host.to_string(),
+            port: 22,
+            username: username.to_string(),
+            remote_path: "/etc/app.conf".to_string(),
+            local_path: local_path.to_string(),
+            remote_size: 0,
+            remote_modified: None,
+            remote_hash: String::new(),
+            started_at: 0,
+            elevated: false,
+        }
+    }
+
+    #[test]
+    fn test_split_for_target_honors_keep() {
+        let edits = vec![
+            edit("srv", "alice", "/tmp/a"),
+            edit("srv", "alice", "/tmp/unsynced"),
+            edit("srv", "bob", "/tmp/b"),
+            edit("other", "alice", "/tmp/c"),
+        ];
+        let (removed, kept) = split_for_target(edits, "srv", 22, "alice", |e| e.local_path == "/tmp/unsynced");
+
+        let paths = |edits: &[PersistedEdit]| edits.iter().map(|e| e.local_path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&removed), vec!["/tmp/a"]);
+        assert_eq!(paths(&kept), vec!["/tmp/unsynced", "/tmp/b", "/tmp/c"]);
+
+        // Another port is another target
+        let (removed, _) = split_for_target(vec![edit("srv", "alice", "/tmp/a")], "srv", 2222, "alice", |_| false);
+        assert!(removed.is_empty());
+    }
+}
@@ -4,16 +4,28 @@
 //! - sessions.rs: CRUD operations for individual sessions
 //! - vault/: Encrypted vault for secure credential storage
 //! - settings.rs: Application settings management
+//! - transfers.rs: Interrupted transfer records (for resume)
+//! - edits.rs: External edit records (restored after a restart)
+//! - triggers.rs: Output trigger rules
+//! - scripts.rs: Automation scripts (send/expect)
 //!
 //! Note: Folders, tags, and recent sessions are now managed by plugins
 //! via the session metadata API.
 
 pub mod config;
+pub mod edits;
+pub mod scripts;
 pub mod sessions;
 pub mod settings;
+pub mod transfers;
+pub mod triggers;
 pub mod vault;
 
 // Core exports
 pub use config::{load_sessions, save_sessions, SavedSession, AuthType};
-pub use settings::{load_settings, save_settings, AppSettings};
+pub use settings::{load_settings, save_settings, AppSettings, LoggingSettings, TransferSettings};
+pub use transfers::{InterruptedTransfer, TransferDirection};
+pub use edits::PersistedEdit;
+pub use scripts::{load_scripts, save_scripts, AutomationScript, ScriptStep};
+pub use triggers::{load_triggers, save_triggers, TriggerAction, TriggerRule};
 pub use vault::{VaultState, VaultCredentialType};
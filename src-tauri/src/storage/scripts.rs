@@ -0,0 +1,103 @@
+//! Automation scripts (~/.simplyterm/scripts.json)
+//!
+//! A script is a list of send/expect steps run against a session, e.g. to log
+//! into network gear (enable, password, terminal length 0). Text sent may
+//! reference vault secrets as `${vault:<key>}` (password) or
+//! `${vault:<key>:passphrase}` (key passphrase); they are resolved when sent
+//! and never stored in the script.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// One of the patterns an expect step waits for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectCase {
+    /// Regex matched against the output (ANSI sequences removed)
+    pub pattern: String,
+    /// Label to continue at when it matches (the next step when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goto: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScriptStep {
+    /// Write text to the session as is (include "\r" to press Enter)
+    Send { text: String },
+    /// Wait for the first of several patterns to appear in the output
+    #[serde(rename_all = "camelCase")]
+    Expect {
+        cases: Vec<ExpectCase>,
+        /// The script's default timeout when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u64>,
+        /// Label to continue at on timeout (the script fails when unset)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        on_timeout: Option<String>,
+    },
+    /// Target of `goto`s
+    Label { name: String },
+    Goto { label: String },
+    Sleep { ms: u64 },
+    /// Stop the script with an error
+    Fail { message: String },
+}
+
+fn default_timeout() -> u64 {
+    10_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationScript {
+    pub id: String,
+    pub name: String,
+    pub steps: Vec<ScriptStep>,
+    /// Timeout of expect steps that don't set one
+    #[serde(default = "default_timeout")]
+    pub default_timeout_ms: u64,
+}
+
+fn get_scripts_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let config_dir = home.join(".simplyterm");
+
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    Ok(config_dir.join("scripts.json"))
+}
+
+/// Loads all automation scripts
+pub fn load_scripts() -> Result<Vec<AutomationScript>, String> {
+    let path = get_scripts_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read scripts file: {}", e))?;
+
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse scripts file: {}", e))
+}
+
+/// Replaces all automation scripts
+pub fn save_scripts(scripts: &[AutomationScript]) -> Result<(), String> {
+    let path = get_scripts_path()?;
+
+    let content = serde_json::to_string_pretty(scripts)
+        .map_err(|e| format!("Failed to serialize scripts: {}", e))?;
+
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write scripts file: {}", e))
+}
@@ -43,6 +43,8 @@ pub fn save_session(
         key_path,
         ssh_key_id: None,
         folder_id: None,
+        encoding: None,
+        on_connect_script: None,
     };
 
     sessions.push(session.clone());
@@ -3,6 +3,7 @@
 //! Saves and loads user settings to ~/.simplyterm/settings.json
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -89,6 +90,67 @@ impl Default for DeveloperSettings {
     }
 }
 
+/// File transfer queue settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferSettings {
+    /// Maximum number of transfers running at once (all sessions)
+    pub max_concurrent: usize,
+    /// Maximum number of transfers running at once on a single session
+    pub max_per_session: usize,
+    /// Automatic retries before a transfer is marked as failed
+    pub max_retries: u32,
+}
+
+impl Default for TransferSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            max_per_session: 2,
+            max_retries: 3,
+        }
+    }
+}
+
+/// Session output logging
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggingSettings {
+    /// Log every session (unless overridden in `sessions`)
+    pub enabled: bool,
+    /// Log directory (defaults to ~/.simplyterm/logs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// File name with `{host}`, `{user}`, `{port}`, `{session}`, `{date}` and `{time}` placeholders
+    pub filename_template: String,
+    /// Prefix each line with the time it was received
+    pub timestamps: bool,
+    /// Remove ANSI escape sequences (colors, cursor movement, titles)
+    pub strip_ansi: bool,
+    /// Rotate once a file reaches this size in MB (0 = never)
+    pub max_file_size_mb: u64,
+    /// Rotated files kept next to the current one
+    pub max_files: u32,
+    /// Per saved session override of `enabled`, keyed by saved session id
+    #[serde(default)]
+    pub sessions: HashMap<String, bool>,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            filename_template: "{user}@{host}_{date}_{time}.log".to_string(),
+            timestamps: true,
+            strip_ansi: true,
+            max_file_size_mb: 10,
+            max_files: 5,
+            sessions: HashMap::new(),
+        }
+    }
+}
+
 /// Full app settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
@@ -103,6 +165,10 @@ pub struct AppSettings {
     pub plugin_registries: Option<Vec<RegistrySourceConfig>>,
     #[serde(default)]
     pub developer: DeveloperSettings,
+    #[serde(default)]
+    pub transfers: TransferSettings,
+    #[serde(default)]
+    pub logging: LoggingSettings,
 }
 
 impl Default for AppSettings {
@@ -124,6 +190,8 @@ impl Default for AppSettings {
             security: SecuritySettings::default(),
             plugin_registries: None,
             developer: DeveloperSettings::default(),
+            transfers: TransferSettings::default(),
+            logging: LoggingSettings::default(),
         }
     }
 }
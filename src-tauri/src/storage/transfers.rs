@@ -0,0 +1,145 @@
+//! Interrupted transfer records (~/.simplyterm/transfers.json)
+//!
+//! When an upload or download fails midway, a record is kept so the transfer
+//! can be resumed later — possibly from a new session after reconnecting.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+lazy_static::lazy_static! {
+    /// Serializes read-modify-write cycles of transfers.json: transfers
+    /// finish or fail concurrently and would otherwise drop each other's records
+    static ref TRANSFERS_FILE: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// A transfer that stopped before completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterruptedTransfer {
+    pub id: String,
+    pub direction: TransferDirection,
+    /// Session the transfer was running on (may no longer exist)
+    pub session_id: String,
+    /// Target host, so the UI can match a reconnected session
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub local_path: String,
+    pub remote_path: String,
+    pub total_bytes: u64,
+    pub transferred_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix timestamp (seconds) of the interruption
+    pub interrupted_at: i64,
+}
+
+impl InterruptedTransfer {
+    fn same_target(&self, other: &InterruptedTransfer) -> bool {
+        self.direction == other.direction
+            && self.host == other.host
+            && self.port == other.port
+            && self.local_path == other.local_path
+            && self.remote_path == other.remote_path
+    }
+}
+
+fn get_transfers_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let config_dir = home.join(".simplyterm");
+
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    Ok(config_dir.join("transfers.json"))
+}
+
+/// Loads all interrupted transfers
+pub fn load_interrupted_transfers() -> Result<Vec<InterruptedTransfer>, String> {
+    let path = get_transfers_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read transfers file: {}", e))?;
+
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse transfers file: {}", e))
+}
+
+fn save_interrupted_transfers(transfers: &[InterruptedTransfer]) -> Result<(), String> {
+    let path = get_transfers_path()?;
+
+    let content = serde_json::to_string_pretty(transfers)
+        .map_err(|e| format!("Failed to serialize transfers: {}", e))?;
+
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write transfers file: {}", e))
+}
+
+/// Record an interrupted transfer, replacing any older record for the same file pair
+pub fn record_interrupted_transfer(transfer: InterruptedTransfer) -> Result<(), String> {
+    let _guard = TRANSFERS_FILE.lock();
+    let mut transfers = load_interrupted_transfers()?;
+    transfers.retain(|t| !t.same_target(&transfer));
+    transfers.push(transfer);
+    save_interrupted_transfers(&transfers)
+}
+
+/// Get a single record by ID
+pub fn get_interrupted_transfer(id: &str) -> Result<Option<InterruptedTransfer>, String> {
+    Ok(load_interrupted_transfers()?.into_iter().find(|t| t.id == id))
+}
+
+/// Remove a record by ID. Returns true if it existed.
+pub fn remove_interrupted_transfer(id: &str) -> Result<bool, String> {
+    let _guard = TRANSFERS_FILE.lock();
+    let mut transfers = load_interrupted_transfers()?;
+    let before = transfers.len();
+    transfers.retain(|t| t.id != id);
+    if transfers.len() == before {
+        return Ok(false);
+    }
+    save_interrupted_transfers(&transfers)?;
+    Ok(true)
+}
+
+/// Remove records matching a file pair (called once a transfer completes)
+pub fn clear_interrupted_transfer(
+    direction: TransferDirection,
+    host: &str,
+    port: u16,
+    local_path: &str,
+    remote_path: &str,
+) -> Result<(), String> {
+    let _guard = TRANSFERS_FILE.lock();
+    let mut transfers = load_interrupted_transfers()?;
+    let before = transfers.len();
+    transfers.retain(|t| {
+        !(t.direction == direction
+            && t.host == host
+            && t.port == port
+            && t.local_path == local_path
+            && t.remote_path == remote_path)
+    });
+    if transfers.len() != before {
+        save_interrupted_transfers(&transfers)?;
+    }
+    Ok(())
+}
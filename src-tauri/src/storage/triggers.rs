@@ -0,0 +1,104 @@
+//! Output trigger rules (~/.simplyterm/triggers.json)
+//!
+//! A rule matches a regex against session output and runs actions when it
+//! does: notify, answer a prompt, start or stop logging, or tell plugins.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// What a trigger does when it fires. Text fields may reference the
+/// pattern's capture groups (`$1`, `${name}`; `$$` for a literal `$`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TriggerAction {
+    /// Desktop notification (defaults: the rule name and the matched line)
+    Notify {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body: Option<String>,
+    },
+    /// Write text to the session, e.g. an answer to a prompt
+    SendText { text: String },
+    StartLogging,
+    StopLogging,
+    /// Deliver `plugin:{event}` to plugins subscribed to events
+    PluginEvent { event: String },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_cooldown() -> u64 {
+    2000
+}
+
+/// A regex matched against each line of output (ANSI sequences removed)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerRule {
+    pub id: String,
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Also match the line before it ends, for prompts waiting for input
+    #[serde(default)]
+    pub match_prompt: bool,
+    /// Only sessions to this host (all sessions when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Only this open session (all sessions when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub actions: Vec<TriggerAction>,
+    /// Minimum time between two firings on the same session
+    #[serde(default = "default_cooldown")]
+    pub cooldown_ms: u64,
+}
+
+fn get_triggers_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let config_dir = home.join(".simplyterm");
+
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    Ok(config_dir.join("triggers.json"))
+}
+
+/// Loads all trigger rules
+pub fn load_triggers() -> Result<Vec<TriggerRule>, String> {
+    let path = get_triggers_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read triggers file: {}", e))?;
+
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse triggers file: {}", e))
+}
+
+/// Replaces all trigger rules
+pub fn save_triggers(rules: &[TriggerRule]) -> Result<(), String> {
+    let path = get_triggers_path()?;
+
+    let content = serde_json::to_string_pretty(rules)
+        .map_err(|e| format!("Failed to serialize triggers: {}", e))?;
+
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write triggers file: {}", e))
+}
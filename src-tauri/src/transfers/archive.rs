@@ -0,0 +1,455 @@
+//! Remote archive creation and extraction
+//!
+//! When `tar`/`zip`/`unzip` exist on the server the work is done there via
+//! `ssh_exec` (nothing goes over the wire). Otherwise the files are pulled
+//! into a local staging directory, archived or extracted locally, and the
+//! result is pushed back through SFTP. Progress is reported with
+//! `sftp-archive-progress` events.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use super::control::TransferControl;
+use super::progress::ProgressTracker;
+use super::recursive::{download_directory, remote_is_dir, upload_directory, DirectoryTransferOptions};
+use super::stream::{download_to_local, upload_to_remote, UploadSource};
+use crate::connectors::ssh_exec::{shell_quote, ssh_exec};
+use crate::connectors::{SftpPool, SshConfig};
+
+/// Printed after a successful remote command (`ssh_exec` doesn't report exit codes)
+const SUCCESS_MARKER: &str = "__SIMPLYTERM_OK__";
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ArchiveFormat {
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar")]
+    Tar,
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Guess the format from an archive file name
+    pub fn from_path(path: &str) -> Option<Self> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if lower.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if lower.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where the archive work was done
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveMethod {
+    /// Server-side tools via ssh_exec
+    Remote,
+    /// Local staging directory + SFTP
+    Local,
+}
+
+/// Progress event for archive operations
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveProgress {
+    pub session_id: String,
+    /// Archive being created or extracted
+    pub archive_path: String,
+    /// "running", "downloading", "archiving", "extracting" or "uploading"
+    pub stage: String,
+    pub method: ArchiveMethod,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// Outcome of an archive operation
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveResult {
+    pub method: ArchiveMethod,
+    /// Created archive, or directory the archive was extracted into
+    pub path: String,
+}
+
+struct Reporter<'a> {
+    app: &'a AppHandle,
+    session_id: &'a str,
+    archive_path: &'a str,
+}
+
+impl Reporter<'_> {
+    fn emit(&self, stage: &str, method: ArchiveMethod, bytes_done: u64, bytes_total: u64, done: bool, error: Option<String>) {
+        let _ = self.app.emit("sftp-archive-progress", ArchiveProgress {
+            session_id: self.session_id.to_string(),
+            archive_path: self.archive_path.to_string(),
+            stage: stage.to_string(),
+            method,
+            bytes_done,
+            bytes_total,
+            done,
+            error,
+        });
+    }
+
+    fn stage(&self, stage: &str, method: ArchiveMethod) {
+        self.emit(stage, method, 0, 0, false, None);
+    }
+
+    fn tracker(&self, stage: &str) -> impl FnMut(&ProgressTracker) + Send + '_ {
+        let stage = stage.to_string();
+        move |t: &ProgressTracker| self.emit(&stage, ArchiveMethod::Local, t.transferred(), t.total_bytes(), false, None)
+    }
+}
+
+/// Split a remote path into (parent directory, name)
+fn split_remote_path(path: &str) -> Result<(String, String), String> {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some((parent, name)) if !name.is_empty() => {
+            let parent = if parent.is_empty() { "/" } else { parent };
+            Ok((parent.to_string(), name.to_string()))
+        }
+        _ => Err(format!("Invalid remote path: {}", path)),
+    }
+}
+
+/// Run a shell command on the server, failing with its output if it doesn't succeed
+async fn run_remote(config: &SshConfig, command: &str) -> Result<(), String> {
+    let output = ssh_exec(config, &format!("{{ {}; }} 2>&1 && echo {}", command, SUCCESS_MARKER)).await?;
+    if output.lines().any(|l| l.trim() == SUCCESS_MARKER) {
+        Ok(())
+    } else {
+        Err(format!("Remote command failed: {}", output.trim()))
+    }
+}
+
+/// Whether all the given commands are available on the server
+async fn remote_has_tools(config: &SshConfig, tools: &[&str]) -> bool {
+    let checks: Vec<String> = tools.iter().map(|t| format!("command -v {} >/dev/null", t)).collect();
+    run_remote(config, &checks.join(" && ")).await.is_ok()
+}
+
+fn staging_dir() -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir()
+        .join("simplyterm-archive")
+        .join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create staging directory: {}", e))?;
+    Ok(dir)
+}
+
+// ============================================================================
+// Create
+// ============================================================================
+
+/// Create an archive at `archive_path` from the given remote paths
+pub async fn create_archive(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    sources: &[String],
+    archive_path: &str,
+    format: ArchiveFormat,
+    app: &AppHandle,
+) -> Result<ArchiveResult, String> {
+    if sources.is_empty() {
+        return Err("Nothing to archive".to_string());
+    }
+    let reporter = Reporter { app, session_id, archive_path };
+
+    let tools: &[&str] = match format {
+        ArchiveFormat::TarGz => &["tar", "gzip"],
+        ArchiveFormat::Tar => &["tar"],
+        ArchiveFormat::Zip => &["zip"],
+    };
+    let method = if remote_has_tools(config, tools).await { ArchiveMethod::Remote } else { ArchiveMethod::Local };
+
+    let result = match method {
+        ArchiveMethod::Remote => {
+            reporter.stage("running", method);
+            create_remote(config, sources, archive_path, format).await
+        }
+        ArchiveMethod::Local => create_local(pool, session_id, config, sources, archive_path, format, &reporter).await,
+    };
+
+    reporter.emit("done", method, 0, 0, true, result.as_ref().err().cloned());
+    result.map(|_| ArchiveResult { method, path: archive_path.to_string() })
+}
+
+async fn create_remote(config: &SshConfig, sources: &[String], archive_path: &str, format: ArchiveFormat) -> Result<(), String> {
+    let out = shell_quote(archive_path);
+    let command = match format {
+        ArchiveFormat::TarGz | ArchiveFormat::Tar => {
+            let flags = if format == ArchiveFormat::TarGz { "-czf" } else { "-cf" };
+            let mut command = format!("tar {} {}", flags, out);
+            for source in sources {
+                let (parent, name) = split_remote_path(source)?;
+                // "./" keeps names starting with '-' from being read as options
+                command.push_str(&format!(" -C {} {}", shell_quote(&parent), shell_quote(&format!("./{}", name))));
+            }
+            command
+        }
+        ArchiveFormat::Zip => {
+            // zip adds to an existing archive, so start from scratch
+            let mut parts = vec![format!("rm -f {}", out)];
+            for source in sources {
+                let (parent, name) = split_remote_path(source)?;
+                parts.push(format!(
+                    "(cd {} && zip -qry {} {})",
+                    shell_quote(&parent),
+                    out,
+                    shell_quote(&format!("./{}", name))
+                ));
+            }
+            parts.join(" && ")
+        }
+    };
+    run_remote(config, &command).await
+}
+
+async fn create_local(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    sources: &[String],
+    archive_path: &str,
+    format: ArchiveFormat,
+    reporter: &Reporter<'_>,
+) -> Result<(), String> {
+    let staging = staging_dir()?;
+    let result = async {
+        let content = staging.join("content");
+        std::fs::create_dir_all(&content).map_err(|e| format!("Failed to create staging directory: {}", e))?;
+        let control = TransferControl::new();
+        let options = DirectoryTransferOptions {
+            preserve_permissions: true,
+            preserve_times: true,
+            ..Default::default()
+        };
+
+        reporter.stage("downloading", ArchiveMethod::Local);
+        let mut names = Vec::new();
+        for source in sources {
+            let (_, name) = split_remote_path(source)?;
+            let local = content.join(&name);
+            if remote_is_dir(pool, session_id, config, source).await? {
+                let summary = download_directory(pool, session_id, config, source, &local, &options, reporter.app).await?;
+                if let Some(error) = summary.failure_message() {
+                    return Err(error);
+                }
+            } else {
+                download_to_local(pool, session_id, config, source, &local, 0, false, &control, reporter.tracker("downloading")).await?;
+            }
+            names.push(name);
+        }
+
+        reporter.stage("archiving", ArchiveMethod::Local);
+        let archive_file = staging.join("archive");
+        let (content_dir, archive_out) = (content.clone(), archive_file.clone());
+        tokio::task::spawn_blocking(move || build_archive(&content_dir, &names, &archive_out, format))
+            .await
+            .map_err(|e| format!("Archive task failed: {}", e))??;
+
+        reporter.stage("uploading", ArchiveMethod::Local);
+        upload_to_remote(
+            pool, session_id, config, UploadSource::Path(archive_file), archive_path, 0, false, &control,
+            reporter.tracker("uploading"),
+        )
+        .await?;
+        Ok::<(), String>(())
+    }
+    .await;
+
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// Build an archive of `names` (relative to `dir`) into `out`
+fn build_archive(dir: &Path, names: &[String], out: &Path, format: ArchiveFormat) -> Result<(), String> {
+    let file = std::fs::File::create(out).map_err(|e| format!("Failed to create archive: {}", e))?;
+    match format {
+        ArchiveFormat::TarGz => {
+            let encoder = build_tar(GzEncoder::new(file, Compression::default()), dir, names)?;
+            encoder.finish().map_err(|e| format!("Failed to write archive: {}", e))?;
+        }
+        ArchiveFormat::Tar => {
+            build_tar(file, dir, names)?;
+        }
+        ArchiveFormat::Zip => build_zip(file, dir, names)?,
+    }
+    Ok(())
+}
+
+fn build_tar<W: Write>(writer: W, dir: &Path, names: &[String]) -> Result<W, String> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+    for name in names {
+        let path = dir.join(name);
+        let result = if path.is_dir() {
+            builder.append_dir_all(name, &path)
+        } else {
+            builder.append_path_with_name(&path, name)
+        };
+        result.map_err(|e| format!("Failed to add {} to archive: {}", name, e))?;
+    }
+    builder.into_inner().map_err(|e| format!("Failed to write archive: {}", e))
+}
+
+fn build_zip(file: std::fs::File, dir: &Path, names: &[String]) -> Result<(), String> {
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default();
+
+    let mut stack: Vec<String> = names.to_vec();
+    while let Some(relative) = stack.pop() {
+        let path = dir.join(&relative);
+        if path.is_dir() {
+            zip.add_directory(format!("{}/", relative), options)
+                .map_err(|e| format!("Failed to add {} to archive: {}", relative, e))?;
+            let entries = std::fs::read_dir(&path).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+            for entry in entries.filter_map(|e| e.ok()) {
+                stack.push(format!("{}/{}", relative, entry.file_name().to_string_lossy()));
+            }
+        } else {
+            zip.start_file(relative.as_str(), options)
+                .map_err(|e| format!("Failed to add {} to archive: {}", relative, e))?;
+            let mut source = std::fs::File::open(&path).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+            std::io::copy(&mut source, &mut zip).map_err(|e| format!("Failed to write archive: {}", e))?;
+        }
+    }
+
+    zip.finish().map_err(|e| format!("Failed to write archive: {}", e))?;
+    Ok(())
+}
+
+// ============================================================================
+// Extract
+// ============================================================================
+
+/// Extract a remote archive into `dest_dir` (created if missing)
+pub async fn extract_archive(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    archive_path: &str,
+    dest_dir: &str,
+    app: &AppHandle,
+) -> Result<ArchiveResult, String> {
+    let format = ArchiveFormat::from_path(archive_path)
+        .ok_or_else(|| format!("Unsupported archive type: {}", archive_path))?;
+    let reporter = Reporter { app, session_id, archive_path };
+
+    let tools: &[&str] = match format {
+        ArchiveFormat::TarGz => &["tar", "gzip"],
+        ArchiveFormat::Tar => &["tar"],
+        ArchiveFormat::Zip => &["unzip"],
+    };
+    let method = if remote_has_tools(config, tools).await { ArchiveMethod::Remote } else { ArchiveMethod::Local };
+
+    let result = match method {
+        ArchiveMethod::Remote => {
+            reporter.stage("running", method);
+            let (archive, dest) = (shell_quote(archive_path), shell_quote(dest_dir));
+            let command = match format {
+                ArchiveFormat::TarGz => format!("mkdir -p {} && tar -xzf {} -C {}", dest, archive, dest),
+                ArchiveFormat::Tar => format!("mkdir -p {} && tar -xf {} -C {}", dest, archive, dest),
+                ArchiveFormat::Zip => format!("mkdir -p {} && unzip -oq {} -d {}", dest, archive, dest),
+            };
+            run_remote(config, &command).await
+        }
+        ArchiveMethod::Local => extract_local(pool, session_id, config, archive_path, dest_dir, format, &reporter).await,
+    };
+
+    reporter.emit("done", method, 0, 0, true, result.as_ref().err().cloned());
+    result.map(|_| ArchiveResult { method, path: dest_dir.to_string() })
+}
+
+async fn extract_local(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    archive_path: &str,
+    dest_dir: &str,
+    format: ArchiveFormat,
+    reporter: &Reporter<'_>,
+) -> Result<(), String> {
+    let staging = staging_dir()?;
+    let result = async {
+        let control = TransferControl::new();
+        let archive_file = staging.join("archive");
+        let content = staging.join("content");
+
+        reporter.stage("downloading", ArchiveMethod::Local);
+        download_to_local(pool, session_id, config, archive_path, &archive_file, 0, false, &control, reporter.tracker("downloading")).await?;
+
+        reporter.stage("extracting", ArchiveMethod::Local);
+        let (archive_in, content_dir) = (archive_file.clone(), content.clone());
+        tokio::task::spawn_blocking(move || unpack_archive(&archive_in, &content_dir, format))
+            .await
+            .map_err(|e| format!("Extract task failed: {}", e))??;
+
+        reporter.stage("uploading", ArchiveMethod::Local);
+        let options = DirectoryTransferOptions {
+            preserve_permissions: true,
+            preserve_times: true,
+            ..Default::default()
+        };
+        let summary = upload_directory(pool, session_id, config, &content, dest_dir, &options, reporter.app).await?;
+        match summary.failure_message() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+    .await;
+
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// Unpack an archive into `dir`. Entries escaping `dir` are skipped by both libraries.
+fn unpack_archive(archive: &Path, dir: &Path, format: ArchiveFormat) -> Result<(), String> {
+    let file = std::fs::File::open(archive).map_err(|e| format!("Failed to open archive: {}", e))?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    match format {
+        ArchiveFormat::TarGz => tar::Archive::new(GzDecoder::new(file)).unpack(dir),
+        ArchiveFormat::Tar => tar::Archive::new(file).unpack(dir),
+        ArchiveFormat::Zip => {
+            return zip::ZipArchive::new(file)
+                .and_then(|mut archive| archive.extract(dir))
+                .map_err(|e| format!("Failed to extract archive: {}", e));
+        }
+    }
+    .map_err(|e| format!("Failed to extract archive: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ArchiveFormat::from_path("/var/log/logs.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_path("backup.TGZ"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_path("a.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(ArchiveFormat::from_path("a.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_path("a.rar"), None);
+    }
+
+    #[test]
+    fn test_split_remote_path() {
+        assert_eq!(split_remote_path("/var/log/").unwrap(), ("/var".to_string(), "log".to_string()));
+        assert_eq!(split_remote_path("/etc").unwrap(), ("/".to_string(), "etc".to_string()));
+        assert!(split_remote_path("/").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! Pause/cancel signalling for running transfers
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+/// Error message returned by transfers stopped through `TransferControl::cancel`
+pub const CANCELLED: &str = "Transfer cancelled";
+
+/// Shared flags checked by a transfer between chunks
+#[derive(Debug, Default)]
+pub struct TransferControl {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+    changed: Notify,
+}
+
+impl TransferControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.changed.notify_waiters();
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.changed.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the transfer is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.changed.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Wait while paused. Returns an error once the transfer is cancelled.
+    pub async fn checkpoint(&self) -> Result<(), String> {
+        loop {
+            if self.is_cancelled() {
+                return Err(CANCELLED.to_string());
+            }
+            if !self.is_paused() {
+                return Ok(());
+            }
+
+            // Register before re-checking so a resume/cancel in between isn't missed
+            let notified = self.changed.notified();
+            if self.is_cancelled() || !self.is_paused() {
+                continue;
+            }
+            notified.await;
+        }
+    }
+}
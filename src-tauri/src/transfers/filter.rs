@@ -0,0 +1,136 @@
+//! Include/exclude glob filters for directory transfers
+//!
+//! Patterns support `*` (any characters except `/`), `?` (one character
+//! except `/`) and `**` (any number of directories). A pattern without a `/`
+//! is matched against the entry name only; otherwise against the path
+//! relative to the transfer root.
+
+use serde::Deserialize;
+
+/// Include/exclude patterns applied to paths relative to the transfer root
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PathFilter {
+    /// Only files matching one of these are transferred (empty = everything)
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Files and directories matching one of these are skipped
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl PathFilter {
+    /// Whether an entry (file or directory) is excluded
+    pub fn is_excluded(&self, relative: &str) -> bool {
+        self.exclude.iter().any(|p| pattern_matches(p, relative))
+    }
+
+    /// Whether a file passes both the include and exclude lists
+    pub fn includes_file(&self, relative: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|p| pattern_matches(p, relative));
+        included && !self.is_excluded(relative)
+    }
+}
+
+fn pattern_matches(pattern: &str, relative: &str) -> bool {
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    if pattern.contains('/') {
+        glob_match(pattern, relative)
+    } else {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        glob_match(pattern, name)
+    }
+}
+
+/// Match `text` against a glob pattern
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(p: &[char], t: &[char]) -> bool {
+    match p.first() {
+        None => t.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => {
+            let rest = &p[2..];
+            // "**/" also matches zero directories
+            if rest.first() == Some(&'/') && match_from(&rest[1..], t) {
+                return true;
+            }
+            (0..=t.len()).any(|i| match_from(rest, &t[i..]))
+        }
+        Some('*') => {
+            let rest = &p[1..];
+            for i in 0..=t.len() {
+                if match_from(rest, &t[i..]) {
+                    return true;
+                }
+                if i < t.len() && t[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => t.first().map_or(false, |c| *c != '/') && match_from(&p[1..], &t[1..]),
+        Some(c) => t.first() == Some(c) && match_from(&p[1..], &t[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        PathFilter {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_star_does_not_cross_directories() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("src/*.rs", "src/a/main.rs"));
+        assert!(glob_match("src/*.rs", "src/main.rs"));
+    }
+
+    #[test]
+    fn test_double_star() {
+        assert!(glob_match("src/**/*.rs", "src/main.rs"));
+        assert!(glob_match("src/**/*.rs", "src/a/b/main.rs"));
+        assert!(glob_match("**", "a/b/c"));
+        assert!(!glob_match("src/**/*.rs", "lib/main.rs"));
+    }
+
+    #[test]
+    fn test_question_mark() {
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(!glob_match("file?.txt", "file10.txt"));
+        assert!(!glob_match("a?b", "a/b"));
+    }
+
+    #[test]
+    fn test_name_patterns_match_any_depth() {
+        let f = filter(&[], &["node_modules", "*.log"]);
+        assert!(f.is_excluded("node_modules"));
+        assert!(f.is_excluded("web/node_modules"));
+        assert!(f.is_excluded("logs/app.log"));
+        assert!(!f.is_excluded("src/app.rs"));
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let f = filter(&["*.html", "assets/**"], &["*.map"]);
+        assert!(f.includes_file("index.html"));
+        assert!(f.includes_file("assets/img/logo.png"));
+        assert!(!f.includes_file("assets/app.js.map"));
+        assert!(!f.includes_file("README.md"));
+    }
+
+    #[test]
+    fn test_empty_filter_includes_everything() {
+        let f = PathFilter::default();
+        assert!(f.includes_file("any/path/file"));
+        assert!(!f.is_excluded("any/path"));
+    }
+}
@@ -0,0 +1,181 @@
+//! SHA-256 checksums of transferred files
+//!
+//! Transfers hash data while it streams (`HashingStream`) and compare the
+//! result with the remote file afterwards. The `check-file` SFTP extension
+//! isn't exposed by russh-sftp, so the remote side uses `sha256sum` (or
+//! `shasum`) on the session's pooled connection; files are never read back
+//! just to hash them.
+
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+use super::stream::CHUNK_SIZE;
+use crate::connectors::sftp::session_exec;
+use crate::connectors::ssh_exec::shell_quote;
+use crate::connectors::{SftpPool, SshConfig};
+
+/// Prefix of the error returned when checksums differ after a transfer
+pub const CHECKSUM_MISMATCH: &str = "Checksum mismatch";
+
+/// Reader or writer adapter that hashes every byte passing through it
+pub(crate) struct HashingStream<T> {
+    inner: T,
+    hasher: Sha256,
+}
+
+impl<T> HashingStream<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Self { inner, hasher: Sha256::new() }
+    }
+
+    /// Hex digest of everything read or written so far
+    pub(crate) fn finish(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+
+    /// The wrapped stream and the digest so far
+    pub(crate) fn into_parts(self) -> (T, String) {
+        (self.inner, hex::encode(self.hasher.finalize()))
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for HashingStream<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            this.hasher.update(&buf.filled()[before..]);
+        }
+        result
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for HashingStream<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let result = Pin::new(&mut this.inner).poll_write(cx, data);
+        if let Poll::Ready(Ok(n)) = result {
+            this.hasher.update(&data[..n]);
+        }
+        result
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// Hash everything `reader` yields
+pub(crate) async fn sha256_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("Failed to read data: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// SHA-256 of a local file
+pub(crate) async fn local_sha256(path: &Path) -> Result<String, String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open local file: {}", e))?;
+    sha256_reader(&mut file).await
+}
+
+fn is_sha256_hex(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// SHA-256 of a remote file, computed on the server with `sha256sum` (or
+/// `shasum -a 256` on BSD/macOS)
+pub async fn remote_sha256(pool: &SftpPool, session_id: &str, config: &SshConfig, path: &str) -> Result<String, String> {
+    let path = shell_quote(path);
+    let command = format!(
+        "sha256sum -- {0} 2>/dev/null || shasum -a 256 -- {0} 2>/dev/null",
+        path
+    );
+    let output = session_exec(pool, session_id, config, &command).await?;
+    parse_sha256_output(&output)
+        .ok_or_else(|| "Cannot verify checksum: sha256sum is not available on the server".to_string())
+}
+
+/// Digest from a `sha256sum` line. GNU sha256sum prefixes the line with `\`
+/// when the file name contains a backslash or a newline.
+fn parse_sha256_output(output: &str) -> Option<String> {
+    let hash = output.split_whitespace().next()?;
+    let hash = hash.strip_prefix('\\').unwrap_or(hash);
+    is_sha256_hex(hash).then(|| hash.to_lowercase())
+}
+
+/// Compare a locally computed digest with the remote file
+pub(crate) async fn verify_remote(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    local_hash: &str,
+) -> Result<(), String> {
+    let remote_hash = remote_sha256(pool, session_id, config, remote_path).await?;
+    check_digests(local_hash, &remote_hash)
+}
+
+/// Error out when two digests differ
+pub(crate) fn check_digests(local: &str, remote: &str) -> Result<(), String> {
+    if local.eq_ignore_ascii_case(remote) {
+        Ok(())
+    } else {
+        Err(format!("{} (local {}, remote {})", CHECKSUM_MISMATCH, local, remote))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    const HELLO: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[tokio::test]
+    async fn test_hashing_stream_matches_sha256() {
+        let mut reader = HashingStream::new(&b"hello"[..]);
+        let mut out = Vec::<u8>::new();
+        reader.read_to_end(&mut out).await.unwrap();
+        assert_eq!(out, b"hello");
+        assert_eq!(reader.finish(), HELLO);
+
+        let writer = HashingStream::new(Vec::<u8>::new());
+        assert_eq!(writer.finish(), EMPTY);
+    }
+
+    #[test]
+    fn test_parse_sha256_output() {
+        assert_eq!(parse_sha256_output(&format!("{}  /srv/a.txt\n", HELLO)).as_deref(), Some(HELLO));
+        assert_eq!(parse_sha256_output(&format!("\\{}  /srv/a\\nb.txt\n", HELLO)).as_deref(), Some(HELLO));
+        assert_eq!(parse_sha256_output(&HELLO.to_uppercase()).as_deref(), Some(HELLO));
+        assert_eq!(parse_sha256_output("sh: sha256sum: not found"), None);
+        assert_eq!(parse_sha256_output(""), None);
+    }
+
+    #[test]
+    fn test_digests_compare_case_insensitively() {
+        assert!(check_digests(HELLO, &HELLO.to_uppercase()).is_ok());
+        assert!(check_digests(HELLO, EMPTY).unwrap_err().starts_with(CHECKSUM_MISMATCH));
+    }
+}
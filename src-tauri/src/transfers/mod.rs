@@ -0,0 +1,34 @@
+//! SFTP file transfers
+//!
+//! - progress.rs: Throttled progress tracking (speed, ETA)
+//! - stream.rs: Chunked uploads/downloads between local disk and remote files
+//! - resume.rs: Resume offsets for interrupted transfers
+//! - control.rs: Pause/cancel signalling for running transfers
+//! - queue.rs: Transfer queue with concurrency limits and retries
+//! - filter.rs: Include/exclude glob filters
+//! - recursive.rs: Recursive directory uploads and downloads
+//! - sync.rs: Local/remote directory synchronisation (plan, then execute)
+//! - remote_copy.rs: Remote-to-remote copies between sessions
+//! - integrity.rs: SHA-256 checksums and post-transfer verification
+//! - archive.rs: Remote archive creation and extraction
+
+pub mod archive;
+pub mod control;
+pub mod filter;
+pub mod integrity;
+pub mod progress;
+pub mod queue;
+pub mod recursive;
+pub mod remote_copy;
+pub mod resume;
+pub mod stream;
+pub mod sync;
+
+pub use archive::{create_archive, extract_archive, ArchiveFormat, ArchiveResult};
+pub use queue::{TransferJobInfo, TransferKind, TransferQueue};
+pub use recursive::{
+    download_directory, remote_is_dir, upload_directory, DirectoryTransferOptions, DirectoryTransferSummary,
+};
+pub use remote_copy::{copy_remote, RemoteCopyOptions};
+pub use stream::{sftp_download_file, sftp_upload_file, RemoteEndpoint, TransferOptions, UploadSource};
+pub use sync::{execute_sync, plan_sync, SyncOptions, SyncPlan, SyncSummary};
@@ -0,0 +1,131 @@
+//! Throttled progress tracking for file transfers
+//!
+//! Computes a smoothed transfer speed and ETA, and decides when a progress
+//! event is worth emitting so large transfers don't flood the frontend.
+
+use std::time::{Duration, Instant};
+
+/// Minimum delay between two progress events for the same transfer
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Weight of the latest sample in the speed moving average
+const SPEED_SMOOTHING: f64 = 0.3;
+
+/// Tracks bytes transferred and rate-limits progress notifications
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    total_bytes: u64,
+    transferred: u64,
+    last_emit: Option<Instant>,
+    sample_start: Instant,
+    sample_bytes: u64,
+    bytes_per_sec: f64,
+}
+
+impl ProgressTracker {
+    pub fn new(total_bytes: u64) -> Self {
+        Self::resumed(total_bytes, 0)
+    }
+
+    /// Tracker for a transfer that continues from `offset` bytes already in place
+    pub fn resumed(total_bytes: u64, offset: u64) -> Self {
+        Self {
+            total_bytes,
+            transferred: offset,
+            last_emit: None,
+            sample_start: Instant::now(),
+            sample_bytes: 0,
+            bytes_per_sec: 0.0,
+        }
+    }
+
+    /// Record `n` more bytes. Returns true when a progress event should be emitted.
+    pub fn advance(&mut self, n: u64) -> bool {
+        self.transferred += n;
+        self.sample_bytes += n;
+
+        let now = Instant::now();
+        let due = self
+            .last_emit
+            .map_or(true, |last| now.duration_since(last) >= PROGRESS_INTERVAL);
+
+        if due {
+            self.update_speed(now);
+            self.last_emit = Some(now);
+        }
+
+        due
+    }
+
+    fn update_speed(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.sample_start).as_secs_f64();
+        if elapsed <= 0.0 {
+            return;
+        }
+
+        let sample = self.sample_bytes as f64 / elapsed;
+        self.bytes_per_sec = if self.bytes_per_sec == 0.0 {
+            sample
+        } else {
+            SPEED_SMOOTHING * sample + (1.0 - SPEED_SMOOTHING) * self.bytes_per_sec
+        };
+
+        self.sample_start = now;
+        self.sample_bytes = 0;
+    }
+
+    pub fn transferred(&self) -> u64 {
+        self.transferred
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec as u64
+    }
+
+    /// Estimated seconds remaining, if the speed is known
+    pub fn eta_secs(&self) -> Option<u64> {
+        if self.bytes_per_sec < 1.0 {
+            return None;
+        }
+        let remaining = self.total_bytes.saturating_sub(self.transferred);
+        Some((remaining as f64 / self.bytes_per_sec).ceil() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_advance_emits() {
+        let mut tracker = ProgressTracker::new(100);
+        assert!(tracker.advance(10));
+        assert_eq!(tracker.transferred(), 10);
+    }
+
+    #[test]
+    fn test_rapid_advances_are_throttled() {
+        let mut tracker = ProgressTracker::new(1000);
+        assert!(tracker.advance(1));
+        assert!(!tracker.advance(1));
+        assert!(!tracker.advance(1));
+        assert_eq!(tracker.transferred(), 3);
+    }
+
+    #[test]
+    fn test_resumed_starts_at_offset() {
+        let mut tracker = ProgressTracker::resumed(1000, 400);
+        tracker.advance(100);
+        assert_eq!(tracker.transferred(), 500);
+    }
+
+    #[test]
+    fn test_eta_unknown_without_speed() {
+        let tracker = ProgressTracker::new(1000);
+        assert_eq!(tracker.eta_secs(), None);
+    }
+}
@@ -0,0 +1,557 @@
+//! Transfer queue - schedules uploads, downloads and remote-to-remote copies
+//!
+//! Jobs start in FIFO order, bounded by a global and a per-session
+//! concurrency limit. Each job can be paused, resumed, cancelled or retried.
+//! Failed jobs are retried automatically, resuming from the partial file
+//! (or starting over after a checksum mismatch).
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use super::control::TransferControl;
+use super::integrity::CHECKSUM_MISMATCH;
+use super::progress::ProgressTracker;
+use super::resume::{copy_resume_offset, download_resume_offset, upload_resume_offset};
+use super::stream::{copy_remote_to_remote, download_to_local, upload_to_remote, RemoteEndpoint, UploadSource};
+use crate::session::SessionManager;
+use crate::storage::TransferSettings;
+
+/// Type of queued transfer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferKind {
+    /// Local file -> remote path
+    Upload,
+    /// Remote file -> local path
+    Download,
+    /// Remote file -> remote path (same or another session)
+    Copy,
+}
+
+/// Status of a queued transfer
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "state", content = "message")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed(_) | JobStatus::Cancelled)
+    }
+}
+
+/// Information about a queued transfer
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferJobInfo {
+    pub id: String,
+    pub kind: TransferKind,
+    /// Session the source path belongs to (the only session for uploads/downloads)
+    pub session_id: String,
+    pub source_path: String,
+    /// Destination session for copies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dest_session_id: Option<String>,
+    pub dest_path: String,
+    /// Compare SHA-256 of both sides once the file is transferred
+    pub verify: bool,
+    pub status: JobStatus,
+    pub total_bytes: u64,
+    pub transferred_bytes: u64,
+    pub bytes_per_sec: u64,
+    pub eta_secs: Option<u64>,
+    pub attempts: u32,
+    pub created_at: i64,
+}
+
+impl TransferJobInfo {
+    fn involves_session(&self, session_id: &str) -> bool {
+        self.session_id == session_id || self.dest_session_id.as_deref() == Some(session_id)
+    }
+
+    fn sessions(&self) -> Vec<&str> {
+        let mut sessions = vec![self.session_id.as_str()];
+        if let Some(dest) = self.dest_session_id.as_deref() {
+            if dest != self.session_id {
+                sessions.push(dest);
+            }
+        }
+        sessions
+    }
+}
+
+struct TransferJob {
+    info: TransferJobInfo,
+    control: Arc<TransferControl>,
+    /// A task is currently driving this job (it may be paused mid-transfer)
+    active: bool,
+}
+
+/// Central queue for all SFTP transfers
+pub struct TransferQueue {
+    jobs: RwLock<HashMap<String, TransferJob>>,
+    /// Job IDs in submission order
+    order: RwLock<Vec<String>>,
+    limits: RwLock<TransferSettings>,
+    session_manager: Arc<SessionManager>,
+    app: AppHandle,
+}
+
+impl TransferQueue {
+    pub fn new(session_manager: Arc<SessionManager>, app: AppHandle, limits: TransferSettings) -> Arc<Self> {
+        Arc::new(Self {
+            jobs: RwLock::new(HashMap::new()),
+            order: RwLock::new(Vec::new()),
+            limits: RwLock::new(limits),
+            session_manager,
+            app,
+        })
+    }
+
+    /// Add a transfer to the queue and start it if a slot is free
+    pub fn enqueue(
+        self: &Arc<Self>,
+        kind: TransferKind,
+        session_id: String,
+        source_path: String,
+        dest_session_id: Option<String>,
+        dest_path: String,
+        verify: bool,
+    ) -> TransferJobInfo {
+        let info = TransferJobInfo {
+            id: Uuid::new_v4().to_string(),
+            kind,
+            session_id,
+            source_path,
+            dest_session_id,
+            dest_path,
+            verify,
+            status: JobStatus::Queued,
+            total_bytes: 0,
+            transferred_bytes: 0,
+            bytes_per_sec: 0,
+            eta_secs: None,
+            attempts: 0,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.jobs.write().insert(info.id.clone(), TransferJob {
+            info: info.clone(),
+            control: Arc::new(TransferControl::new()),
+            active: false,
+        });
+        self.order.write().push(info.id.clone());
+        self.emit_updated(&info);
+
+        self.schedule();
+        self.get(&info.id).unwrap_or(info)
+    }
+
+    /// List jobs in submission order, optionally filtered by session
+    pub fn list(&self, session_id: Option<&str>) -> Vec<TransferJobInfo> {
+        let jobs = self.jobs.read();
+        self.order
+            .read()
+            .iter()
+            .filter_map(|id| jobs.get(id))
+            .filter(|j| session_id.map_or(true, |sid| j.info.involves_session(sid)))
+            .map(|j| j.info.clone())
+            .collect()
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<TransferJobInfo> {
+        self.jobs.read().get(job_id).map(|j| j.info.clone())
+    }
+
+    /// Pause a queued or running job
+    pub fn pause(self: &Arc<Self>, job_id: &str) -> Result<(), String> {
+        let info = {
+            let mut jobs = self.jobs.write();
+            let job = jobs.get_mut(job_id).ok_or_else(|| format!("Transfer not found: {}", job_id))?;
+            match job.info.status {
+                JobStatus::Queued | JobStatus::Running => {
+                    job.control.pause();
+                    job.info.status = JobStatus::Paused;
+                    job.info.bytes_per_sec = 0;
+                    job.info.eta_secs = None;
+                }
+                JobStatus::Paused => return Ok(()),
+                _ => return Err("Transfer already finished".to_string()),
+            }
+            job.info.clone()
+        };
+        self.emit_updated(&info);
+        // A paused job frees its slot for the next one
+        self.schedule();
+        Ok(())
+    }
+
+    /// Resume a paused job
+    pub fn resume(self: &Arc<Self>, job_id: &str) -> Result<(), String> {
+        let info = {
+            let mut jobs = self.jobs.write();
+            let job = jobs.get_mut(job_id).ok_or_else(|| format!("Transfer not found: {}", job_id))?;
+            if job.info.status != JobStatus::Paused {
+                return Ok(());
+            }
+            // Stays paused until `schedule` gives it a slot again
+            job.info.status = JobStatus::Queued;
+            job.info.clone()
+        };
+        self.emit_updated(&info);
+        self.schedule();
+        Ok(())
+    }
+
+    /// Cancel a job (running transfers stop at the next chunk)
+    pub fn cancel(self: &Arc<Self>, job_id: &str) -> Result<(), String> {
+        let info = {
+            let mut jobs = self.jobs.write();
+            let job = jobs.get_mut(job_id).ok_or_else(|| format!("Transfer not found: {}", job_id))?;
+            if job.info.status.is_finished() {
+                return Ok(());
+            }
+            job.control.cancel();
+            // Active jobs report Cancelled themselves once their task exits
+            if job.active {
+                return Ok(());
+            }
+            job.info.status = JobStatus::Cancelled;
+            job.info.clone()
+        };
+        self.emit_updated(&info);
+        Ok(())
+    }
+
+    /// Cancel every unfinished job involving a session (called when it closes)
+    pub fn cancel_session(self: &Arc<Self>, session_id: &str) {
+        let ids: Vec<String> = self
+            .jobs
+            .read()
+            .values()
+            .filter(|j| j.info.involves_session(session_id) && !j.info.status.is_finished())
+            .map(|j| j.info.id.clone())
+            .collect();
+
+        for id in ids {
+            let _ = self.cancel(&id);
+        }
+    }
+
+    /// Put a failed or cancelled job back in the queue
+    pub fn retry(self: &Arc<Self>, job_id: &str) -> Result<(), String> {
+        let info = {
+            let mut jobs = self.jobs.write();
+            let job = jobs.get_mut(job_id).ok_or_else(|| format!("Transfer not found: {}", job_id))?;
+            if job.active || !matches!(job.info.status, JobStatus::Failed(_) | JobStatus::Cancelled) {
+                return Err("Only failed or cancelled transfers can be retried".to_string());
+            }
+            job.control = Arc::new(TransferControl::new());
+            job.info.status = JobStatus::Queued;
+            job.info.attempts = 0;
+            job.info.clone()
+        };
+        self.emit_updated(&info);
+        self.schedule();
+        Ok(())
+    }
+
+    /// Drop finished jobs from the list
+    pub fn clear_finished(&self) {
+        let mut jobs = self.jobs.write();
+        jobs.retain(|_, j| j.active || !j.info.status.is_finished());
+        self.order.write().retain(|id| jobs.contains_key(id));
+    }
+
+    /// Update concurrency/retry limits (takes effect for the next scheduling round)
+    pub fn set_limits(self: &Arc<Self>, limits: TransferSettings) {
+        *self.limits.write() = limits;
+        self.schedule();
+    }
+
+    /// Start (or let continue, for resumed jobs) as many queued jobs as the
+    /// limits allow
+    fn schedule(self: &Arc<Self>) {
+        let started: Vec<(TransferJobInfo, bool)> = {
+            let limits = self.limits.read().clone();
+            let mut jobs = self.jobs.write();
+            let order = self.order.read();
+
+            select_jobs(&jobs, &order, &limits)
+                .into_iter()
+                .filter_map(|id| {
+                    let job = jobs.get_mut(&id)?;
+                    let spawn = !job.active;
+                    job.info.status = JobStatus::Running;
+                    job.active = true;
+                    job.control.resume();
+                    Some((job.info.clone(), spawn))
+                })
+                .collect()
+        };
+
+        for (info, spawn) in started {
+            self.emit_updated(&info);
+            if spawn {
+                tokio::spawn(self.clone().run_job(info.id));
+            }
+        }
+    }
+
+    /// Drive a job to completion, retrying on failure
+    async fn run_job(self: Arc<Self>, job_id: String) {
+        let (info, control) = match self.jobs.read().get(&job_id) {
+            Some(job) => (job.info.clone(), job.control.clone()),
+            None => return,
+        };
+        let max_retries = self.limits.read().max_retries;
+
+        let mut attempt = 0;
+        let mut resume = false;
+        let result = loop {
+            attempt += 1;
+            self.update(&job_id, |info| info.attempts = attempt);
+
+            match self.execute(&info, &control, resume).await {
+                Ok(()) => break Ok(()),
+                Err(e) if control.is_cancelled() || attempt > max_retries => break Err(e),
+                Err(e) => {
+                    // Resuming would keep the corrupted data: start over instead
+                    resume = !e.starts_with(CHECKSUM_MISMATCH);
+                    eprintln!("[TransferQueue] {} failed (attempt {}): {}", job_id, attempt, e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(2u64.pow(attempt.min(5)))) => {}
+                        _ = control.cancelled() => break Err(e),
+                    }
+                    // Paused during the backoff: wait for a slot before retrying
+                    if control.checkpoint().await.is_err() {
+                        break Err(e);
+                    }
+                }
+            }
+        };
+
+        let info = {
+            let mut jobs = self.jobs.write();
+            let Some(job) = jobs.get_mut(&job_id) else { return };
+            job.active = false;
+            job.info.bytes_per_sec = 0;
+            job.info.eta_secs = None;
+            job.info.status = match result {
+                Ok(()) => {
+                    job.info.transferred_bytes = job.info.total_bytes;
+                    JobStatus::Completed
+                }
+                Err(_) if control.is_cancelled() => JobStatus::Cancelled,
+                Err(e) => JobStatus::Failed(e),
+            };
+            job.info.clone()
+        };
+        self.emit_updated(&info);
+        self.schedule();
+    }
+
+    /// Run a single attempt of a job
+    async fn execute(&self, info: &TransferJobInfo, control: &TransferControl, resume: bool) -> Result<(), String> {
+        let config = self
+            .session_manager
+            .get_ssh_config(&info.session_id)
+            .ok_or_else(|| "SSH session not found".to_string())?;
+        let pool = self.session_manager.sftp_pool();
+        let on_progress = |tracker: &ProgressTracker| self.report_progress(&info.id, tracker);
+
+        match info.kind {
+            TransferKind::Upload => {
+                let local = PathBuf::from(&info.source_path);
+                let offset = if resume {
+                    upload_resume_offset(pool, &info.session_id, &config, &local, &info.dest_path, false).await?
+                } else {
+                    0
+                };
+                upload_to_remote(
+                    pool, &info.session_id, &config, UploadSource::Path(local), &info.dest_path,
+                    offset, info.verify, control, on_progress,
+                )
+                .await?;
+            }
+            TransferKind::Download => {
+                let local = PathBuf::from(&info.dest_path);
+                let offset = if resume {
+                    download_resume_offset(pool, &info.session_id, &config, &info.source_path, &local, false).await?
+                } else {
+                    0
+                };
+                download_to_local(
+                    pool, &info.session_id, &config, &info.source_path, &local,
+                    offset, info.verify, control, on_progress,
+                )
+                .await?;
+            }
+            TransferKind::Copy => {
+                let dest_session_id = info.dest_session_id.as_deref().unwrap_or(&info.session_id);
+                let dest_config = self
+                    .session_manager
+                    .get_ssh_config(dest_session_id)
+                    .ok_or_else(|| "Destination SSH session not found".to_string())?;
+                let source = RemoteEndpoint {
+                    session_id: &info.session_id,
+                    config: &config,
+                    path: &info.source_path,
+                };
+                let dest = RemoteEndpoint {
+                    session_id: dest_session_id,
+                    config: &dest_config,
+                    path: &info.dest_path,
+                };
+                let offset = if resume {
+                    copy_resume_offset(pool, &source, &dest).await?
+                } else {
+                    0
+                };
+                copy_remote_to_remote(pool, source, dest, offset, info.verify, control, on_progress).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn report_progress(&self, job_id: &str, tracker: &ProgressTracker) {
+        let info = {
+            let mut jobs = self.jobs.write();
+            let Some(job) = jobs.get_mut(job_id) else { return };
+            job.info.total_bytes = tracker.total_bytes();
+            job.info.transferred_bytes = tracker.transferred();
+            job.info.bytes_per_sec = tracker.bytes_per_sec();
+            job.info.eta_secs = tracker.eta_secs();
+            job.info.clone()
+        };
+        let _ = self.app.emit("transfer-job-progress", info);
+    }
+
+    fn update(&self, job_id: &str, f: impl FnOnce(&mut TransferJobInfo)) {
+        if let Some(job) = self.jobs.write().get_mut(job_id) {
+            f(&mut job.info);
+        }
+    }
+
+    fn emit_updated(&self, info: &TransferJobInfo) {
+        let _ = self.app.emit("transfer-job-updated", info.clone());
+    }
+}
+
+/// IDs of the queued jobs to run next, in submission order, without going
+/// over the global or per-session limit
+fn select_jobs(jobs: &HashMap<String, TransferJob>, order: &[String], limits: &TransferSettings) -> Vec<String> {
+    let max_concurrent = limits.max_concurrent.max(1);
+    let max_per_session = limits.max_per_session.max(1);
+
+    let mut running = 0;
+    let mut per_session: HashMap<&str, usize> = HashMap::new();
+    for job in jobs.values().filter(|j| j.info.status == JobStatus::Running) {
+        running += 1;
+        for sid in job.info.sessions() {
+            *per_session.entry(sid).or_default() += 1;
+        }
+    }
+
+    let mut selected = Vec::new();
+    for id in order {
+        if running >= max_concurrent {
+            break;
+        }
+        let Some(job) = jobs.get(id) else { continue };
+        if job.info.status != JobStatus::Queued {
+            continue;
+        }
+        let sessions = job.info.sessions();
+        if sessions.iter().any(|sid| per_session.get(sid).copied().unwrap_or(0) >= max_per_session) {
+            continue;
+        }
+
+        running += 1;
+        for sid in sessions {
+            *per_session.entry(sid).or_default() += 1;
+        }
+        selected.push(id.clone());
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, session_id: &str, status: JobStatus, active: bool) -> TransferJob {
+        TransferJob {
+            info: TransferJobInfo {
+                id: id.to_string(),
+                kind: TransferKind::Download,
+                session_id: session_id.to_string(),
+                source_path: "/remote".to_string(),
+                dest_session_id: None,
+                dest_path: "/local".to_string(),
+                verify: false,
+                status,
+                total_bytes: 0,
+                transferred_bytes: 0,
+                bytes_per_sec: 0,
+                eta_secs: None,
+                attempts: 0,
+                created_at: 0,
+            },
+            control: Arc::new(TransferControl::new()),
+            active,
+        }
+    }
+
+    fn queue(jobs: Vec<TransferJob>) -> (HashMap<String, TransferJob>, Vec<String>) {
+        let order = jobs.iter().map(|j| j.info.id.clone()).collect();
+        (jobs.into_iter().map(|j| (j.info.id.clone(), j)).collect(), order)
+    }
+
+    fn limits(max_concurrent: usize, max_per_session: usize) -> TransferSettings {
+        TransferSettings { max_concurrent, max_per_session, max_retries: 0 }
+    }
+
+    #[test]
+    fn test_selects_queued_jobs_within_limits() {
+        let (jobs, order) = queue(vec![
+            job("a", "s1", JobStatus::Running, true),
+            job("b", "s1", JobStatus::Queued, false),
+            job("c", "s1", JobStatus::Queued, false),
+            job("d", "s2", JobStatus::Queued, false),
+            job("e", "s3", JobStatus::Queued, false),
+        ]);
+        assert_eq!(select_jobs(&jobs, &order, &limits(3, 2)), vec!["b", "d"]);
+        assert_eq!(select_jobs(&jobs, &order, &limits(4, 1)), vec!["d", "e"]);
+    }
+
+    #[test]
+    fn test_resumed_jobs_wait_for_a_slot() {
+        // "a" was paused mid-transfer, freeing its slot for "b"
+        let (mut jobs, order) = queue(vec![
+            job("a", "s1", JobStatus::Paused, true),
+            job("b", "s1", JobStatus::Running, true),
+        ]);
+
+        // Once resumed, it's queued behind the running job instead of running
+        jobs.get_mut("a").unwrap().info.status = JobStatus::Queued;
+        assert!(select_jobs(&jobs, &order, &limits(1, 1)).is_empty());
+        assert!(select_jobs(&jobs, &order, &limits(2, 1)).is_empty());
+        assert_eq!(select_jobs(&jobs, &order, &limits(2, 2)), vec!["a"]);
+
+        jobs.get_mut("b").unwrap().info.status = JobStatus::Completed;
+        assert_eq!(select_jobs(&jobs, &order, &limits(1, 1)), vec!["a"]);
+    }
+}
@@ -0,0 +1,692 @@
+//! Recursive directory uploads and downloads
+//!
+//! The source tree is scanned first (applying the include/exclude filters) so
+//! progress can report totals, then directories are created and files are
+//! streamed one by one. A failing entry doesn't abort the transfer; it is
+//! reported in the returned summary along with skipped entries.
+
+use russh_sftp::protocol::FileAttributes;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use super::control::TransferControl;
+use super::filter::PathFilter;
+use super::stream::{download_to_local, upload_to_remote, UploadSource};
+use crate::connectors::scp::{scp_list_dir, scp_set_attrs, scp_stat};
+use crate::connectors::sftp::{join_remote_path, uses_scp, with_sftp};
+use crate::connectors::{FileEntry, SftpPool, SshConfig};
+use crate::storage::TransferDirection;
+
+/// Options for a directory transfer
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryTransferOptions {
+    #[serde(flatten)]
+    pub filter: PathFilter,
+    /// Copy permission bits to the destination
+    #[serde(default)]
+    pub preserve_permissions: bool,
+    /// Copy modification times to the destination
+    #[serde(default)]
+    pub preserve_times: bool,
+    /// Compare SHA-256 of each uploaded or downloaded file
+    #[serde(default)]
+    pub verify: bool,
+}
+
+/// Entry that was skipped or failed during a directory transfer
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferIssue {
+    /// Path relative to the transfer root
+    pub path: String,
+    pub reason: String,
+}
+
+/// Outcome of a directory transfer
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DirectoryTransferSummary {
+    pub files_transferred: u32,
+    pub bytes_transferred: u64,
+    pub directories_created: u32,
+    pub skipped: Vec<TransferIssue>,
+    pub failed: Vec<TransferIssue>,
+}
+
+impl DirectoryTransferSummary {
+    /// Error describing the failed entries, if any
+    pub fn failure_message(&self) -> Option<String> {
+        self.failed.first().map(|first| {
+            format!("{} entries failed (first: {}: {})", self.failed.len(), first.path, first.reason)
+        })
+    }
+}
+
+/// Progress event emitted during a directory transfer
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryTransferProgress {
+    pub session_id: String,
+    pub direction: TransferDirection,
+    pub current_path: String,
+    pub files_done: u32,
+    pub files_total: u32,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub bytes_per_sec: u64,
+    pub done: bool,
+}
+
+/// File or directory found while scanning the source tree
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedEntry {
+    /// Path relative to the root, `/`-separated
+    pub relative: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub permissions: Option<u32>,
+    pub mtime: Option<u32>,
+}
+
+/// Result of scanning a source tree
+#[derive(Debug, Default)]
+pub(crate) struct ScanResult {
+    /// Entries in pre-order (a directory always comes before its children)
+    pub entries: Vec<ScannedEntry>,
+    pub skipped: Vec<TransferIssue>,
+}
+
+impl ScanResult {
+    fn files(&self) -> impl Iterator<Item = &ScannedEntry> {
+        self.entries.iter().filter(|e| !e.is_dir)
+    }
+}
+
+fn join_relative(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+/// Whether a relative path (from a remote listing or a sync plan) stays
+/// inside the directory it's joined to
+pub(crate) fn is_contained(relative: &str) -> bool {
+    !relative.is_empty() && Path::new(relative).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn skip(skipped: &mut Vec<TransferIssue>, relative: String, reason: &str) {
+    skipped.push(TransferIssue { path: relative, reason: reason.to_string() });
+}
+
+// ============================================================================
+// Scanning
+// ============================================================================
+
+/// Walk a local directory, applying the filter
+pub(crate) fn scan_local(root: &Path, filter: &PathFilter) -> Result<ScanResult, String> {
+    let mut result = ScanResult::default();
+    scan_local_dir(root, "", filter, &mut result)?;
+    Ok(result)
+}
+
+fn scan_local_dir(dir: &Path, relative: &str, filter: &PathFilter, result: &mut ScanResult) -> Result<(), String> {
+    let read_dir = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+
+    let mut children: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
+    children.sort_by_key(|e| e.file_name());
+
+    for child in children {
+        let name = child.file_name().to_string_lossy().to_string();
+        let child_relative = join_relative(relative, &name);
+        let meta = match std::fs::symlink_metadata(child.path()) {
+            Ok(meta) => meta,
+            Err(e) => {
+                skip(&mut result.skipped, child_relative, &format!("Failed to read metadata: {}", e));
+                continue;
+            }
+        };
+
+        if meta.file_type().is_symlink() {
+            skip(&mut result.skipped, child_relative, "Symbolic link");
+        } else if meta.is_dir() {
+            if filter.is_excluded(&child_relative) {
+                skip(&mut result.skipped, child_relative, "Excluded by filter");
+                continue;
+            }
+            result.entries.push(ScannedEntry {
+                relative: child_relative.clone(),
+                is_dir: true,
+                size: 0,
+                permissions: local_permissions(&meta),
+                mtime: local_mtime(&meta),
+            });
+            if let Err(e) = scan_local_dir(&child.path(), &child_relative, filter, result) {
+                skip(&mut result.skipped, child_relative, &e);
+            }
+        } else if meta.is_file() {
+            if !filter.includes_file(&child_relative) {
+                skip(&mut result.skipped, child_relative, "Excluded by filter");
+                continue;
+            }
+            result.entries.push(ScannedEntry {
+                relative: child_relative,
+                is_dir: false,
+                size: meta.len(),
+                permissions: local_permissions(&meta),
+                mtime: local_mtime(&meta),
+            });
+        } else {
+            skip(&mut result.skipped, child_relative, "Not a regular file");
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the raw attributes of every entry in a remote directory
+pub(crate) async fn read_remote_dir(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+) -> Result<Vec<(String, FileAttributes)>, String> {
+    if uses_scp(pool, session_id, config).await {
+        let mut result: Vec<(String, FileAttributes)> = scp_list_dir(config, path)
+            .await?
+            .into_iter()
+            .map(|entry| (entry.name.clone(), scp_entry_attrs(&entry)))
+            .collect();
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        return Ok(result);
+    }
+    let path = path.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let path = path.clone();
+        async move {
+            let entries = entry.sftp
+                .read_dir(&path)
+                .await
+                .map_err(|e| format!("Failed to read directory {}: {}", path, e))?;
+            let mut result: Vec<(String, FileAttributes)> = entries
+                .filter(|e| e.file_name() != "." && e.file_name() != "..")
+                .map(|e| (e.file_name(), e.metadata()))
+                .collect();
+            result.sort_by(|a, b| a.0.cmp(&b.0));
+            Ok(result)
+        }
+    })
+    .await
+}
+
+/// Attributes of an entry listed over SCP. Only directories and symlinks are
+/// told apart: anything else is taken for a regular file.
+fn scp_entry_attrs(entry: &FileEntry) -> FileAttributes {
+    let file_type = if entry.is_symlink {
+        0o120000
+    } else if entry.is_dir {
+        0o040000
+    } else {
+        0o100000
+    };
+    let mut attrs = FileAttributes::empty();
+    attrs.size = Some(entry.size);
+    attrs.permissions = Some(file_type | entry.mode.unwrap_or(0));
+    attrs.mtime = entry.modified.map(|t| t as u32);
+    attrs
+}
+
+/// Walk a remote directory, applying the filter
+pub(crate) async fn scan_remote(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    root: &str,
+    filter: &PathFilter,
+) -> Result<ScanResult, String> {
+    let mut result = ScanResult::default();
+    scan_remote_dir(pool, session_id, config, root, "", filter, &mut result).await?;
+    Ok(result)
+}
+
+async fn scan_remote_dir(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    dir: &str,
+    relative: &str,
+    filter: &PathFilter,
+    result: &mut ScanResult,
+) -> Result<(), String> {
+    for (name, attrs) in read_remote_dir(pool, session_id, config, dir).await? {
+        let child_relative = join_relative(relative, &name);
+
+        if attrs.is_symlink() {
+            skip(&mut result.skipped, child_relative, "Symbolic link");
+        } else if attrs.is_dir() {
+            if filter.is_excluded(&child_relative) {
+                skip(&mut result.skipped, child_relative, "Excluded by filter");
+                continue;
+            }
+            result.entries.push(ScannedEntry {
+                relative: child_relative.clone(),
+                is_dir: true,
+                size: 0,
+                permissions: attrs.permissions,
+                mtime: attrs.mtime,
+            });
+            let child_path = join_remote_path(dir, &name);
+            // Use Box::pin for recursive async
+            if let Err(e) = Box::pin(scan_remote_dir(
+                pool, session_id, config, &child_path, &child_relative, filter, result,
+            ))
+            .await
+            {
+                skip(&mut result.skipped, child_relative, &e);
+            }
+        } else if attrs.is_regular() {
+            if !filter.includes_file(&child_relative) {
+                skip(&mut result.skipped, child_relative, "Excluded by filter");
+                continue;
+            }
+            result.entries.push(ScannedEntry {
+                relative: child_relative,
+                is_dir: false,
+                size: attrs.size.unwrap_or(0),
+                permissions: attrs.permissions,
+                mtime: attrs.mtime,
+            });
+        } else {
+            skip(&mut result.skipped, child_relative, "Not a regular file");
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a remote path exists and is a directory
+pub async fn remote_is_dir(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+) -> Result<bool, String> {
+    if uses_scp(pool, session_id, config).await {
+        return Ok(scp_stat(config, path).await?.map_or(false, |stat| stat.is_dir));
+    }
+    let path = path.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let path = path.clone();
+        async move {
+            if !entry.sftp
+                .try_exists(&path)
+                .await
+                .map_err(|e| format!("Failed to stat {}: {}", path, e))?
+            {
+                return Ok(false);
+            }
+            let attrs = entry.sftp
+                .metadata(&path)
+                .await
+                .map_err(|e| format!("Failed to stat {}: {}", path, e))?;
+            Ok(attrs.is_dir())
+        }
+    })
+    .await
+}
+
+// ============================================================================
+// Attributes
+// ============================================================================
+
+#[cfg(unix)]
+fn local_permissions(meta: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn local_permissions(_meta: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+fn local_mtime(meta: &std::fs::Metadata) -> Option<u32> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as u32)
+}
+
+#[cfg(unix)]
+fn set_local_permissions(path: &Path, mode: u32) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o7777))
+        .map_err(|e| format!("Failed to set permissions: {}", e))
+}
+
+#[cfg(not(unix))]
+fn set_local_permissions(_path: &Path, _mode: u32) -> Result<(), String> {
+    Ok(())
+}
+
+fn set_local_mtime(path: &Path, mtime: u32) -> Result<(), String> {
+    let file = if path.is_dir() {
+        std::fs::File::open(path)
+    } else {
+        std::fs::OpenOptions::new().write(true).open(path)
+    }
+    .map_err(|e| format!("Failed to set modification time: {}", e))?;
+    file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(mtime as u64))
+        .map_err(|e| format!("Failed to set modification time: {}", e))
+}
+
+/// Apply the preserved attributes of `entry` to a local path
+pub(crate) fn apply_local_attrs(path: &Path, entry: &ScannedEntry, options: &DirectoryTransferOptions) -> Result<(), String> {
+    if options.preserve_permissions {
+        if let Some(mode) = entry.permissions {
+            set_local_permissions(path, mode)?;
+        }
+    }
+    if options.preserve_times {
+        if let Some(mtime) = entry.mtime {
+            set_local_mtime(path, mtime)?;
+        }
+    }
+    Ok(())
+}
+
+/// Apply the preserved attributes of `entry` to a remote path
+pub(crate) async fn apply_remote_attrs(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+    entry: &ScannedEntry,
+    options: &DirectoryTransferOptions,
+) -> Result<(), String> {
+    let mut attrs = FileAttributes::empty();
+    if options.preserve_permissions {
+        attrs.permissions = entry.permissions;
+    }
+    if options.preserve_times {
+        // SFTP sets access and modification times together
+        attrs.atime = entry.mtime;
+        attrs.mtime = entry.mtime;
+    }
+    if attrs.permissions.is_none() && attrs.mtime.is_none() {
+        return Ok(());
+    }
+    if uses_scp(pool, session_id, config).await {
+        return scp_set_attrs(config, path, attrs.permissions, attrs.atime, attrs.mtime).await;
+    }
+
+    let path = path.to_string();
+    with_sftp(pool, session_id, config, |e| {
+        let path = path.clone();
+        let attrs = attrs.clone();
+        async move {
+            e.sftp
+                .set_metadata(&path, attrs)
+                .await
+                .map_err(|e| format!("Failed to set attributes: {}", e))
+        }
+    })
+    .await
+}
+
+/// Create a remote directory unless it already exists
+pub(crate) async fn ensure_remote_dir(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    path: &str,
+) -> Result<bool, String> {
+    if remote_is_dir(pool, session_id, config, path).await? {
+        return Ok(false);
+    }
+    crate::connectors::sftp_mkdir(pool, session_id, config, path).await?;
+    Ok(true)
+}
+
+// ============================================================================
+// Transfers
+// ============================================================================
+
+struct ProgressState<'a> {
+    app: &'a AppHandle,
+    session_id: &'a str,
+    direction: TransferDirection,
+    files_done: u32,
+    files_total: u32,
+    bytes_done: u64,
+    bytes_total: u64,
+}
+
+impl ProgressState<'_> {
+    fn emit(&self, current_path: &str, file_bytes: u64, bytes_per_sec: u64, done: bool) {
+        let _ = self.app.emit("sftp-directory-progress", DirectoryTransferProgress {
+            session_id: self.session_id.to_string(),
+            direction: self.direction,
+            current_path: current_path.to_string(),
+            files_done: self.files_done,
+            files_total: self.files_total,
+            bytes_done: self.bytes_done + file_bytes,
+            bytes_total: self.bytes_total,
+            bytes_per_sec,
+            done,
+        });
+    }
+}
+
+/// Upload a local directory tree to `remote_root` (created if missing)
+pub async fn upload_directory(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    local_root: &Path,
+    remote_root: &str,
+    options: &DirectoryTransferOptions,
+    app: &AppHandle,
+) -> Result<DirectoryTransferSummary, String> {
+    let mut scan = scan_local(local_root, &options.filter)?;
+    let mut summary = DirectoryTransferSummary {
+        skipped: std::mem::take(&mut scan.skipped),
+        ..Default::default()
+    };
+    let mut progress = ProgressState {
+        app,
+        session_id,
+        direction: TransferDirection::Upload,
+        files_done: 0,
+        files_total: scan.files().count() as u32,
+        bytes_done: 0,
+        bytes_total: scan.files().map(|e| e.size).sum(),
+    };
+
+    if ensure_remote_dir(pool, session_id, config, remote_root).await? {
+        summary.directories_created += 1;
+    }
+
+    let control = TransferControl::new();
+    let mut failed_dirs: Vec<String> = Vec::new();
+
+    for entry in &scan.entries {
+        let remote_path = join_remote_path(remote_root, &entry.relative);
+
+        // Children of a directory that couldn't be created fail with it
+        if failed_dirs.iter().any(|d| entry.relative.starts_with(&format!("{}/", d))) {
+            continue;
+        }
+
+        if entry.is_dir {
+            match ensure_remote_dir(pool, session_id, config, &remote_path).await {
+                Ok(created) => {
+                    if created {
+                        summary.directories_created += 1;
+                    }
+                }
+                Err(e) => {
+                    failed_dirs.push(entry.relative.clone());
+                    summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e });
+                }
+            }
+            continue;
+        }
+
+        progress.emit(&entry.relative, 0, 0, false);
+        let local_path = local_root.join(&entry.relative);
+        let result = upload_to_remote(
+            pool, session_id, config, UploadSource::Path(local_path), &remote_path, 0, options.verify, &control,
+            |tracker| progress.emit(&entry.relative, tracker.transferred(), tracker.bytes_per_sec(), false),
+        )
+        .await;
+
+        match result {
+            Ok(bytes) => {
+                summary.files_transferred += 1;
+                summary.bytes_transferred += bytes;
+                if let Err(e) = apply_remote_attrs(pool, session_id, config, &remote_path, entry, options).await {
+                    summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e });
+                }
+            }
+            Err(e) => summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e }),
+        }
+        progress.files_done += 1;
+        progress.bytes_done += entry.size;
+    }
+
+    // Directory times last, since writing files into them updates their mtime
+    for entry in scan.entries.iter().rev().filter(|e| e.is_dir) {
+        if failed_dirs.contains(&entry.relative) {
+            continue;
+        }
+        let remote_path = join_remote_path(remote_root, &entry.relative);
+        let _ = apply_remote_attrs(pool, session_id, config, &remote_path, entry, options).await;
+    }
+
+    progress.emit("", 0, 0, true);
+    Ok(summary)
+}
+
+/// Download a remote directory tree to `local_root` (created if missing)
+pub async fn download_directory(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_root: &str,
+    local_root: &Path,
+    options: &DirectoryTransferOptions,
+    app: &AppHandle,
+) -> Result<DirectoryTransferSummary, String> {
+    let mut scan = scan_remote(pool, session_id, config, remote_root, &options.filter).await?;
+    let mut summary = DirectoryTransferSummary {
+        skipped: std::mem::take(&mut scan.skipped),
+        ..Default::default()
+    };
+    let mut progress = ProgressState {
+        app,
+        session_id,
+        direction: TransferDirection::Download,
+        files_done: 0,
+        files_total: scan.files().count() as u32,
+        bytes_done: 0,
+        bytes_total: scan.files().map(|e| e.size).sum(),
+    };
+
+    if !local_root.is_dir() {
+        std::fs::create_dir_all(local_root)
+            .map_err(|e| format!("Failed to create local directory: {}", e))?;
+        summary.directories_created += 1;
+    }
+
+    let control = TransferControl::new();
+    let mut failed_dirs: Vec<String> = Vec::new();
+
+    for entry in &scan.entries {
+        if failed_dirs.iter().any(|d| entry.relative.starts_with(&format!("{}/", d))) {
+            continue;
+        }
+        // A hostile server could name entries to escape `local_root`
+        if !is_contained(&entry.relative) {
+            if entry.is_dir {
+                failed_dirs.push(entry.relative.clone());
+            } else {
+                progress.files_done += 1;
+                progress.bytes_done += entry.size;
+            }
+            skip(&mut summary.skipped, entry.relative.clone(), "Unsafe path");
+            continue;
+        }
+        let local_path = local_root.join(&entry.relative);
+
+        if entry.is_dir {
+            if local_path.is_dir() {
+                continue;
+            }
+            match std::fs::create_dir(&local_path) {
+                Ok(()) => summary.directories_created += 1,
+                Err(e) => {
+                    failed_dirs.push(entry.relative.clone());
+                    summary.failed.push(TransferIssue {
+                        path: entry.relative.clone(),
+                        reason: format!("Failed to create local directory: {}", e),
+                    });
+                }
+            }
+            continue;
+        }
+
+        progress.emit(&entry.relative, 0, 0, false);
+        let remote_path = join_remote_path(remote_root, &entry.relative);
+        let result = download_to_local(
+            pool, session_id, config, &remote_path, &local_path, 0, options.verify, &control,
+            |tracker| progress.emit(&entry.relative, tracker.transferred(), tracker.bytes_per_sec(), false),
+        )
+        .await;
+
+        match result {
+            Ok(bytes) => {
+                summary.files_transferred += 1;
+                summary.bytes_transferred += bytes;
+                if let Err(e) = apply_local_attrs(&local_path, entry, options) {
+                    summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e });
+                }
+            }
+            Err(e) => {
+                // Don't leave a truncated file behind in a recursive download
+                let _ = std::fs::remove_file(&local_path);
+                summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e });
+            }
+        }
+        progress.files_done += 1;
+        progress.bytes_done += entry.size;
+    }
+
+    for entry in scan.entries.iter().rev().filter(|e| e.is_dir) {
+        if failed_dirs.contains(&entry.relative) {
+            continue;
+        }
+        let _ = apply_local_attrs(&local_root.join(&entry.relative), entry, options);
+    }
+
+    progress.emit("", 0, 0, true);
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_paths_leaving_the_root() {
+        assert!(is_contained("a"));
+        assert!(is_contained("a/b/c.txt"));
+        assert!(!is_contained(""));
+        assert!(!is_contained(".."));
+        assert!(!is_contained("a/../../b"));
+        assert!(!is_contained("/etc/passwd"));
+        assert!(!is_contained("./a"));
+    }
+}
@@ -0,0 +1,239 @@
+//! Remote-to-remote copies between two sessions (or within one)
+//!
+//! Data is streamed from the source session's SFTP connection straight to the
+//! destination's, without touching local disk. Each copied file can be
+//! verified by comparing SHA-256 checksums of both sides.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::control::TransferControl;
+use super::progress::ProgressTracker;
+use super::resume::remote_file_size;
+use super::recursive::{
+    apply_remote_attrs, ensure_remote_dir, remote_is_dir, scan_remote, DirectoryTransferOptions,
+    DirectoryTransferSummary, ScannedEntry, TransferIssue,
+};
+use super::integrity::verify_remote;
+use super::stream::{check_copy_target, stream_remote_to_remote, RemoteEndpoint};
+use crate::connectors::sftp::join_remote_path;
+use crate::connectors::SftpPool;
+
+/// Options for a remote-to-remote copy
+#[derive(Debug, Clone)]
+pub struct RemoteCopyOptions {
+    /// Filters, attribute preservation (directories) and verification.
+    /// Unlike uploads and downloads, copies are verified unless `verify` is false.
+    pub directory: DirectoryTransferOptions,
+}
+
+impl Default for RemoteCopyOptions {
+    fn default() -> Self {
+        Self {
+            directory: DirectoryTransferOptions {
+                verify: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RemoteCopyOptions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.entry("verify").or_insert(serde_json::Value::Bool(true));
+        }
+        let directory = serde_json::from_value(value).map_err(serde::de::Error::custom)?;
+        Ok(Self { directory })
+    }
+}
+
+/// Progress event emitted during a remote-to-remote copy
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteCopyProgress {
+    pub source_session_id: String,
+    pub dest_session_id: String,
+    pub current_path: String,
+    pub files_done: u32,
+    pub files_total: u32,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub bytes_per_sec: u64,
+    /// Checksums are being compared for `current_path`
+    pub verifying: bool,
+    pub done: bool,
+}
+
+/// Copy one file and optionally verify it
+async fn copy_file<F>(
+    pool: &SftpPool,
+    source: RemoteEndpoint<'_>,
+    dest: RemoteEndpoint<'_>,
+    verify: bool,
+    control: &TransferControl,
+    on_progress: F,
+    on_verify: impl FnOnce(),
+) -> Result<u64, String>
+where
+    F: FnMut(&ProgressTracker) + Send,
+{
+    let (bytes, source_hash) = stream_remote_to_remote(pool, source, dest, 0, control, on_progress).await?;
+
+    if verify {
+        on_verify();
+        verify_remote(pool, dest.session_id, dest.config, dest.path, &source_hash).await?;
+    }
+
+    Ok(bytes)
+}
+
+/// Copy a file or directory tree from `source.path` to `dest.path`
+pub async fn copy_remote(
+    pool: &SftpPool,
+    source: RemoteEndpoint<'_>,
+    dest: RemoteEndpoint<'_>,
+    options: &RemoteCopyOptions,
+    app: &AppHandle,
+) -> Result<DirectoryTransferSummary, String> {
+    check_copy_target(&source, &dest)?;
+    let is_dir = remote_is_dir(pool, source.session_id, source.config, source.path).await?;
+
+    let mut summary = DirectoryTransferSummary::default();
+    let (entries, files_total, bytes_total) = if is_dir {
+        let mut scan = scan_remote(pool, source.session_id, source.config, source.path, &options.directory.filter).await?;
+        summary.skipped = std::mem::take(&mut scan.skipped);
+        let files_total = scan.entries.iter().filter(|e| !e.is_dir).count() as u32;
+        let bytes_total = scan.entries.iter().map(|e| e.size).sum();
+        (scan.entries, files_total, bytes_total)
+    } else {
+        let size = remote_file_size(pool, source.session_id, source.config, source.path)
+            .await?
+            .ok_or_else(|| format!("Remote file not found: {}", source.path))?;
+        (Vec::new(), 1, size)
+    };
+    let mut files_done = 0;
+    let mut bytes_done = 0;
+
+    let emit = |current_path: &str, files_done: u32, bytes_done: u64, bytes_per_sec: u64, verifying: bool, done: bool| {
+        let _ = app.emit("sftp-remote-copy-progress", RemoteCopyProgress {
+            source_session_id: source.session_id.to_string(),
+            dest_session_id: dest.session_id.to_string(),
+            current_path: current_path.to_string(),
+            files_done,
+            files_total,
+            bytes_done,
+            bytes_total,
+            bytes_per_sec,
+            verifying,
+            done,
+        });
+    };
+    let control = TransferControl::new();
+
+    if !is_dir {
+        let result = copy_file(
+            pool,
+            source,
+            dest,
+            options.directory.verify,
+            &control,
+            |t| emit(source.path, 0, t.transferred(), t.bytes_per_sec(), false, false),
+            || emit(source.path, 0, bytes_total, 0, true, false),
+        )
+        .await;
+        emit(source.path, 1, bytes_total, 0, false, true);
+        let bytes = result?;
+        summary.files_transferred = 1;
+        summary.bytes_transferred = bytes;
+        return Ok(summary);
+    }
+
+    if ensure_remote_dir(pool, dest.session_id, dest.config, dest.path).await? {
+        summary.directories_created += 1;
+    }
+
+    let mut failed_dirs: Vec<String> = Vec::new();
+    for entry in &entries {
+        if failed_dirs.iter().any(|d| entry.relative.starts_with(&format!("{}/", d))) {
+            continue;
+        }
+        let src_path = join_remote_path(source.path, &entry.relative);
+        let dst_path = join_remote_path(dest.path, &entry.relative);
+
+        if entry.is_dir {
+            match ensure_remote_dir(pool, dest.session_id, dest.config, &dst_path).await {
+                Ok(true) => summary.directories_created += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    failed_dirs.push(entry.relative.clone());
+                    summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e });
+                }
+            }
+            continue;
+        }
+
+        emit(&entry.relative, files_done, bytes_done, 0, false, false);
+        let result = copy_file(
+            pool,
+            RemoteEndpoint { path: &src_path, ..source },
+            RemoteEndpoint { path: &dst_path, ..dest },
+            options.directory.verify,
+            &control,
+            |t| emit(&entry.relative, files_done, bytes_done + t.transferred(), t.bytes_per_sec(), false, false),
+            || emit(&entry.relative, files_done, bytes_done + entry.size, 0, true, false),
+        )
+        .await;
+
+        match result {
+            Ok(bytes) => {
+                summary.files_transferred += 1;
+                summary.bytes_transferred += bytes;
+                if let Err(e) = apply_dest_attrs(pool, &dest, &dst_path, entry, options).await {
+                    summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e });
+                }
+            }
+            Err(e) => summary.failed.push(TransferIssue { path: entry.relative.clone(), reason: e }),
+        }
+        files_done += 1;
+        bytes_done += entry.size;
+    }
+
+    // Directory times last, since copying files into them updates their mtime
+    for entry in entries.iter().rev().filter(|e| e.is_dir) {
+        if !failed_dirs.contains(&entry.relative) {
+            let dst_path = join_remote_path(dest.path, &entry.relative);
+            let _ = apply_dest_attrs(pool, &dest, &dst_path, entry, options).await;
+        }
+    }
+
+    emit("", files_done, bytes_done, 0, false, true);
+    Ok(summary)
+}
+
+async fn apply_dest_attrs(
+    pool: &SftpPool,
+    dest: &RemoteEndpoint<'_>,
+    path: &str,
+    entry: &ScannedEntry,
+    options: &RemoteCopyOptions,
+) -> Result<(), String> {
+    apply_remote_attrs(pool, dest.session_id, dest.config, path, entry, &options.directory).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_copy_verifies_by_default() {
+        let options: RemoteCopyOptions = serde_json::from_str("{}").unwrap();
+        assert!(options.directory.verify);
+        assert!(RemoteCopyOptions::default().directory.verify);
+
+        let options: RemoteCopyOptions =
+            serde_json::from_str(r#"{"verify": false, "preservePermissions": true}"#).unwrap();
+        assert!(!options.directory.verify);
+        assert!(options.directory.preserve_permissions);
+    }
+}
@@ -0,0 +1,220 @@
+//! Resume support for interrupted transfers
+//!
+//! Works out where a transfer can pick up from by comparing the partial file
+//! on the destination with the source. Optionally checks that the tail of the
+//! already-transferred bytes matches before trusting it.
+
+use russh_sftp::protocol::OpenFlags;
+use std::io::SeekFrom;
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use super::stream::{open_remote, RemoteEndpoint};
+use crate::connectors::sftp::{uses_scp, with_sftp};
+use crate::connectors::{SftpPool, SshConfig};
+
+/// Number of bytes compared at the end of the overlapping prefix
+const VERIFY_WINDOW: u64 = 1024 * 1024;
+
+/// Size of a remote file, or None if it doesn't exist
+pub(crate) async fn remote_file_size(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+) -> Result<Option<u64>, String> {
+    let remote_path = remote_path.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let remote_path = remote_path.clone();
+        async move {
+            if !entry.sftp
+                .try_exists(&remote_path)
+                .await
+                .map_err(|e| format!("Failed to stat remote file: {}", e))?
+            {
+                return Ok(None);
+            }
+            let attrs = entry.sftp
+                .metadata(&remote_path)
+                .await
+                .map_err(|e| format!("Failed to stat remote file: {}", e))?;
+            Ok(Some(attrs.size.unwrap_or(0)))
+        }
+    })
+    .await
+}
+
+/// Compare the last `VERIFY_WINDOW` bytes before `offset` on both sides
+async fn overlap_matches(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    local_path: &Path,
+    offset: u64,
+) -> Result<bool, String> {
+    let mut remote = open_remote(pool, session_id, config, remote_path, OpenFlags::READ).await?;
+    let mut local = tokio::fs::File::open(local_path)
+        .await
+        .map_err(|e| format!("Failed to open local file: {}", e))?;
+    windows_match(&mut remote, &mut local, offset).await
+}
+
+/// Whether both streams hold the same bytes in the window before `offset`
+async fn windows_match<A, B>(a: &mut A, b: &mut B, offset: u64) -> Result<bool, String>
+where
+    A: AsyncRead + AsyncSeek + Unpin,
+    B: AsyncRead + AsyncSeek + Unpin,
+{
+    Ok(read_window(a, offset).await? == read_window(b, offset).await?)
+}
+
+/// The last `VERIFY_WINDOW` bytes before `offset`
+async fn read_window<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R, offset: u64) -> Result<Vec<u8>, String> {
+    let window = VERIFY_WINDOW.min(offset);
+    let mut buf = vec![0u8; window as usize];
+    reader
+        .seek(SeekFrom::Start(offset - window))
+        .await
+        .map_err(|e| format!("Failed to seek file: {}", e))?;
+    reader
+        .read_exact(&mut buf)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(buf)
+}
+
+/// Upload offset for the given sizes: the partial remote file, if it isn't
+/// larger than the local one
+fn upload_offset(local_size: u64, remote_size: Option<u64>) -> u64 {
+    match remote_size {
+        Some(size) if size > 0 && size <= local_size => size,
+        _ => 0,
+    }
+}
+
+/// Download offset for the given sizes: the partial local file, if it isn't
+/// larger than the remote one
+fn download_offset(local_size: u64, remote_size: u64) -> u64 {
+    if local_size == 0 || local_size > remote_size {
+        0
+    } else {
+        local_size
+    }
+}
+
+/// Offset an upload can resume from (0 means start over)
+pub async fn upload_resume_offset(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    local_path: &Path,
+    remote_path: &str,
+    verify: bool,
+) -> Result<u64, String> {
+    // SCP always sends whole files
+    if uses_scp(pool, session_id, config).await {
+        return Ok(0);
+    }
+    let local_size = std::fs::metadata(local_path)
+        .map_err(|e| format!("Failed to read local metadata: {}", e))?
+        .len();
+
+    let remote_size = remote_file_size(pool, session_id, config, remote_path).await?;
+    let remote_size = match upload_offset(local_size, remote_size) {
+        0 => return Ok(0),
+        size => size,
+    };
+
+    if verify && !overlap_matches(pool, session_id, config, remote_path, local_path, remote_size).await? {
+        return Ok(0);
+    }
+
+    Ok(remote_size)
+}
+
+/// Offset a download can resume from (0 means start over)
+pub async fn download_resume_offset(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    local_path: &Path,
+    verify: bool,
+) -> Result<u64, String> {
+    if uses_scp(pool, session_id, config).await {
+        return Ok(0);
+    }
+    let local_size = match std::fs::metadata(local_path) {
+        Ok(meta) if meta.is_file() => meta.len(),
+        _ => return Ok(0),
+    };
+
+    let remote_size = remote_file_size(pool, session_id, config, remote_path)
+        .await?
+        .ok_or_else(|| format!("Remote file not found: {}", remote_path))?;
+
+    let offset = download_offset(local_size, remote_size);
+    if offset == 0 {
+        return Ok(0);
+    }
+
+    if verify && !overlap_matches(pool, session_id, config, remote_path, local_path, offset).await? {
+        return Ok(0);
+    }
+
+    Ok(offset)
+}
+
+/// Offset a remote-to-remote copy can resume from (0 means start over)
+pub async fn copy_resume_offset(
+    pool: &SftpPool,
+    source: &RemoteEndpoint<'_>,
+    dest: &RemoteEndpoint<'_>,
+) -> Result<u64, String> {
+    let source_size = remote_file_size(pool, source.session_id, source.config, source.path)
+        .await?
+        .ok_or_else(|| format!("Remote file not found: {}", source.path))?;
+
+    let dest_size = remote_file_size(pool, dest.session_id, dest.config, dest.path).await?;
+    Ok(upload_offset(source_size, dest_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_offsets_only_trust_smaller_partial_files() {
+        assert_eq!(upload_offset(100, Some(40)), 40);
+        assert_eq!(upload_offset(100, Some(100)), 100);
+        assert_eq!(upload_offset(100, Some(120)), 0);
+        assert_eq!(upload_offset(100, Some(0)), 0);
+        assert_eq!(upload_offset(100, None), 0);
+
+        assert_eq!(download_offset(40, 100), 40);
+        assert_eq!(download_offset(0, 100), 0);
+        assert_eq!(download_offset(120, 100), 0);
+    }
+
+    #[tokio::test]
+    async fn test_overlap_compares_the_window_before_the_offset() {
+        let source: Vec<u8> = (0..3 * VERIFY_WINDOW).map(|i| (i % 251) as u8).collect();
+        let offset = 2 * VERIFY_WINDOW;
+
+        let mut partial = source[..offset as usize].to_vec();
+        assert!(windows_match(&mut Cursor::new(&source), &mut Cursor::new(&partial), offset).await.unwrap());
+
+        // A difference before the window isn't noticed, one inside it is
+        partial[0] ^= 0xff;
+        assert!(windows_match(&mut Cursor::new(&source), &mut Cursor::new(&partial), offset).await.unwrap());
+        let last = partial.len() - 1;
+        partial[last] ^= 0xff;
+        assert!(!windows_match(&mut Cursor::new(&source), &mut Cursor::new(&partial), offset).await.unwrap());
+
+        // Short offsets compare everything before them
+        assert!(windows_match(&mut Cursor::new(&source), &mut Cursor::new(&source[..10]), 10).await.unwrap());
+        assert!(windows_match(&mut Cursor::new(&source), &mut Cursor::new(&partial), offset + 1).await.is_err());
+    }
+}
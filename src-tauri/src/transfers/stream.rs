@@ -0,0 +1,711 @@
+//! Chunked SFTP uploads and downloads
+//!
+//! Data is streamed between local disk (or an in-memory buffer) and the remote
+//! file handle in fixed-size chunks, so memory usage stays flat regardless of
+//! file size. Progress events are throttled by `ProgressTracker`.
+//! Sessions without SFTP go through SCP (`connectors::scp`) instead.
+
+use russh_sftp::client::fs::File as SftpFile;
+use russh_sftp::protocol::OpenFlags;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use super::control::TransferControl;
+use super::integrity::{local_sha256, remote_sha256, sha256_reader, verify_remote, HashingStream, CHECKSUM_MISMATCH};
+use super::progress::ProgressTracker;
+use super::resume::{download_resume_offset, upload_resume_offset};
+use crate::connectors::scp::{scp_download, scp_upload};
+use crate::connectors::sftp::{uses_scp, with_sftp};
+use crate::connectors::{SftpPool, SshConfig};
+use crate::storage::transfers::{
+    clear_interrupted_transfer, record_interrupted_transfer, InterruptedTransfer, TransferDirection,
+};
+
+/// Size of each read/write chunk
+pub const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Progress event emitted during upload
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SftpUploadProgress {
+    pub session_id: String,
+    pub file_name: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    pub bytes_per_sec: u64,
+    pub eta_secs: Option<u64>,
+    pub file_index: u32,
+    pub total_files: u32,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// Progress event emitted during download
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SftpDownloadProgress {
+    pub session_id: String,
+    pub remote_path: String,
+    pub local_path: String,
+    pub bytes_received: u64,
+    pub total_bytes: u64,
+    pub bytes_per_sec: u64,
+    pub eta_secs: Option<u64>,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// Options shared by uploads and downloads
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferOptions {
+    /// Continue from an existing partial file instead of starting over
+    #[serde(default)]
+    pub resume: bool,
+    /// Compare the bytes already transferred before resuming
+    #[serde(default)]
+    pub verify_resume: bool,
+    /// Compare SHA-256 of both sides once the transfer completes
+    #[serde(default)]
+    pub verify: bool,
+}
+
+/// Where the data of an upload comes from
+pub enum UploadSource {
+    /// Data already in memory (e.g. sent by the frontend)
+    Bytes(Vec<u8>),
+    /// File on local disk, streamed chunk by chunk
+    Path(PathBuf),
+}
+
+/// Copy everything from `reader` to `writer`, reporting progress along the way.
+/// `control` is checked between chunks so the copy can be paused or cancelled.
+pub(crate) async fn copy_with_progress<R, W, F>(
+    reader: &mut R,
+    writer: &mut W,
+    tracker: &mut ProgressTracker,
+    control: &TransferControl,
+    on_progress: &mut F,
+) -> Result<(), String>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&ProgressTracker),
+{
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        control.checkpoint().await?;
+
+        let n = reader
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("Failed to read data: {}", e))?;
+        if n == 0 {
+            break;
+        }
+
+        writer
+            .write_all(&buf[..n])
+            .await
+            .map_err(|e| format!("Failed to write data: {}", e))?;
+
+        if tracker.advance(n as u64) {
+            on_progress(tracker);
+        }
+    }
+
+    writer
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to flush: {}", e))
+}
+
+/// Open a remote file through the pool (retries once on a stale connection)
+pub(crate) async fn open_remote(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    flags: OpenFlags,
+) -> Result<SftpFile, String> {
+    let remote_path = remote_path.to_string();
+    with_sftp(pool, session_id, config, |entry| {
+        let remote_path = remote_path.clone();
+        async move {
+            entry
+                .bulk()
+                .await
+                .open_with_flags(&remote_path, flags)
+                .await
+                .map_err(|e| format!("Failed to open remote file: {}", e))
+        }
+    })
+    .await
+}
+
+/// Stream a local file or buffer to a remote path, starting at `offset`.
+/// Returns the total size of the remote file once written.
+///
+/// With `verify`, the data is hashed as it is read and compared with the
+/// remote file afterwards (resumed uploads hash the whole local file instead).
+pub async fn upload_to_remote<F>(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    source: UploadSource,
+    remote_path: &str,
+    offset: u64,
+    verify: bool,
+    control: &TransferControl,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(&ProgressTracker) + Send,
+{
+    if uses_scp(pool, session_id, config).await {
+        return upload_via_scp(pool, session_id, config, source, remote_path, verify, control, on_progress).await;
+    }
+
+    let flags = if offset > 0 {
+        OpenFlags::CREATE | OpenFlags::WRITE
+    } else {
+        OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE
+    };
+    let mut remote = open_remote(pool, session_id, config, remote_path, flags).await?;
+
+    if offset > 0 {
+        remote
+            .seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("Failed to seek remote file: {}", e))?;
+    }
+
+    let (transferred, local_hash) = match source {
+        UploadSource::Bytes(data) => {
+            let start = (offset as usize).min(data.len());
+            let mut tracker = ProgressTracker::resumed(data.len() as u64, start as u64);
+            let mut reader = HashingStream::new(&data[start..]);
+            copy_with_progress(&mut reader, &mut remote, &mut tracker, control, &mut on_progress).await?;
+            close_remote(&mut remote).await?;
+            let local_hash = match (verify, start) {
+                (false, _) => None,
+                (true, 0) => Some(reader.finish()),
+                (true, _) => Some(sha256_reader(&mut &data[..]).await?),
+            };
+            (tracker.transferred(), local_hash)
+        }
+        UploadSource::Path(local_path) => {
+            let mut local = tokio::fs::File::open(&local_path)
+                .await
+                .map_err(|e| format!("Failed to open local file: {}", e))?;
+            let total = local
+                .metadata()
+                .await
+                .map_err(|e| format!("Failed to read local metadata: {}", e))?
+                .len();
+            if offset > 0 {
+                local
+                    .seek(SeekFrom::Start(offset))
+                    .await
+                    .map_err(|e| format!("Failed to seek local file: {}", e))?;
+            }
+            let mut tracker = ProgressTracker::resumed(total, offset);
+            let mut reader = HashingStream::new(local);
+            copy_with_progress(&mut reader, &mut remote, &mut tracker, control, &mut on_progress).await?;
+            close_remote(&mut remote).await?;
+            let local_hash = match (verify, offset) {
+                (false, _) => None,
+                (true, 0) => Some(reader.finish()),
+                // Only the tail was streamed
+                (true, _) => Some(local_sha256(&local_path).await?),
+            };
+            (tracker.transferred(), local_hash)
+        }
+    };
+
+    if let Some(local_hash) = local_hash {
+        verify_remote(pool, session_id, config, remote_path, &local_hash).await?;
+    }
+    Ok(transferred)
+}
+
+/// Upload over SCP for servers without SFTP (no resume: the whole file is sent)
+async fn upload_via_scp<F>(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    source: UploadSource,
+    remote_path: &str,
+    verify: bool,
+    control: &TransferControl,
+    on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(&ProgressTracker) + Send,
+{
+    let (transferred, local_hash) = match source {
+        UploadSource::Bytes(data) => {
+            let mut reader = HashingStream::new(&data[..]);
+            let sent = scp_upload(config, remote_path, &mut reader, data.len() as u64, 0o644, control, on_progress).await?;
+            (sent, reader.finish())
+        }
+        UploadSource::Path(local_path) => {
+            let local = tokio::fs::File::open(&local_path)
+                .await
+                .map_err(|e| format!("Failed to open local file: {}", e))?;
+            let metadata = local
+                .metadata()
+                .await
+                .map_err(|e| format!("Failed to read local metadata: {}", e))?;
+            #[cfg(unix)]
+            let mode = {
+                use std::os::unix::fs::PermissionsExt;
+                metadata.permissions().mode()
+            };
+            #[cfg(not(unix))]
+            let mode = 0o644;
+
+            let mut reader = HashingStream::new(local);
+            let sent = scp_upload(config, remote_path, &mut reader, metadata.len(), mode, control, on_progress).await?;
+            (sent, reader.finish())
+        }
+    };
+
+    if verify {
+        verify_remote(pool, session_id, config, remote_path, &local_hash).await?;
+    }
+    Ok(transferred)
+}
+
+/// Stream a remote file to a local path, starting at `offset`.
+/// Returns the total size of the local file once written.
+///
+/// On failure the partial local file is kept so the download can be resumed.
+/// With `verify`, the written data is hashed and compared with the remote file.
+pub async fn download_to_local<F>(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    local_path: &Path,
+    offset: u64,
+    verify: bool,
+    control: &TransferControl,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(&ProgressTracker) + Send,
+{
+    // SCP (server without SFTP): no resume, the whole file is received
+    if uses_scp(pool, session_id, config).await {
+        let local = tokio::fs::File::create(local_path)
+            .await
+            .map_err(|e| format!("Failed to create local file: {}", e))?;
+        let mut writer = HashingStream::new(local);
+        let received = scp_download(config, remote_path, &mut writer, control, on_progress).await?;
+        if verify {
+            verify_remote(pool, session_id, config, remote_path, &writer.finish()).await?;
+        }
+        return Ok(received);
+    }
+
+    let mut remote = open_remote(pool, session_id, config, remote_path, OpenFlags::READ).await?;
+    let total = remote
+        .metadata()
+        .await
+        .map_err(|e| format!("Failed to read remote metadata: {}", e))?
+        .size
+        .unwrap_or(0);
+
+    let local = if offset > 0 {
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(local_path)
+            .await
+            .map_err(|e| format!("Failed to open local file: {}", e))?;
+        file.set_len(offset)
+            .await
+            .map_err(|e| format!("Failed to truncate local file: {}", e))?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("Failed to seek local file: {}", e))?;
+        remote
+            .seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("Failed to seek remote file: {}", e))?;
+        file
+    } else {
+        tokio::fs::File::create(local_path)
+            .await
+            .map_err(|e| format!("Failed to create local file: {}", e))?
+    };
+
+    let mut tracker = ProgressTracker::resumed(total, offset);
+    let mut writer = HashingStream::new(local);
+    let result = copy_with_progress(&mut remote, &mut writer, &mut tracker, control, &mut on_progress).await;
+    let _ = remote.shutdown().await;
+    result?;
+
+    if verify {
+        let local_hash = if offset == 0 { writer.finish() } else { local_sha256(local_path).await? };
+        verify_remote(pool, session_id, config, remote_path, &local_hash).await?;
+    }
+    Ok(tracker.transferred())
+}
+
+/// Endpoint of a remote-to-remote copy
+#[derive(Clone, Copy)]
+pub struct RemoteEndpoint<'a> {
+    pub session_id: &'a str,
+    pub config: &'a SshConfig,
+    pub path: &'a str,
+}
+
+/// Refuse copying a path onto itself or into itself within a session, which
+/// would truncate the source or never finish
+pub(crate) fn check_copy_target(source: &RemoteEndpoint<'_>, dest: &RemoteEndpoint<'_>) -> Result<(), String> {
+    if source.session_id != dest.session_id {
+        return Ok(());
+    }
+    let source_parts = normalize_remote_path(source.path);
+    let dest_parts = normalize_remote_path(dest.path);
+    if dest_parts.starts_with(&source_parts) {
+        return Err(format!("Cannot copy {} into itself", source.path));
+    }
+    Ok(())
+}
+
+/// Components of a remote path with `.`, `..` and repeated slashes resolved
+fn normalize_remote_path(path: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts
+}
+
+/// Stream a file from one session to another (or within the same session)
+/// without touching local disk, starting at `offset`.
+/// Returns the total size of the destination file once written.
+///
+/// With `verify`, the data is hashed as it is read and compared with the
+/// destination file afterwards (resumed copies hash the source on its server).
+pub async fn copy_remote_to_remote<F>(
+    pool: &SftpPool,
+    source: RemoteEndpoint<'_>,
+    dest: RemoteEndpoint<'_>,
+    offset: u64,
+    verify: bool,
+    control: &TransferControl,
+    on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(&ProgressTracker) + Send,
+{
+    let (transferred, streamed_hash) = stream_remote_to_remote(pool, source, dest, offset, control, on_progress).await?;
+
+    if verify {
+        let source_hash = match offset {
+            0 => streamed_hash,
+            // Only the tail was streamed
+            _ => remote_sha256(pool, source.session_id, source.config, source.path).await?,
+        };
+        verify_remote(pool, dest.session_id, dest.config, dest.path, &source_hash).await?;
+    }
+    Ok(transferred)
+}
+
+/// Copy without verifying. Returns the size of the destination file and the
+/// SHA-256 of the data streamed (the whole file when `offset` is 0).
+pub(crate) async fn stream_remote_to_remote<F>(
+    pool: &SftpPool,
+    source: RemoteEndpoint<'_>,
+    dest: RemoteEndpoint<'_>,
+    offset: u64,
+    control: &TransferControl,
+    mut on_progress: F,
+) -> Result<(u64, String), String>
+where
+    F: FnMut(&ProgressTracker) + Send,
+{
+    check_copy_target(&source, &dest)?;
+    let mut src = open_remote(pool, source.session_id, source.config, source.path, OpenFlags::READ).await?;
+    let total = src
+        .metadata()
+        .await
+        .map_err(|e| format!("Failed to read source metadata: {}", e))?
+        .size
+        .unwrap_or(0);
+
+    let flags = if offset > 0 {
+        OpenFlags::CREATE | OpenFlags::WRITE
+    } else {
+        OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE
+    };
+    let mut dst = open_remote(pool, dest.session_id, dest.config, dest.path, flags).await?;
+
+    if offset > 0 {
+        src.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("Failed to seek source file: {}", e))?;
+        dst.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("Failed to seek destination file: {}", e))?;
+    }
+
+    let mut tracker = ProgressTracker::resumed(total, offset);
+    let mut reader = HashingStream::new(src);
+    let result = copy_with_progress(&mut reader, &mut dst, &mut tracker, control, &mut on_progress).await;
+    let (mut src, source_hash) = reader.into_parts();
+    let _ = src.shutdown().await;
+    result?;
+    close_remote(&mut dst).await?;
+
+    Ok((tracker.transferred(), source_hash))
+}
+
+/// Close a remote file handle, surfacing errors from the final write
+async fn close_remote(remote: &mut SftpFile) -> Result<(), String> {
+    remote
+        .shutdown()
+        .await
+        .map_err(|e| format!("Failed to close file: {}", e))
+}
+
+/// Upload a file via SFTP (uses pool), emitting `sftp-upload-progress` events.
+///
+/// Failed uploads from a local path are recorded so they can be resumed later.
+pub async fn sftp_upload_file(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    source: UploadSource,
+    file_name: String,
+    file_index: u32,
+    total_files: u32,
+    options: TransferOptions,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    let total_bytes = match &source {
+        UploadSource::Bytes(data) => data.len() as u64,
+        UploadSource::Path(path) => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    };
+    let local_path = match &source {
+        UploadSource::Path(path) => Some(path.to_string_lossy().to_string()),
+        UploadSource::Bytes(_) => None,
+    };
+
+    let progress = |bytes_sent: u64, bytes_per_sec: u64, eta_secs: Option<u64>, done: bool, error: Option<String>| {
+        SftpUploadProgress {
+            session_id: session_id.to_string(),
+            file_name: file_name.clone(),
+            bytes_sent,
+            total_bytes,
+            bytes_per_sec,
+            eta_secs,
+            file_index,
+            total_files,
+            done,
+            error,
+        }
+    };
+
+    let offset = match &source {
+        UploadSource::Path(path) if options.resume => {
+            upload_resume_offset(pool, session_id, config, path, remote_path, options.verify_resume).await?
+        }
+        _ => 0,
+    };
+
+    // Emit start progress
+    let _ = app_handle.emit("sftp-upload-progress", progress(offset, 0, None, false, None));
+
+    let control = TransferControl::new();
+    let mut last_sent = offset;
+    // Set once data was written (the first chunk always reports progress)
+    let mut started = false;
+    let result = upload_to_remote(pool, session_id, config, source, remote_path, offset, options.verify, &control, |tracker| {
+        started = true;
+        last_sent = tracker.transferred();
+        let _ = app_handle.emit(
+            "sftp-upload-progress",
+            progress(tracker.transferred(), tracker.bytes_per_sec(), tracker.eta_secs(), false, None),
+        );
+    })
+    .await;
+
+    match &result {
+        Ok(sent) => {
+            if let Some(local) = &local_path {
+                let _ = clear_interrupted_transfer(
+                    TransferDirection::Upload, &config.host, config.port, local, remote_path,
+                );
+            }
+            let _ = app_handle.emit("sftp-upload-progress", progress(*sent, 0, Some(0), true, None));
+        }
+        Err(e) => {
+            // Only a partial remote file can be resumed, and a corrupted
+            // upload must start over
+            let resumable = (started || offset > 0) && !e.starts_with(CHECKSUM_MISMATCH);
+            if let Some(local) = local_path.filter(|_| resumable) {
+                let _ = record_interrupted_transfer(interrupted(
+                    TransferDirection::Upload, session_id, config, local, remote_path,
+                    total_bytes, last_sent, e,
+                ));
+            }
+            let _ = app_handle.emit("sftp-upload-progress", progress(last_sent, 0, None, true, Some(e.clone())));
+        }
+    }
+
+    result.map(|_| ())
+}
+
+/// Download a file via SFTP to a local path, emitting `sftp-download-progress` events.
+///
+/// Failed downloads are recorded so they can be resumed later.
+pub async fn sftp_download_file(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    remote_path: &str,
+    local_path: &Path,
+    options: TransferOptions,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    let local_display = local_path.to_string_lossy().to_string();
+
+    let progress = |bytes_received: u64, total_bytes: u64, bytes_per_sec: u64, eta_secs: Option<u64>, done: bool, error: Option<String>| {
+        SftpDownloadProgress {
+            session_id: session_id.to_string(),
+            remote_path: remote_path.to_string(),
+            local_path: local_display.clone(),
+            bytes_received,
+            total_bytes,
+            bytes_per_sec,
+            eta_secs,
+            done,
+            error,
+        }
+    };
+
+    let offset = if options.resume {
+        download_resume_offset(pool, session_id, config, remote_path, local_path, options.verify_resume).await?
+    } else {
+        0
+    };
+
+    let control = TransferControl::new();
+    let mut last_received = offset;
+    let mut last_total = 0;
+    let mut started = false;
+    let result = download_to_local(pool, session_id, config, remote_path, local_path, offset, options.verify, &control, |tracker| {
+        started = true;
+        last_received = tracker.transferred();
+        last_total = tracker.total_bytes();
+        let _ = app_handle.emit(
+            "sftp-download-progress",
+            progress(
+                tracker.transferred(),
+                tracker.total_bytes(),
+                tracker.bytes_per_sec(),
+                tracker.eta_secs(),
+                false,
+                None,
+            ),
+        );
+    })
+    .await;
+
+    match &result {
+        Ok(received) => {
+            let _ = clear_interrupted_transfer(
+                TransferDirection::Download, &config.host, config.port, &local_display, remote_path,
+            );
+            let _ = app_handle.emit(
+                "sftp-download-progress",
+                progress(*received, *received, 0, Some(0), true, None),
+            );
+        }
+        Err(e) => {
+            // Nothing to resume when no data was written
+            if (started || offset > 0) && !e.starts_with(CHECKSUM_MISMATCH) {
+                let _ = record_interrupted_transfer(interrupted(
+                    TransferDirection::Download, session_id, config, local_display.clone(), remote_path,
+                    last_total, last_received, e,
+                ));
+            }
+            let _ = app_handle.emit(
+                "sftp-download-progress",
+                progress(last_received, last_total, 0, None, true, Some(e.clone())),
+            );
+        }
+    }
+
+    result.map(|_| ())
+}
+
+/// Build the record persisted for a failed transfer
+fn interrupted(
+    direction: TransferDirection,
+    session_id: &str,
+    config: &SshConfig,
+    local_path: String,
+    remote_path: &str,
+    total_bytes: u64,
+    transferred_bytes: u64,
+    error: &str,
+) -> InterruptedTransfer {
+    InterruptedTransfer {
+        id: uuid::Uuid::new_v4().to_string(),
+        direction,
+        session_id: session_id.to_string(),
+        host: config.host.clone(),
+        port: config.port,
+        username: config.username.clone(),
+        local_path,
+        remote_path: remote_path.to_string(),
+        total_bytes,
+        transferred_bytes,
+        error: Some(error.to_string()),
+        interrupted_at: chrono::Utc::now().timestamp(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::SshAuth;
+
+    fn check(source: (&str, &str), dest: (&str, &str)) -> Result<(), String> {
+        let config = SshConfig {
+            host: "example.com".to_string(),
+            port: 22,
+            username: "user".to_string(),
+            auth: SshAuth::Password(String::new()),
+            jump_host: None,
+        };
+        check_copy_target(
+            &RemoteEndpoint { session_id: source.0, config: &config, path: source.1 },
+            &RemoteEndpoint { session_id: dest.0, config: &config, path: dest.1 },
+        )
+    }
+
+    #[test]
+    fn test_refuses_copies_into_the_source() {
+        assert!(check(("a", "/srv/www"), ("a", "/srv/www")).is_err());
+        assert!(check(("a", "/srv/www/"), ("a", "/srv//www/./")).is_err());
+        assert!(check(("a", "/srv/www"), ("a", "/srv/www/backup/www")).is_err());
+        assert!(check(("a", "/srv/www"), ("a", "/srv/tmp/../www/www")).is_err());
+        assert!(check(("a", "/srv/www"), ("a", "/srv/www-backup")).is_ok());
+        assert!(check(("a", "/srv/www/site"), ("a", "/srv/www")).is_ok());
+        assert!(check(("a", "/srv/www"), ("b", "/srv/www")).is_ok());
+    }
+}
@@ -0,0 +1,577 @@
+//! Directory synchronisation between a local and a remote directory
+//!
+//! `plan_sync` scans both trees and returns the list of actions (upload,
+//! download, delete) without changing anything, so it can be reviewed.
+//! `execute_sync` then applies a plan, possibly with some actions removed.
+//!
+//! Files are compared by size and modification time, or by SHA-256 when
+//! `compare_content` is set (remote hashes come from `sha256sum` over SSH).
+//! Synced files always get the source mtime so the next comparison is stable.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use super::control::TransferControl;
+use super::filter::PathFilter;
+use super::recursive::{
+    apply_local_attrs, apply_remote_attrs, ensure_remote_dir, is_contained, remote_is_dir, scan_local, scan_remote,
+    DirectoryTransferOptions, ScannedEntry, TransferIssue,
+};
+use super::stream::{download_to_local, upload_to_remote, UploadSource};
+use crate::connectors::sftp::join_remote_path;
+use crate::connectors::ssh_exec::{shell_quote, ssh_exec};
+use crate::connectors::{sftp_delete, SftpPool, SshConfig};
+
+/// Modification times closer than this are considered equal (FAT has 2s resolution)
+const MTIME_TOLERANCE_SECS: i64 = 2;
+
+/// Number of paths passed to a single `sha256sum` invocation
+const HASH_BATCH_SIZE: usize = 200;
+
+/// Direction of a sync
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncMode {
+    /// Local -> remote
+    Push,
+    /// Remote -> local
+    Pull,
+    /// Both ways, the newer file wins
+    TwoWay,
+}
+
+/// Options for planning a sync
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOptions {
+    pub mode: SyncMode,
+    #[serde(flatten)]
+    pub filter: PathFilter,
+    /// Compare file contents (SHA-256) instead of size/mtime
+    #[serde(default)]
+    pub compare_content: bool,
+    /// One-way modes only: delete destination files missing from the source
+    #[serde(default)]
+    pub delete_extraneous: bool,
+}
+
+/// What to do with a single path
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncActionKind {
+    Upload,
+    Download,
+    DeleteLocal,
+    DeleteRemote,
+    /// Both sides changed and neither is newer; left untouched
+    Conflict,
+}
+
+/// A planned action, relative to the sync roots
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncAction {
+    pub kind: SyncActionKind,
+    pub path: String,
+    /// Bytes to transfer (0 for deletes and conflicts)
+    pub size: u64,
+    /// Human-readable reason ("new", "size differs", ...)
+    pub reason: String,
+    /// Source modification time, applied to the destination
+    #[serde(default)]
+    pub mtime: Option<u32>,
+}
+
+/// Reviewable sync plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+    pub id: String,
+    pub session_id: String,
+    pub local_root: String,
+    pub remote_root: String,
+    pub mode: SyncMode,
+    pub actions: Vec<SyncAction>,
+    /// Entries ignored while scanning (filters, symlinks, ...)
+    #[serde(default, skip_deserializing)]
+    pub skipped: Vec<TransferIssue>,
+}
+
+/// Outcome of executing a plan
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncSummary {
+    pub uploaded: u32,
+    pub downloaded: u32,
+    pub deleted: u32,
+    pub conflicts: u32,
+    pub bytes_transferred: u64,
+    pub failed: Vec<TransferIssue>,
+}
+
+/// Progress event emitted while executing a plan
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncProgress {
+    pub plan_id: String,
+    pub session_id: String,
+    pub current_path: String,
+    pub actions_done: u32,
+    pub actions_total: u32,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub bytes_per_sec: u64,
+    pub done: bool,
+}
+
+/// Content hashes of both sides, keyed by relative path
+#[derive(Debug, Default)]
+struct Hashes {
+    local: HashMap<String, String>,
+    remote: HashMap<String, String>,
+}
+
+impl Hashes {
+    /// None when either hash is unknown
+    fn same(&self, path: &str) -> Option<bool> {
+        Some(self.local.get(path)? == self.remote.get(path)?)
+    }
+}
+
+// ============================================================================
+// Planning
+// ============================================================================
+
+/// Scan both directories and work out what needs to change
+pub async fn plan_sync(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    local_root: &Path,
+    remote_root: &str,
+    options: &SyncOptions,
+) -> Result<SyncPlan, String> {
+    let local_scan = scan_local(local_root, &options.filter)?;
+    // A missing remote directory is created when the plan is executed
+    let mut remote_scan = if remote_is_dir(pool, session_id, config, remote_root).await? {
+        scan_remote(pool, session_id, config, remote_root, &options.filter).await?
+    } else {
+        Default::default()
+    };
+
+    // A hostile server could name entries to escape `local_root`
+    let (entries, unsafe_entries): (Vec<_>, Vec<_>) = std::mem::take(&mut remote_scan.entries)
+        .into_iter()
+        .partition(|e| is_contained(&e.relative));
+    remote_scan.entries = entries;
+    for entry in unsafe_entries {
+        remote_scan.skipped.push(TransferIssue { path: entry.relative, reason: "Unsafe path".to_string() });
+    }
+
+    let local = files_by_path(&local_scan.entries);
+    let remote = files_by_path(&remote_scan.entries);
+
+    let hashes = if options.compare_content {
+        // Only files present on both sides with the same size need hashing
+        let candidates: Vec<String> = local
+            .iter()
+            .filter(|(path, l)| remote.get(*path).map_or(false, |r| r.size == l.size))
+            .map(|(path, _)| path.to_string())
+            .collect();
+        Hashes {
+            local: hash_local_files(local_root, &candidates),
+            remote: hash_remote_files(config, remote_root, &candidates).await?,
+        }
+    } else {
+        Hashes::default()
+    };
+
+    let mut skipped = local_scan.skipped;
+    skipped.extend(remote_scan.skipped);
+
+    Ok(SyncPlan {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.to_string(),
+        local_root: local_root.to_string_lossy().to_string(),
+        remote_root: remote_root.to_string(),
+        mode: options.mode,
+        actions: diff_trees(&local, &remote, options, &hashes),
+        skipped,
+    })
+}
+
+fn files_by_path(entries: &[ScannedEntry]) -> HashMap<&str, &ScannedEntry> {
+    entries
+        .iter()
+        .filter(|e| !e.is_dir)
+        .map(|e| (e.relative.as_str(), e))
+        .collect()
+}
+
+/// Why two files differ, or None if they are considered identical
+fn difference(path: &str, local: &ScannedEntry, remote: &ScannedEntry, hashes: &Hashes, compare_content: bool) -> Option<&'static str> {
+    if local.size != remote.size {
+        return Some("size differs");
+    }
+    if compare_content {
+        return match hashes.same(path) {
+            Some(true) => None,
+            Some(false) => Some("content differs"),
+            None => Some("content unknown"),
+        };
+    }
+    match (local.mtime, remote.mtime) {
+        (Some(l), Some(r)) if (l as i64 - r as i64).abs() > MTIME_TOLERANCE_SECS => Some("modified"),
+        _ => None,
+    }
+}
+
+fn transfer_action(kind: SyncActionKind, path: &str, source: &ScannedEntry, reason: &str) -> SyncAction {
+    SyncAction {
+        kind,
+        path: path.to_string(),
+        size: source.size,
+        reason: reason.to_string(),
+        mtime: source.mtime,
+    }
+}
+
+fn delete_action(kind: SyncActionKind, path: &str) -> SyncAction {
+    SyncAction {
+        kind,
+        path: path.to_string(),
+        size: 0,
+        reason: "not in source".to_string(),
+        mtime: None,
+    }
+}
+
+/// Compare two file listings and produce the actions for `options.mode`
+fn diff_trees(
+    local: &HashMap<&str, &ScannedEntry>,
+    remote: &HashMap<&str, &ScannedEntry>,
+    options: &SyncOptions,
+    hashes: &Hashes,
+) -> Vec<SyncAction> {
+    let paths: BTreeSet<&str> = local.keys().chain(remote.keys()).copied().collect();
+    let mut actions = Vec::new();
+
+    for path in paths {
+        let action = match (local.get(path), remote.get(path), options.mode) {
+            (Some(l), None, SyncMode::Push | SyncMode::TwoWay) => {
+                Some(transfer_action(SyncActionKind::Upload, path, l, "new"))
+            }
+            (None, Some(r), SyncMode::Pull | SyncMode::TwoWay) => {
+                Some(transfer_action(SyncActionKind::Download, path, r, "new"))
+            }
+            (Some(_), None, SyncMode::Pull) if options.delete_extraneous => {
+                Some(delete_action(SyncActionKind::DeleteLocal, path))
+            }
+            (None, Some(_), SyncMode::Push) if options.delete_extraneous => {
+                Some(delete_action(SyncActionKind::DeleteRemote, path))
+            }
+            (Some(l), Some(r), mode) => {
+                difference(path, l, r, hashes, options.compare_content).and_then(|reason| match mode {
+                    SyncMode::Push => Some(transfer_action(SyncActionKind::Upload, path, l, reason)),
+                    SyncMode::Pull => Some(transfer_action(SyncActionKind::Download, path, r, reason)),
+                    SyncMode::TwoWay => Some(match l.mtime.cmp(&r.mtime) {
+                        std::cmp::Ordering::Greater => transfer_action(SyncActionKind::Upload, path, l, reason),
+                        std::cmp::Ordering::Less => transfer_action(SyncActionKind::Download, path, r, reason),
+                        std::cmp::Ordering::Equal => SyncAction {
+                            kind: SyncActionKind::Conflict,
+                            path: path.to_string(),
+                            size: 0,
+                            reason: reason.to_string(),
+                            mtime: None,
+                        },
+                    }),
+                })
+            }
+            _ => None,
+        };
+        actions.extend(action);
+    }
+
+    actions
+}
+
+// ============================================================================
+// Hashing
+// ============================================================================
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; super::stream::CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Hash local files; unreadable files are left out (and compare as changed)
+fn hash_local_files(root: &Path, paths: &[String]) -> HashMap<String, String> {
+    paths
+        .iter()
+        .filter_map(|p| sha256_file(&root.join(p)).ok().map(|h| (p.clone(), h)))
+        .collect()
+}
+
+/// Hash remote files with `sha256sum`, in batches
+async fn hash_remote_files(config: &SshConfig, root: &str, paths: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut hashes = HashMap::new();
+    for batch in paths.chunks(HASH_BATCH_SIZE) {
+        let args: Vec<String> = batch.iter().map(|p| shell_quote(p)).collect();
+        let command = format!("cd {} && sha256sum -- {} 2>/dev/null", shell_quote(root), args.join(" "));
+        // sha256sum exits non-zero if a file is unreadable; parse whatever it printed
+        let output = ssh_exec(config, &command).await?;
+        hashes.extend(parse_sha256sum(&output));
+    }
+    Ok(hashes)
+}
+
+/// Parse `sha256sum` output ("<hash>  <path>" per line)
+fn parse_sha256sum(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        // Lines starting with '\' have escaped file names; leave those unknown
+        .filter(|line| !line.starts_with('\\'))
+        .filter_map(|line| {
+            let (hash, path) = line.split_once("  ").or_else(|| line.split_once(" *"))?;
+            (hash.len() == 64).then(|| (path.to_string(), hash.to_lowercase()))
+        })
+        .collect()
+}
+
+// ============================================================================
+// Execution
+// ============================================================================
+
+/// Apply a plan, emitting `sftp-sync-progress` events
+pub async fn execute_sync(
+    pool: &SftpPool,
+    config: &SshConfig,
+    plan: &SyncPlan,
+    app: &AppHandle,
+) -> Result<SyncSummary, String> {
+    let session_id = plan.session_id.as_str();
+    let local_root = PathBuf::from(&plan.local_root);
+    let attrs = DirectoryTransferOptions {
+        preserve_times: true,
+        ..Default::default()
+    };
+    let control = TransferControl::new();
+
+    let mut summary = SyncSummary::default();
+    let actions_total = plan.actions.len() as u32;
+    let bytes_total: u64 = plan.actions.iter().map(|a| a.size).sum();
+    let mut actions_done = 0;
+    let mut bytes_done = 0;
+
+    let emit = |current_path: &str, actions_done: u32, bytes_done: u64, bytes_per_sec: u64, done: bool| {
+        let _ = app.emit("sftp-sync-progress", SyncProgress {
+            plan_id: plan.id.clone(),
+            session_id: session_id.to_string(),
+            current_path: current_path.to_string(),
+            actions_done,
+            actions_total,
+            bytes_done,
+            bytes_total,
+            bytes_per_sec,
+            done,
+        });
+    };
+
+    if plan.actions.iter().any(|a| a.kind == SyncActionKind::Upload) {
+        ensure_remote_dir(pool, session_id, config, &plan.remote_root).await?;
+    }
+
+    for action in &plan.actions {
+        emit(&action.path, actions_done, bytes_done, 0, false);
+
+        // Plans come back from the frontend: don't follow paths out of either root
+        if !is_contained(&action.path) {
+            summary.failed.push(TransferIssue { path: action.path.clone(), reason: "Unsafe path".to_string() });
+            actions_done += 1;
+            bytes_done += action.size;
+            continue;
+        }
+
+        let local_path = local_root.join(&action.path);
+        let remote_path = join_remote_path(&plan.remote_root, &action.path);
+        let entry = ScannedEntry {
+            relative: action.path.clone(),
+            is_dir: false,
+            size: action.size,
+            permissions: None,
+            mtime: action.mtime,
+        };
+
+        let result = match action.kind {
+            SyncActionKind::Upload => {
+                let result = async {
+                    ensure_remote_parents(pool, session_id, config, &plan.remote_root, &action.path).await?;
+                    let bytes = upload_to_remote(
+                        pool, session_id, config, UploadSource::Path(local_path.clone()), &remote_path, 0, false, &control,
+                        |t| emit(&action.path, actions_done, bytes_done + t.transferred(), t.bytes_per_sec(), false),
+                    )
+                    .await?;
+                    apply_remote_attrs(pool, session_id, config, &remote_path, &entry, &attrs).await?;
+                    Ok::<u64, String>(bytes)
+                }
+                .await;
+                result.map(|bytes| {
+                    summary.uploaded += 1;
+                    summary.bytes_transferred += bytes;
+                })
+            }
+            SyncActionKind::Download => {
+                let result = async {
+                    if let Some(parent) = local_path.parent() {
+                        std::fs::create_dir_all(parent)
+                            .map_err(|e| format!("Failed to create local directory: {}", e))?;
+                    }
+                    let bytes = download_to_local(
+                        pool, session_id, config, &remote_path, &local_path, 0, false, &control,
+                        |t| emit(&action.path, actions_done, bytes_done + t.transferred(), t.bytes_per_sec(), false),
+                    )
+                    .await?;
+                    apply_local_attrs(&local_path, &entry, &attrs)?;
+                    Ok::<u64, String>(bytes)
+                }
+                .await;
+                result.map(|bytes| {
+                    summary.downloaded += 1;
+                    summary.bytes_transferred += bytes;
+                })
+            }
+            SyncActionKind::DeleteLocal => std::fs::remove_file(&local_path)
+                .map(|_| summary.deleted += 1)
+                .map_err(|e| format!("Failed to delete local file: {}", e)),
+            SyncActionKind::DeleteRemote => sftp_delete(pool, session_id, config, &remote_path, false)
+                .await
+                .map(|_| summary.deleted += 1),
+            SyncActionKind::Conflict => {
+                summary.conflicts += 1;
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result {
+            summary.failed.push(TransferIssue { path: action.path.clone(), reason: e });
+        }
+        actions_done += 1;
+        bytes_done += action.size;
+    }
+
+    emit("", actions_done, bytes_done, 0, true);
+    Ok(summary)
+}
+
+/// Create the remote directories leading to `relative` under `root`
+async fn ensure_remote_parents(
+    pool: &SftpPool,
+    session_id: &str,
+    config: &SshConfig,
+    root: &str,
+    relative: &str,
+) -> Result<(), String> {
+    let mut dir = root.to_string();
+    let components: Vec<&str> = relative.split('/').collect();
+    for component in &components[..components.len().saturating_sub(1)] {
+        dir = join_remote_path(&dir, component);
+        ensure_remote_dir(pool, session_id, config, &dir).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(relative: &str, size: u64, mtime: u32) -> ScannedEntry {
+        ScannedEntry {
+            relative: relative.to_string(),
+            is_dir: false,
+            size,
+            permissions: None,
+            mtime: Some(mtime),
+        }
+    }
+
+    fn options(mode: SyncMode, delete_extraneous: bool) -> SyncOptions {
+        SyncOptions {
+            mode,
+            filter: PathFilter::default(),
+            compare_content: false,
+            delete_extraneous,
+        }
+    }
+
+    fn plan(local: &[ScannedEntry], remote: &[ScannedEntry], options: &SyncOptions) -> Vec<(SyncActionKind, String)> {
+        diff_trees(&files_by_path(local), &files_by_path(remote), options, &Hashes::default())
+            .into_iter()
+            .map(|a| (a.kind, a.path))
+            .collect()
+    }
+
+    #[test]
+    fn test_push_uploads_new_and_changed() {
+        let local = [file("a", 10, 100), file("b", 10, 100), file("c", 5, 500)];
+        let remote = [file("b", 10, 101), file("c", 5, 100), file("d", 1, 100)];
+        assert_eq!(
+            plan(&local, &remote, &options(SyncMode::Push, false)),
+            vec![(SyncActionKind::Upload, "a".to_string()), (SyncActionKind::Upload, "c".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_push_deletes_extraneous_when_asked() {
+        let local = [file("a", 10, 100)];
+        let remote = [file("a", 10, 100), file("old", 1, 100)];
+        assert_eq!(
+            plan(&local, &remote, &options(SyncMode::Push, true)),
+            vec![(SyncActionKind::DeleteRemote, "old".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_two_way_newer_wins() {
+        let local = [file("a", 10, 200), file("b", 10, 100), file("c", 1, 100)];
+        let remote = [file("a", 12, 100), file("b", 12, 200), file("d", 1, 100)];
+        assert_eq!(
+            plan(&local, &remote, &options(SyncMode::TwoWay, true)),
+            vec![
+                (SyncActionKind::Upload, "a".to_string()),
+                (SyncActionKind::Download, "b".to_string()),
+                (SyncActionKind::Upload, "c".to_string()),
+                (SyncActionKind::Download, "d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_two_way_same_mtime_is_conflict() {
+        let local = [file("a", 10, 100)];
+        let remote = [file("a", 11, 100)];
+        assert_eq!(
+            plan(&local, &remote, &options(SyncMode::TwoWay, false)),
+            vec![(SyncActionKind::Conflict, "a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_sha256sum() {
+        let hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let output = format!("{}  dir/file.txt\n\\{}  we\\nird\n", hash, hash);
+        let parsed = parse_sha256sum(&output);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed.get("dir/file.txt").map(String::as_str), Some(hash));
+    }
+}